@@ -1,15 +1,47 @@
 use anyhow::Result;
-use ratatui::Frame;
-use std::path::PathBuf;
+use ratatui::{layout::Rect, Frame};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
 use std::thread;
 
-use crate::models::{CleanableItem, DuplicateGroup, LargeFileItem, SystemStats, TreeMapItem};
+use crate::models::{
+    self, CleanableItem, CleanupCategory, DuplicateGroup, LargeFileItem, SecurityThreat, SystemStats,
+    ThreatType, TreeMapItem,
+};
+use crate::operations::compress::gzip_file;
+use crate::operations::delete::{delete_path, move_path, run_remove_command};
+use crate::operations::trash::move_to_trash;
 use crate::scanner::cleanup::CleanupScanner;
+use crate::scanner::content::ContentBreakdown;
 use crate::scanner::duplicates::DuplicateScanner;
+use crate::scanner::errors::{ScanError, ScanErrors};
+use crate::scanner::inspect::{inspect, FileInspection};
 use crate::scanner::large_files::LargeFileScanner;
+use crate::scanner::logs;
+use crate::scanner::pattern::Pattern;
 use crate::scanner::treemap::TreeMapScanner;
+use crate::security;
+use crate::security::allowlist::{Allowlist, AllowlistEntry};
+use crate::security::ioc::IocList;
+use crate::security::permissions;
+use crate::security::quarantine::Quarantine;
+use crate::security::sanitizer::PathSanitizer;
+use crate::security::signatures::SignatureDatabase;
+use crate::security::updates::SignatureStore;
+use crate::system::privileges::is_permission_denied;
 use crate::system::stats::get_system_stats;
+use crate::ui::widgets::image::GraphicsProtocol;
+
+/// Projects with no commits or edits for this long count as stale
+const STALE_PROJECT_DAYS: i64 = 90;
+
+/// Entries in the home menu
+const MENU_ITEMS: usize = 3;
+
+/// A background scan's result, with the paths it had to skip
+type ScanOutcome<T> = (std::result::Result<T, String>, Vec<ScanError>);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Screen {
@@ -23,6 +55,43 @@ pub enum Screen {
     Help,
 }
 
+/// File action waiting for confirmation on the TreeMap screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeMapAction {
+    Delete,
+    Trash,
+    Move,
+}
+
+impl TreeMapAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Delete => "Delete",
+            Self::Trash => "Move to Trash",
+            Self::Move => "Move",
+        }
+    }
+}
+
+/// How long a suppression made on the Security Scan screen lasts, cycled
+/// with Tab
+pub const SUPPRESS_EXPIRIES: &[(&str, Option<i64>)] = &[
+    ("never", None),
+    ("30 days", Some(30)),
+    ("90 days", Some(90)),
+    ("1 year", Some(365)),
+];
+
+/// A suppression being filled in on the Security Scan screen
+#[derive(Debug, Clone, Default)]
+pub struct SuppressDraft {
+    pub reason: String,
+    /// Index into `SUPPRESS_EXPIRIES`
+    pub expiry: usize,
+    /// Keyed by the file's contents rather than the signature that matched
+    pub by_content: bool,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SortOrder {
     None,           // Original scan order
@@ -43,21 +112,44 @@ pub struct App {
     // Storage cleanup state
     pub cleanable_items: Vec<CleanableItem>,
     pub selected_index: usize,
+    /// Cleanup tree nodes showing their children
+    pub cleanup_expanded: HashSet<PathBuf>,
     pub scanning: bool,
     pub deleting: bool,
     pub scan_progress: f64,
     pub spinner_state: usize,
     pub needs_scan: bool,
     pub sort_order: SortOrder,
-    scan_receiver: Option<Receiver<Vec<CleanableItem>>>,
+    /// Paths the last cleanup scan had to skip
+    pub cleanup_scan_errors: Vec<ScanError>,
+    scan_receiver: Option<Receiver<ScanOutcome<Vec<CleanableItem>>>>,
 
     // TreeMap state
     pub treemap_root: Option<TreeMapItem>,
+    /// The tree came from an ncdu dump, possibly of another machine, so its
+    /// paths and sizes aren't this machine's
+    pub treemap_imported: bool,
     pub treemap_scanning: bool,
     pub treemap_selected_index: usize,
     pub treemap_path_stack: Vec<PathBuf>,
     pub treemap_show_preview: bool,
-    treemap_receiver: Option<Receiver<TreeMapItem>>,
+    pub treemap_pending_action: Option<TreeMapAction>,
+    pub treemap_action_targets: Vec<PathBuf>,
+    pub treemap_move_destination: String,
+    /// Content breakdown computed on demand for one directory
+    pub treemap_breakdown: Option<(PathBuf, ContentBreakdown)>,
+    treemap_breakdown_pending: Option<PathBuf>,
+    treemap_breakdown_receiver: Option<Receiver<(PathBuf, ContentBreakdown)>>,
+    /// Preview data for the highlighted file, gathered in the background
+    pub treemap_inspection: Option<FileInspection>,
+    treemap_inspection_pending: Option<PathBuf>,
+    treemap_inspection_receiver: Option<Receiver<FileInspection>>,
+    /// Where the last frame left room for an overlay image
+    pub treemap_image_area: Option<Rect>,
+    pub graphics_protocol: GraphicsProtocol,
+    /// Paths the last TreeMap scan had to skip
+    pub treemap_scan_errors: Vec<ScanError>,
+    treemap_receiver: Option<Receiver<ScanOutcome<TreeMapItem>>>,
 
     // Duplicate Finder state
     pub duplicate_groups: Vec<DuplicateGroup>,
@@ -74,6 +166,35 @@ pub struct App {
     pub large_files_min_age: u64,     // Minimum age filter (days)
     large_files_receiver: Option<Receiver<Vec<LargeFileItem>>>,
 
+    // Security Scan state
+    /// Threats from the last scan, most severe first
+    pub security_threats: Vec<SecurityThreat>,
+    pub security_scanning: bool,
+    pub security_selected_index: usize,
+    /// Signatures the threats were matched against, for their details
+    pub security_database: Option<Arc<SignatureDatabase>>,
+    /// Quarantining the highlighted threat waits for confirmation
+    pub security_confirm_quarantine: bool,
+    /// Some while the suppression prompt for the highlighted threat is open
+    pub security_suppress: Option<SuppressDraft>,
+    /// Saved suppressions, while their panel is open
+    pub security_allowlist: Option<Vec<AllowlistEntry>>,
+    pub security_allowlist_index: usize,
+    /// Paths the last security scan had to skip
+    pub security_scan_errors: Vec<ScanError>,
+    security_receiver: Option<Receiver<ScanOutcome<Vec<SecurityThreat>>>>,
+
+    // Search state (shared by the list screens)
+    pub search_input: Option<String>, // Some while the `/` prompt is open
+    pub search_pattern: Option<Pattern>,
+    pub search_query: String,
+    pub treemap_search_matches: Vec<PathBuf>,
+    pub treemap_search_index: usize,
+
+    // Scan error drill-down, for the current screen's scan
+    pub scan_errors_open: bool,
+    pub scan_errors_index: usize,
+
     // UI state
     pub status_message: Option<String>,
     pub error_message: Option<String>,
@@ -100,18 +221,33 @@ impl App {
             menu_index: 0,
             cleanable_items: Vec::new(),
             selected_index: 0,
+            cleanup_expanded: HashSet::new(),
             scanning: false,
             deleting: false,
             scan_progress: 0.0,
             spinner_state: 0,
             needs_scan: false,
             sort_order: SortOrder::None,
+            cleanup_scan_errors: Vec::new(),
             scan_receiver: None,
             treemap_root: None,
             treemap_scanning: false,
             treemap_selected_index: 0,
             treemap_path_stack: Vec::new(),
             treemap_show_preview: true,
+            treemap_pending_action: None,
+            treemap_action_targets: Vec::new(),
+            treemap_move_destination: String::new(),
+            treemap_breakdown: None,
+            treemap_breakdown_pending: None,
+            treemap_breakdown_receiver: None,
+            treemap_imported: false,
+            treemap_inspection: None,
+            treemap_inspection_pending: None,
+            treemap_inspection_receiver: None,
+            treemap_image_area: None,
+            graphics_protocol: GraphicsProtocol::HalfBlock,
+            treemap_scan_errors: Vec::new(),
             treemap_receiver: None,
             duplicate_groups: Vec::new(),
             duplicate_scanning: false,
@@ -124,6 +260,23 @@ impl App {
             large_files_min_size: 1024 * 1024 * 100, // 100 MB default
             large_files_min_age: 0, // No age filter by default
             large_files_receiver: None,
+            security_threats: Vec::new(),
+            security_scanning: false,
+            security_selected_index: 0,
+            security_database: None,
+            security_confirm_quarantine: false,
+            security_suppress: None,
+            security_allowlist: None,
+            security_allowlist_index: 0,
+            security_scan_errors: Vec::new(),
+            security_receiver: None,
+            search_input: None,
+            search_pattern: None,
+            search_query: String::new(),
+            treemap_search_matches: Vec::new(),
+            treemap_search_index: 0,
+            scan_errors_open: false,
+            scan_errors_index: 0,
             status_message: None,
             error_message: None,
             number_buffer: String::new(),
@@ -139,25 +292,80 @@ impl App {
 
         // Check for cleanup scan results
         if let Some(receiver) = &self.scan_receiver {
-            if let Ok(items) = receiver.try_recv() {
-                self.cleanable_items = items;
-                self.selected_index = 0; // Reset selection to first item
+            if let Ok((result, errors)) = receiver.try_recv() {
                 self.scanning = false;
                 self.scan_receiver = None;
-                self.status_message = Some(format!("Found {} items", self.cleanable_items.len()));
+                self.cleanup_scan_errors = errors;
+                match result {
+                    Ok(items) => {
+                        self.cleanable_items = items;
+                        self.selected_index = 0; // Reset selection to first item
+                        self.cleanup_expanded.clear();
+                        self.status_message = Some(format!("Found {} items", self.cleanable_items.len()));
+                    }
+                    Err(err) => self.error_message = Some(format!("Scan failed: {}", err)),
+                }
             }
         }
 
         // Check for treemap scan results
         if let Some(receiver) = &self.treemap_receiver {
-            if let Ok(root) = receiver.try_recv() {
-                self.treemap_root = Some(root);
+            if let Ok((result, errors)) = receiver.try_recv() {
                 self.treemap_scanning = false;
                 self.treemap_receiver = None;
-                self.status_message = Some("Scan complete".to_string());
+                self.treemap_scan_errors = errors;
+                match result {
+                    Ok(root) => {
+                        self.treemap_root = Some(root);
+                        self.treemap_imported = false;
+                        self.treemap_reset_breakdown();
+                        self.status_message = Some("Scan complete".to_string());
+                    }
+                    Err(err) => self.error_message = Some(format!("Scan failed: {}", err)),
+                }
+            }
+        }
+
+        // Check for security scan results
+        if let Some(receiver) = &self.security_receiver {
+            if let Ok((result, errors)) = receiver.try_recv() {
+                self.security_scanning = false;
+                self.security_receiver = None;
+                self.security_scan_errors = errors;
+                match result {
+                    Ok(threats) => {
+                        self.security_threats = threats;
+                        self.security_selected_index = 0;
+                        self.status_message = Some(if self.security_threats.is_empty() {
+                            "No known threats found".to_string()
+                        } else {
+                            format!("Found {} threats", self.security_threats.len())
+                        });
+                    }
+                    Err(err) => self.error_message = Some(format!("Scan failed: {}", err)),
+                }
+            }
+        }
+
+        // Check for content breakdown results
+        if let Some(receiver) = &self.treemap_breakdown_receiver {
+            if let Ok(breakdown) = receiver.try_recv() {
+                self.treemap_breakdown = Some(breakdown);
+                self.treemap_breakdown_pending = None;
+                self.treemap_breakdown_receiver = None;
             }
         }
 
+        // Check for file inspection results, then inspect a newly highlighted file
+        if let Some(receiver) = &self.treemap_inspection_receiver {
+            if let Ok(inspection) = receiver.try_recv() {
+                self.treemap_inspection = Some(inspection);
+                self.treemap_inspection_pending = None;
+                self.treemap_inspection_receiver = None;
+            }
+        }
+        self.start_treemap_inspection();
+
         // Check for duplicate scan results
         if let Some(receiver) = &self.duplicate_receiver {
             if let Ok(groups) = receiver.try_recv() {
@@ -201,15 +409,47 @@ impl App {
         let (tx, rx) = channel();
         self.scan_receiver = Some(rx);
 
+        // A custom scan path narrows the project artifact search
+        let project_root = self.custom_scan_path.clone();
+
         thread::spawn(move || {
-            let scanner = CleanupScanner::new();
-            if let Ok(items) = scanner.scan_all() {
-                let _ = tx.send(items);
-            }
+            let errors = ScanErrors::new();
+            let mut scanner = CleanupScanner::new().with_errors(errors.clone());
+            if let Some(root) = project_root {
+                scanner = scanner.with_project_roots(vec![root]);
+            }
+            // Always answer, so a failed scan doesn't spin forever
+            let result = scanner.scan_all().map_err(|e| format!("{:#}", e));
+            let _ = tx.send((result, errors.take()));
         });
     }
 
 
+    /// Paths skipped by the scan shown on the current screen
+    pub fn current_scan_errors(&self) -> &[ScanError] {
+        match self.current_screen {
+            Screen::StorageCleanup => &self.cleanup_scan_errors,
+            Screen::DiskTreeMap => &self.treemap_scan_errors,
+            Screen::SecurityScan => &self.security_scan_errors,
+            _ => &[],
+        }
+    }
+
+    /// Open or close the list of skipped paths
+    pub fn toggle_scan_errors(&mut self) {
+        if self.scan_errors_open || self.current_scan_errors().is_empty() {
+            self.scan_errors_open = false;
+        } else {
+            self.scan_errors_open = true;
+            self.scan_errors_index = 0;
+        }
+    }
+
+    pub fn scan_errors_step(&mut self, delta: isize) {
+        let last = self.current_scan_errors().len().saturating_sub(1);
+        self.scan_errors_index = self.scan_errors_index.saturating_add_signed(delta).min(last);
+    }
+
     pub fn get_spinner(&self) -> &str {
         const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
         SPINNER_FRAMES[self.spinner_state % SPINNER_FRAMES.len()]
@@ -220,10 +460,14 @@ impl App {
 
         let area = frame.size();
 
+        self.treemap_image_area = None;
+
         match self.current_screen {
             Screen::Home => screens::home::render(frame, self, area),
             Screen::StorageCleanup => screens::cleanup::render(frame, self, area),
-            Screen::DiskTreeMap => screens::treemap::render(frame, self, area),
+            Screen::DiskTreeMap => {
+                self.treemap_image_area = screens::treemap::render(frame, self, area);
+            }
             Screen::DuplicateFinder => screens::duplicates::render(frame, self, area),
             Screen::LargeFiles => screens::large_files::render(frame, self, area),
             Screen::Performance => screens::performance::render(frame, self, area),
@@ -237,17 +481,18 @@ impl App {
         let new_screen = match number {
             1 => Screen::StorageCleanup,
             2 => Screen::DiskTreeMap,
+            3 => Screen::SecurityScan,
             // Commented out - not yet available
-            // 3 => Screen::DuplicateFinder,
-            // 4 => Screen::LargeFiles,
-            // 5 => Screen::Performance,
-            // 6 => Screen::SecurityScan,
+            // 4 => Screen::DuplicateFinder,
+            // 5 => Screen::LargeFiles,
+            // 6 => Screen::Performance,
             _ => return,
         };
 
         self.previous_screen = Some(self.current_screen);
         self.current_screen = new_screen;
         self.number_buffer.clear();
+        self.search_clear();
 
         // Start async scanning for relevant screens
         match new_screen {
@@ -261,6 +506,9 @@ impl App {
                     self.start_treemap_scan();
                 }
             }
+            Screen::SecurityScan if self.security_database.is_none() && !self.security_scanning => {
+                self.start_security_scan();
+            }
             // Commented out - not yet available
             // Screen::DuplicateFinder => {
             //     if self.duplicate_groups.is_empty() && !self.duplicate_scanning {
@@ -278,6 +526,7 @@ impl App {
 
     pub fn go_back(&mut self) {
         self.number_buffer.clear();
+        self.search_clear();
         if let Some(prev) = self.previous_screen {
             self.current_screen = prev;
             self.previous_screen = None;
@@ -288,6 +537,7 @@ impl App {
 
     pub fn go_home(&mut self) {
         self.number_buffer.clear();
+        self.search_clear();
         self.current_screen = Screen::Home;
         self.previous_screen = None;
         // Clear treemap navigation stack when going home
@@ -301,17 +551,18 @@ impl App {
 
     // List navigation
     pub fn move_up(&mut self) {
+        if self.move_in_search_results(-1) {
+            return;
+        }
+
         match self.current_screen {
             Screen::Home => {
                 if self.menu_index > 0 {
                     self.menu_index -= 1;
                 }
             }
-            Screen::StorageCleanup => {
-                if self.selected_index > 0 {
-                    self.selected_index -= 1;
-                }
-            }
+            Screen::StorageCleanup => self.cleanup_step(-1),
+            Screen::SecurityScan => self.security_step(-1),
             Screen::LargeFiles => {
                 if self.large_files_selected_index > 0 {
                     self.large_files_selected_index -= 1;
@@ -324,8 +575,10 @@ impl App {
     pub fn jump_to_item(&mut self, number: usize) {
         match self.current_screen {
             Screen::StorageCleanup => {
-                if number > 0 && number <= self.cleanable_items.len() {
-                    self.selected_index = number - 1; // Convert to 0-based index
+                // Numbers refer to rows as shown, collapsed nodes hide theirs
+                let visible = self.visible_cleanup_indices();
+                if number > 0 && number <= visible.len() {
+                    self.selected_index = visible[number - 1]; // Convert to 0-based index
                 }
             }
             _ => {}
@@ -382,10 +635,10 @@ impl App {
                 // For now, we'll just not sort
             }
             SortOrder::SizeDesc => {
-                self.cleanable_items.sort_by(|a, b| b.size.cmp(&a.size));
+                models::sort_tree(&mut self.cleanable_items, |a, b| b.size.cmp(&a.size));
             }
             SortOrder::SizeAsc => {
-                self.cleanable_items.sort_by(|a, b| a.size.cmp(&b.size));
+                models::sort_tree(&mut self.cleanable_items, |a, b| a.size.cmp(&b.size));
             }
         }
 
@@ -396,18 +649,14 @@ impl App {
     }
 
     pub fn move_down(&mut self) {
+        if self.move_in_search_results(1) {
+            return;
+        }
+
         match self.current_screen {
-            Screen::Home => {
-                if self.menu_index < 1 {
-                    // 2 menu items (0-1)
-                    self.menu_index += 1;
-                }
-            }
-            Screen::StorageCleanup => {
-                if !self.cleanable_items.is_empty() && self.selected_index < self.cleanable_items.len() - 1 {
-                    self.selected_index += 1;
-                }
-            }
+            Screen::Home if self.menu_index < MENU_ITEMS - 1 => self.menu_index += 1,
+            Screen::StorageCleanup => self.cleanup_step(1),
+            Screen::SecurityScan => self.security_step(1),
             Screen::LargeFiles => {
                 if !self.large_files.is_empty() && self.large_files_selected_index < self.large_files.len() - 1 {
                     self.large_files_selected_index += 1;
@@ -419,17 +668,16 @@ impl App {
 
     // Fast navigation - jump by 10 items
     pub fn page_up(&mut self) {
+        if self.move_in_search_results(-10) {
+            return;
+        }
+
         match self.current_screen {
             Screen::Home => {
                 self.menu_index = 0;
             }
-            Screen::StorageCleanup => {
-                if self.selected_index >= 10 {
-                    self.selected_index -= 10;
-                } else {
-                    self.selected_index = 0;
-                }
-            }
+            Screen::StorageCleanup => self.cleanup_step(-10),
+            Screen::SecurityScan => self.security_step(-10),
             Screen::DiskTreeMap => {
                 if self.treemap_selected_index >= 10 {
                     self.treemap_selected_index -= 10;
@@ -449,16 +697,16 @@ impl App {
     }
 
     pub fn page_down(&mut self) {
+        if self.move_in_search_results(10) {
+            return;
+        }
+
         match self.current_screen {
             Screen::Home => {
-                self.menu_index = 5;
-            }
-            Screen::StorageCleanup => {
-                if !self.cleanable_items.is_empty() {
-                    let new_index = self.selected_index + 10;
-                    self.selected_index = new_index.min(self.cleanable_items.len() - 1);
-                }
+                self.menu_index = MENU_ITEMS - 1;
             }
+            Screen::StorageCleanup => self.cleanup_step(10),
+            Screen::SecurityScan => self.security_step(10),
             Screen::DiskTreeMap => {
                 let items = self.get_current_treemap_items();
                 if !items.is_empty() {
@@ -478,17 +726,16 @@ impl App {
 
     // Medium navigation - jump by 5 items (vim-style Ctrl+U/Ctrl+D)
     pub fn jump_up(&mut self) {
+        if self.move_in_search_results(-5) {
+            return;
+        }
+
         match self.current_screen {
             Screen::Home => {
                 self.menu_index = 0;
             }
-            Screen::StorageCleanup => {
-                if self.selected_index >= 5 {
-                    self.selected_index -= 5;
-                } else {
-                    self.selected_index = 0;
-                }
-            }
+            Screen::StorageCleanup => self.cleanup_step(-5),
+            Screen::SecurityScan => self.security_step(-5),
             Screen::DiskTreeMap => {
                 if self.treemap_selected_index >= 5 {
                     self.treemap_selected_index -= 5;
@@ -508,16 +755,16 @@ impl App {
     }
 
     pub fn jump_down(&mut self) {
+        if self.move_in_search_results(5) {
+            return;
+        }
+
         match self.current_screen {
             Screen::Home => {
-                self.menu_index = 5;
-            }
-            Screen::StorageCleanup => {
-                if !self.cleanable_items.is_empty() {
-                    let new_index = self.selected_index + 5;
-                    self.selected_index = new_index.min(self.cleanable_items.len() - 1);
-                }
+                self.menu_index = MENU_ITEMS - 1;
             }
+            Screen::StorageCleanup => self.cleanup_step(5),
+            Screen::SecurityScan => self.security_step(5),
             Screen::DiskTreeMap => {
                 let items = self.get_current_treemap_items();
                 if !items.is_empty() {
@@ -536,19 +783,81 @@ impl App {
     }
 
     pub fn move_left(&mut self) {
-        // Future use for tab navigation
+        if self.current_screen == Screen::StorageCleanup {
+            self.cleanup_collapse();
+        }
     }
 
     pub fn move_right(&mut self) {
-        // Future use for tab navigation
+        if self.current_screen == Screen::StorageCleanup {
+            self.cleanup_expand();
+        }
+    }
+
+    /// Move the cleanup cursor by `delta` rows among the visible ones
+    fn cleanup_step(&mut self, delta: isize) {
+        let visible = self.visible_cleanup_indices();
+        if visible.is_empty() {
+            return;
+        }
+        let pos = visible.iter().position(|&i| i == self.selected_index).unwrap_or(0) as isize;
+        let next = (pos + delta).clamp(0, visible.len() as isize - 1);
+        self.selected_index = visible[next as usize];
+    }
+
+    /// Show the children of the current cleanup node
+    pub fn cleanup_expand(&mut self) {
+        let index = self.selected_index;
+        if index < self.cleanable_items.len() && models::has_children(&self.cleanable_items, index) {
+            self.cleanup_expanded.insert(self.cleanable_items[index].path.clone());
+        }
+    }
+
+    /// Hide the children of the current node, or jump to its parent if
+    /// it has none showing
+    pub fn cleanup_collapse(&mut self) {
+        let index = self.selected_index;
+        let item = match self.cleanable_items.get(index) {
+            Some(item) => item,
+            None => return,
+        };
+        if !self.cleanup_expanded.remove(&item.path) {
+            if let Some(&parent) = models::ancestors(&self.cleanable_items, index).first() {
+                self.selected_index = parent;
+            }
+        }
+    }
+
+    /// Select or deselect a cleanup item together with everything under it.
+    /// Deselecting also clears its ancestors, since a selected parent means
+    /// all of its children.
+    fn cleanup_set_selected(&mut self, index: usize, selected: bool) {
+        let end = models::subtree_end(&self.cleanable_items, index);
+        for item in &mut self.cleanable_items[index..end] {
+            item.selected = selected;
+        }
+        if !selected {
+            for ancestor in models::ancestors(&self.cleanable_items, index) {
+                self.cleanable_items[ancestor].selected = false;
+            }
+        }
     }
 
     // Selection
     pub fn toggle_selection(&mut self) {
         match self.current_screen {
             Screen::StorageCleanup => {
-                if let Some(item) = self.cleanable_items.get_mut(self.selected_index) {
-                    item.selected = !item.selected;
+                if let Some(item) = self.cleanable_items.get(self.selected_index) {
+                    let selected = !item.selected;
+                    self.cleanup_set_selected(self.selected_index, selected);
+                }
+            }
+            Screen::DiskTreeMap => {
+                let path = self.get_selected_treemap_item().map(|i| i.path.clone());
+                if let (Some(root), Some(path)) = (self.treemap_root.as_mut(), path) {
+                    if let Some(item) = root.find_mut(&path) {
+                        item.selected = !item.selected;
+                    }
                 }
             }
             Screen::DuplicateFinder => {
@@ -566,22 +875,51 @@ impl App {
     pub fn select_all(&mut self) {
         match self.current_screen {
             Screen::StorageCleanup => {
-                for item in &mut self.cleanable_items {
-                    item.selected = true;
+                let pattern = self.search_pattern.clone();
+                for index in 0..self.cleanable_items.len() {
+                    if pattern.as_ref().is_none_or(|p| p.matches_path(&self.cleanable_items[index].path)) {
+                        self.cleanup_set_selected(index, true);
+                    }
                 }
             }
+            Screen::DiskTreeMap => self.treemap_set_selection(true),
             Screen::DuplicateFinder => {
                 self.duplicate_select_all_but_newest();
             }
             Screen::LargeFiles => {
+                let pattern = self.search_pattern.clone();
                 for item in &mut self.large_files {
-                    item.selected = true;
+                    if pattern.as_ref().is_none_or(|p| p.matches_path(&item.path)) {
+                        item.selected = true;
+                    }
                 }
             }
             _ => {}
         }
     }
 
+    /// Select project artifacts whose project hasn't been touched in a while
+    pub fn select_stale_artifacts(&mut self) {
+        let cutoff = chrono::Local::now() - chrono::Duration::days(STALE_PROJECT_DAYS);
+        let pattern = self.search_pattern.clone();
+        let mut count = 0;
+
+        for item in &mut self.cleanable_items {
+            if item.category == CleanupCategory::ProjectArtifacts
+                && item.modified < cutoff
+                && pattern.as_ref().is_none_or(|p| p.matches_path(&item.path))
+            {
+                item.selected = true;
+                count += 1;
+            }
+        }
+
+        self.status_message = Some(format!(
+            "Selected {} artifacts from projects idle for {}+ days",
+            count, STALE_PROJECT_DAYS
+        ));
+    }
+
     pub fn select_none(&mut self) {
         match self.current_screen {
             Screen::StorageCleanup => {
@@ -589,6 +927,7 @@ impl App {
                     item.selected = false;
                 }
             }
+            Screen::DiskTreeMap => self.treemap_set_selection(false),
             Screen::DuplicateFinder => {
                 self.duplicate_select_none();
             }
@@ -659,7 +998,9 @@ impl App {
             _ => return Ok(()),
         }
 
-        let selected_count = self.cleanable_items.iter().filter(|i| i.selected).count();
+        // Deleting a directory covers everything listed under it
+        let roots = models::selected_roots(&self.cleanable_items);
+        let selected_count = roots.len();
 
         if selected_count == 0 {
             self.error_message = Some("No items selected".to_string());
@@ -683,43 +1024,61 @@ impl App {
         } else {
             // Actually delete files from disk
             let mut deleted_count = 0;
+            let mut deleted_size = 0;
             let mut failed_count = 0;
-
-            for item in self.cleanable_items.iter().filter(|i| i.selected) {
-                let result = if item.path.is_dir() {
-                    std::fs::remove_dir_all(&item.path)
-                } else {
-                    std::fs::remove_file(&item.path)
+            // Left for `surge clean --system`; the TUI never runs as root
+            let mut privileged_count = 0;
+
+            // Back to front, so removing a subtree leaves earlier indices valid
+            for &index in roots.iter().rev() {
+                let item = &self.cleanable_items[index];
+                if item.needs_privileges {
+                    privileged_count += 1;
+                    continue;
+                }
+                // Package caches and the like are cleared by their own tools
+                let result = match &item.remove_command {
+                    Some(command) => run_remove_command(command),
+                    None => delete_path(&item.path),
                 };
 
                 match result {
-                    Ok(_) => deleted_count += 1,
+                    Ok(_) => {
+                        deleted_count += 1;
+                        deleted_size += item.size;
+                        // Drop it from the list and from its parents' totals
+                        models::remove_subtree(&mut self.cleanable_items, index);
+                    }
+                    Err(err) if is_permission_denied(&err) => {
+                        self.cleanable_items[index].needs_privileges = true;
+                        privileged_count += 1;
+                    }
                     Err(_) => failed_count += 1,
                 }
             }
 
-            // Remove deleted items from the list
-            self.cleanable_items.retain(|item| !item.selected);
-
             // Reset selection index if needed
             if self.selected_index >= self.cleanable_items.len() && !self.cleanable_items.is_empty() {
                 self.selected_index = self.cleanable_items.len() - 1;
             }
+            // The row may have disappeared under a collapsed parent
+            self.search_select_first_visible();
 
+            let mut message = format!(
+                "✓ Deleted {} items ({})",
+                deleted_count,
+                humansize::format_size(deleted_size, humansize::BINARY)
+            );
             if failed_count > 0 {
-                self.status_message = Some(format!(
-                    "✓ Deleted {} items ({}) - {} failed",
-                    deleted_count,
-                    humansize::format_size(total_size, humansize::BINARY),
-                    failed_count
-                ));
-            } else {
-                self.status_message = Some(format!(
-                    "✓ Deleted {} items ({})",
-                    deleted_count,
-                    humansize::format_size(total_size, humansize::BINARY)
+                message.push_str(&format!(" - {} failed", failed_count));
+            }
+            if privileged_count > 0 {
+                message.push_str(&format!(
+                    " - {} need elevated privileges, run `surge clean --system`",
+                    privileged_count
                 ));
             }
+            self.status_message = Some(message);
 
             // Clear deleting flag
             self.deleting = false;
@@ -728,14 +1087,76 @@ impl App {
         Ok(())
     }
 
-    pub fn get_selected_size(&self) -> u64 {
-        self.cleanable_items
+    /// Gzip the selected uncompressed rotated logs instead of deleting them
+    pub fn compress_selected_logs(&mut self) {
+        let targets: Vec<usize> = self
+            .cleanable_items
             .iter()
-            .filter(|i| i.selected)
-            .map(|i| i.size)
+            .enumerate()
+            .filter(|(_, i)| {
+                i.selected
+                    && i.category == CleanupCategory::Logs
+                    && i.remove_command.is_none()
+                    && !logs::is_compressed(&i.path.to_string_lossy())
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        if targets.is_empty() {
+            self.error_message = Some("No uncompressed rotated logs selected".to_string());
+            return;
+        }
+
+        let before: u64 = targets.iter().map(|&i| self.cleanable_items[i].size).sum();
+        if self.preview_mode {
+            self.status_message = Some(format!(
+                "Preview mode: Would compress {} logs ({})",
+                targets.len(),
+                humansize::format_size(before, humansize::BINARY)
+            ));
+            return;
+        }
+
+        let mut after = 0u64;
+        let mut failures = Vec::new();
+        for index in targets.iter().copied() {
+            let item = &mut self.cleanable_items[index];
+            match gzip_file(&item.path) {
+                Ok(compressed) => {
+                    item.size = std::fs::metadata(&compressed).map(|m| m.len()).unwrap_or(0);
+                    item.path = compressed;
+                    item.selected = false;
+                    item.note = Some(logs::rotated_note(&item.modified, true));
+                    after += item.size;
+                }
+                Err(e) => {
+                    after += item.size;
+                    failures.push(format!("{}: {}", item.path.display(), e));
+                }
+            }
+        }
+
+        self.status_message = Some(format!(
+            "✓ Compressed {} logs, saved {}",
+            targets.len() - failures.len(),
+            humansize::format_size(before.saturating_sub(after), humansize::BINARY)
+        ));
+        self.error_message = (!failures.is_empty())
+            .then(|| format!("{} failed: {}", failures.len(), failures.join("; ")));
+    }
+
+    pub fn get_selected_size(&self) -> u64 {
+        models::selected_roots(&self.cleanable_items)
+            .into_iter()
+            .map(|i| self.cleanable_items[i].size)
             .sum()
     }
 
+    /// Number of selected cleanup items, counting a selected directory once
+    pub fn cleanup_selected_count(&self) -> usize {
+        models::selected_roots(&self.cleanable_items).len()
+    }
+
     // TreeMap methods
     pub fn start_treemap_scan(&mut self) {
         self.treemap_scanning = true;
@@ -749,13 +1170,25 @@ impl App {
         self.treemap_receiver = Some(rx);
 
         thread::spawn(move || {
-            let scanner = TreeMapScanner::new();
-            if let Ok(root) = scanner.scan(&scan_path) {
-                let _ = tx.send(root);
-            }
+            let errors = ScanErrors::new();
+            let scanner = TreeMapScanner::new().with_errors(errors.clone());
+            let result = scanner.scan(&scan_path).map_err(|e| format!("{:#}", e));
+            let _ = tx.send((result, errors.take()));
         });
     }
 
+    /// Show an already-built tree (e.g. an imported ncdu dump) instead of scanning
+    pub fn load_treemap(&mut self, root: TreeMapItem) {
+        self.status_message = Some(format!("Loaded {}", root.path.display()));
+        self.treemap_root = Some(root);
+        self.treemap_imported = true;
+        self.treemap_scanning = false;
+        self.treemap_receiver = None;
+        self.treemap_selected_index = 0;
+        self.treemap_path_stack.clear();
+        self.treemap_reset_breakdown();
+    }
+
     pub fn get_current_treemap_items(&self) -> Vec<&TreeMapItem> {
         if let Some(root) = &self.treemap_root {
             // Navigate to current directory based on path stack
@@ -767,7 +1200,12 @@ impl App {
                     return Vec::new();
                 }
             }
-            current.children.iter().collect()
+            // An active search filters the listing down to matching entries
+            current
+                .children
+                .iter()
+                .filter(|c| self.search_pattern.as_ref().is_none_or(|p| p.matches_path(&c.path)))
+                .collect()
         } else {
             Vec::new()
         }
@@ -808,6 +1246,308 @@ impl App {
         self.treemap_show_preview = !self.treemap_show_preview;
     }
 
+    /// Inspect the highlighted file in the background when the preview needs it
+    fn start_treemap_inspection(&mut self) {
+        // An imported tree's paths aren't this machine's files
+        if self.current_screen != Screen::DiskTreeMap || !self.treemap_show_preview || self.treemap_imported {
+            return;
+        }
+
+        let path = match self.get_selected_treemap_item() {
+            Some(item) if item.is_file => item.path.clone(),
+            _ => return,
+        };
+        let done = self.treemap_inspection.as_ref().is_some_and(|i| i.path == path);
+        if done || self.treemap_inspection_pending.as_ref() == Some(&path) {
+            return;
+        }
+
+        let (tx, rx) = channel();
+        self.treemap_inspection_receiver = Some(rx);
+        self.treemap_inspection_pending = Some(path.clone());
+
+        thread::spawn(move || {
+            let _ = tx.send(inspect(&path));
+        });
+    }
+
+    /// The image an overlay graphics protocol should draw after this frame
+    pub fn treemap_image(&self) -> Option<(&Path, &image::DynamicImage, Rect)> {
+        let area = self.treemap_image_area?;
+        let inspection = self.treemap_inspection.as_ref()?;
+        Some((&inspection.path, inspection.thumbnail.as_ref()?, area))
+    }
+
+    /// Directory a breakdown applies to: the highlighted directory, or the
+    /// current one when a file is highlighted
+    fn treemap_breakdown_target(&self) -> Option<PathBuf> {
+        match self.get_selected_treemap_item() {
+            Some(item) if !item.is_file => Some(item.path.clone()),
+            _ => self.treemap_current_dir(),
+        }
+    }
+
+    /// Compute (or hide) the content breakdown for the highlighted directory.
+    /// Sniffing files can take a while, so it runs in the background.
+    pub fn treemap_toggle_breakdown(&mut self) {
+        let target = match self.treemap_breakdown_target() {
+            Some(target) => target,
+            None => return,
+        };
+
+        if self.treemap_visible_breakdown().is_some() {
+            self.treemap_breakdown = None;
+            return;
+        }
+        if self.treemap_breakdown_pending.as_ref() == Some(&target) {
+            return;
+        }
+
+        let (root, item) = match self
+            .treemap_root
+            .as_ref()
+            .and_then(|root| Some((root.path.clone(), root.find(&target)?.clone())))
+        {
+            Some(found) => found,
+            None => return,
+        };
+        let read_files = !self.treemap_imported;
+
+        let (tx, rx) = channel();
+        self.treemap_breakdown_receiver = Some(rx);
+        self.treemap_breakdown_pending = Some(target.clone());
+        self.treemap_show_preview = true;
+
+        thread::spawn(move || {
+            let _ = tx.send((target, ContentBreakdown::from_tree(&item, &root, read_files)));
+        });
+    }
+
+    /// Whether the breakdown for what's currently highlighted is still running
+    pub fn treemap_breakdown_computing(&self) -> bool {
+        self.treemap_breakdown_pending.is_some()
+            && self.treemap_breakdown_pending == self.treemap_breakdown_target()
+    }
+
+    /// Drop the breakdown and any result still in flight for an older tree
+    fn treemap_reset_breakdown(&mut self) {
+        self.treemap_breakdown = None;
+        self.treemap_breakdown_pending = None;
+        self.treemap_breakdown_receiver = None;
+    }
+
+    /// The cached breakdown, if it belongs to what's currently highlighted
+    pub fn treemap_visible_breakdown(&self) -> Option<&(PathBuf, ContentBreakdown)> {
+        let target = self.treemap_breakdown_target()?;
+        self.treemap_breakdown
+            .as_ref()
+            .filter(|(path, _)| *path == target)
+    }
+
+    /// Path of the directory currently shown in the TreeMap
+    fn treemap_current_dir(&self) -> Option<PathBuf> {
+        self.treemap_path_stack
+            .last()
+            .cloned()
+            .or_else(|| self.treemap_root.as_ref().map(|r| r.path.clone()))
+    }
+
+    /// Select or deselect everything in the current TreeMap directory
+    fn treemap_set_selection(&mut self, selected: bool) {
+        let dir = self.treemap_current_dir();
+        if let (Some(root), Some(dir)) = (self.treemap_root.as_mut(), dir) {
+            if let Some(current) = root.find_mut(&dir) {
+                let pattern = self.search_pattern.as_ref();
+                for child in &mut current.children {
+                    if pattern.is_none_or(|p| p.matches_path(&child.path)) {
+                        child.selected = selected;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn get_treemap_selected_size(&self) -> u64 {
+        self.treemap_action_targets
+            .iter()
+            .filter_map(|p| self.treemap_root.as_ref().and_then(|r| r.find(p)))
+            .map(|i| i.size)
+            .sum()
+    }
+
+    /// Ask for confirmation before deleting, trashing or moving the selected items
+    /// (or the highlighted item when nothing is selected)
+    pub fn treemap_request_action(&mut self, action: TreeMapAction) {
+        // Same-named local paths aren't what the dump measured
+        if self.treemap_imported {
+            self.error_message = Some(format!(
+                "{} is disabled for an imported tree; rescan this machine to act on its files",
+                action.name()
+            ));
+            return;
+        }
+
+        let mut targets = self
+            .treemap_root
+            .as_ref()
+            .map(|r| r.selected_paths())
+            .unwrap_or_default();
+
+        if targets.is_empty() {
+            if let Some(item) = self.get_selected_treemap_item() {
+                targets.push(item.path.clone());
+            }
+        }
+
+        if targets.is_empty() {
+            self.error_message = Some("No items selected".to_string());
+            return;
+        }
+
+        self.error_message = None;
+        self.treemap_action_targets = targets;
+        self.treemap_pending_action = Some(action);
+        self.treemap_move_destination = self
+            .treemap_current_dir()
+            .map(|p| format!("{}/", p.display()))
+            .unwrap_or_default();
+    }
+
+    pub fn treemap_cancel_action(&mut self) {
+        self.treemap_pending_action = None;
+        self.treemap_action_targets.clear();
+        self.treemap_move_destination.clear();
+    }
+
+    /// Run the confirmed TreeMap action and update the tree in place
+    pub fn treemap_confirm_action(&mut self) {
+        let action = match self.treemap_pending_action.take() {
+            Some(action) => action,
+            None => return,
+        };
+        let targets = std::mem::take(&mut self.treemap_action_targets);
+        let destination = expand_home(self.treemap_move_destination.trim());
+        self.treemap_move_destination.clear();
+
+        // Permanent deletion keeps age protection; trash and move are reversible
+        let sanitizer = match action {
+            TreeMapAction::Delete => PathSanitizer::default(),
+            TreeMapAction::Trash | TreeMapAction::Move => {
+                PathSanitizer::default().without_age_protection()
+            }
+        };
+        // Only what's inside the scanned tree, even below /home
+        let sanitizer = match &self.treemap_root {
+            Some(root) => sanitizer.with_allowed_root(&root.path),
+            None => sanitizer,
+        };
+        // Moved items may land anywhere in the user's own home, nowhere blacklisted
+        let destination_sanitizer = PathSanitizer::default()
+            .without_age_protection()
+            .with_allowed_root(Path::new("~"));
+
+        let mut done_count = 0;
+        let mut done_size = 0u64;
+        let mut failures = Vec::new();
+
+        for target in &targets {
+            if let Err(e) = sanitizer.sanitize_path(target) {
+                failures.push(e.to_string());
+                continue;
+            }
+            if action == TreeMapAction::Move {
+                let landing = destination.join(target.file_name().unwrap_or_default());
+                if let Err(e) = destination_sanitizer.sanitize_path(&landing) {
+                    failures.push(e.to_string());
+                    continue;
+                }
+            }
+
+            let size = self
+                .treemap_root
+                .as_ref()
+                .and_then(|r| r.find(target))
+                .map(|i| i.size)
+                .unwrap_or(0);
+
+            if self.preview_mode {
+                done_count += 1;
+                done_size += size;
+                continue;
+            }
+
+            let result = match action {
+                TreeMapAction::Delete => delete_path(target).map(|_| None),
+                TreeMapAction::Trash => move_to_trash(target).map(|_| None),
+                TreeMapAction::Move => move_path(target, &destination).map(Some),
+            };
+
+            match result {
+                Ok(new_path) => {
+                    done_count += 1;
+                    done_size += size;
+                    if let Some(root) = self.treemap_root.as_mut() {
+                        if let Some(mut item) = root.remove(target) {
+                            // Keep moved items visible if they landed inside the scanned tree
+                            if let Some(parent) = new_path.as_ref().and_then(|p| p.parent()) {
+                                item.clear_selection();
+                                root.insert(parent, item);
+                            }
+                        }
+                    }
+                }
+                Err(e) => failures.push(format!("{}: {}", target.display(), e)),
+            }
+        }
+
+        self.treemap_fix_navigation();
+        if done_count > 0 && !self.preview_mode {
+            self.treemap_reset_breakdown();
+        }
+
+        let verb = match (action, self.preview_mode) {
+            (TreeMapAction::Delete, false) => "Deleted",
+            (TreeMapAction::Trash, false) => "Trashed",
+            (TreeMapAction::Move, false) => "Moved",
+            (TreeMapAction::Delete, true) => "Preview mode: Would delete",
+            (TreeMapAction::Trash, true) => "Preview mode: Would trash",
+            (TreeMapAction::Move, true) => "Preview mode: Would move",
+        };
+        let summary = format!(
+            "✓ {} {} items ({})",
+            verb,
+            done_count,
+            humansize::format_size(done_size, humansize::BINARY)
+        );
+
+        if failures.is_empty() {
+            self.status_message = Some(summary);
+            self.error_message = None;
+        } else {
+            self.status_message = Some(format!("{} - {} failed", summary, failures.len()));
+            self.error_message = Some(failures[0].clone());
+        }
+    }
+
+    /// Keep the path stack and selection valid after items left the tree
+    fn treemap_fix_navigation(&mut self) {
+        if let Some(root) = &self.treemap_root {
+            self.treemap_search_matches.retain(|m| root.find(m).is_some());
+
+            while let Some(last) = self.treemap_path_stack.last() {
+                if root.find(last).is_some() {
+                    break;
+                }
+                self.treemap_path_stack.pop();
+            }
+        }
+
+        let count = self.get_current_treemap_items().len();
+        if self.treemap_selected_index >= count {
+            self.treemap_selected_index = count.saturating_sub(1);
+        }
+    }
+
     pub fn get_selected_treemap_item(&self) -> Option<&TreeMapItem> {
         let items = self.get_current_treemap_items();
         items.get(self.treemap_selected_index).copied()
@@ -816,21 +1556,586 @@ impl App {
     pub fn treemap_open_file(&mut self) {
         if let Some(item) = self.get_selected_treemap_item() {
             if item.is_file {
-                let path = &item.path;
-                // Open file with system's default application
-                #[cfg(target_os = "macos")]
-                {
-                    let _ = std::process::Command::new("open")
-                        .arg(path)
-                        .spawn();
+                open_path(&item.path);
+                self.status_message = Some(format!("Opening: {}", item.name));
+            }
+        }
+    }
+
+    // Search methods
+    pub fn search_start(&mut self) {
+        if matches!(
+            self.current_screen,
+            Screen::StorageCleanup | Screen::DiskTreeMap | Screen::DuplicateFinder | Screen::LargeFiles
+        ) {
+            self.search_input = Some(self.search_query.clone());
+        }
+    }
+
+    pub fn search_push(&mut self, c: char) {
+        if let Some(input) = self.search_input.as_mut() {
+            input.push(c);
+        }
+        self.search_update();
+    }
+
+    pub fn search_pop(&mut self) {
+        if let Some(input) = self.search_input.as_mut() {
+            input.pop();
+        }
+        self.search_update();
+    }
+
+    /// Re-filter as the query is typed (incremental search)
+    fn search_update(&mut self) {
+        let query = self.search_input.clone().unwrap_or_default();
+        self.search_query = query.clone();
+
+        if query.is_empty() {
+            self.search_pattern = None;
+            self.error_message = None;
+        } else {
+            match Pattern::parse(&query) {
+                Ok(pattern) => {
+                    self.search_pattern = Some(pattern);
+                    self.error_message = None;
                 }
-                #[cfg(target_os = "linux")]
-                {
-                    let _ = std::process::Command::new("xdg-open")
-                        .arg(path)
-                        .spawn();
+                // Keep the last valid filter while a regex is half-typed
+                Err(e) => self.error_message = Some(e.to_string()),
+            }
+        }
+
+        self.search_select_first_visible();
+    }
+
+    /// Close the prompt, keeping the filter active
+    pub fn search_commit(&mut self) {
+        self.search_input = None;
+        if self.search_pattern.is_none() {
+            return;
+        }
+
+        if self.current_screen == Screen::DiskTreeMap {
+            // Search the whole scanned tree, not just the current directory
+            self.treemap_search_matches = match (&self.treemap_root, &self.search_pattern) {
+                (Some(root), Some(pattern)) => collect_tree_matches(root, pattern),
+                _ => Vec::new(),
+            };
+            self.treemap_search_index = 0;
+
+            if self.get_current_treemap_items().is_empty() && !self.treemap_search_matches.is_empty() {
+                self.treemap_jump_to_match(0);
+            }
+        }
+
+        self.status_message = Some(format!("{} matches", self.search_match_count()));
+    }
+
+    /// Close the prompt and drop the filter
+    pub fn search_cancel(&mut self) {
+        self.search_clear();
+        self.error_message = None;
+    }
+
+    pub fn search_clear(&mut self) {
+        let had_filter = self.search_pattern.is_some();
+
+        self.search_input = None;
+        self.search_pattern = None;
+        self.search_query.clear();
+        self.treemap_search_matches.clear();
+        self.treemap_search_index = 0;
+
+        // Keep the highlighted TreeMap entry selected once the full listing returns
+        if had_filter && self.current_screen == Screen::DiskTreeMap {
+            let path = self.get_selected_treemap_item().map(|i| i.path.clone());
+            let items = self.get_current_treemap_items();
+            self.treemap_selected_index = path
+                .and_then(|p| items.iter().position(|i| i.path == p))
+                .unwrap_or(0);
+        }
+    }
+
+    pub fn search_is_active(&self) -> bool {
+        self.search_pattern.is_some()
+    }
+
+    pub fn search_match_count(&self) -> usize {
+        match self.current_screen {
+            Screen::StorageCleanup => self.visible_cleanup_indices().len(),
+            Screen::DiskTreeMap => {
+                if self.search_input.is_some() || self.treemap_search_matches.is_empty() {
+                    self.get_current_treemap_items().len()
+                } else {
+                    self.treemap_search_matches.len()
+                }
+            }
+            Screen::DuplicateFinder => self.visible_duplicate_groups().len(),
+            Screen::LargeFiles => self.visible_large_file_indices().len(),
+            _ => 0,
+        }
+    }
+
+    /// Jump to the next (`n`) or previous (`N`) match
+    pub fn search_next(&mut self, forward: bool) {
+        match self.current_screen {
+            Screen::DiskTreeMap => {
+                let count = self.treemap_search_matches.len();
+                if count == 0 {
+                    return;
+                }
+                let index = if forward {
+                    (self.treemap_search_index + 1) % count
+                } else {
+                    (self.treemap_search_index + count - 1) % count
+                };
+                self.treemap_jump_to_match(index);
+            }
+            Screen::DuplicateFinder => self.duplicate_step_group(forward),
+            _ => {
+                self.move_in_search_results(if forward { 1 } else { -1 });
+            }
+        }
+    }
+
+    /// Open the directory containing a whole-tree match and highlight it
+    fn treemap_jump_to_match(&mut self, index: usize) {
+        let (root_path, target) = match (&self.treemap_root, self.treemap_search_matches.get(index)) {
+            (Some(root), Some(target)) => (root.path.clone(), target.clone()),
+            _ => return,
+        };
+
+        let mut stack: Vec<PathBuf> = target
+            .ancestors()
+            .skip(1)
+            .take_while(|a| *a != root_path)
+            .map(|a| a.to_path_buf())
+            .collect();
+        stack.reverse();
+
+        self.treemap_path_stack = stack;
+        self.treemap_search_index = index;
+        self.treemap_selected_index = self
+            .get_current_treemap_items()
+            .iter()
+            .position(|i| i.path == target)
+            .unwrap_or(0);
+        self.status_message = Some(format!(
+            "Match {}/{}: {}",
+            index + 1,
+            self.treemap_search_matches.len(),
+            target.display()
+        ));
+    }
+
+    /// Cleanup rows as shown: everything matching a search, otherwise the
+    /// items whose ancestors are all expanded
+    pub fn visible_cleanup_indices(&self) -> Vec<usize> {
+        if let Some(pattern) = &self.search_pattern {
+            return self
+                .cleanable_items
+                .iter()
+                .enumerate()
+                .filter(|(_, i)| pattern.matches_path(&i.path))
+                .map(|(idx, _)| idx)
+                .collect();
+        }
+
+        let mut visible = Vec::new();
+        let mut index = 0;
+        while index < self.cleanable_items.len() {
+            visible.push(index);
+            if self.cleanup_expanded.contains(&self.cleanable_items[index].path) {
+                index += 1;
+            } else {
+                index = models::subtree_end(&self.cleanable_items, index);
+            }
+        }
+        visible
+    }
+
+    pub fn visible_large_file_indices(&self) -> Vec<usize> {
+        self.large_files
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| self.search_pattern.as_ref().is_none_or(|p| p.matches_path(&f.path)))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Duplicate groups with at least one file matching the search
+    pub fn visible_duplicate_groups(&self) -> Vec<usize> {
+        self.duplicate_groups
+            .iter()
+            .enumerate()
+            .filter(|(_, g)| {
+                self.search_pattern
+                    .as_ref()
+                    .is_none_or(|p| g.files.iter().any(|f| p.matches_path(&f.path)))
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Move the selection by `delta` matches on filtered list screens.
+    /// Returns false when no filter applies and normal navigation should run.
+    fn move_in_search_results(&mut self, delta: isize) -> bool {
+        if self.search_pattern.is_none() {
+            return false;
+        }
+
+        let (visible, current) = match self.current_screen {
+            Screen::StorageCleanup => (self.visible_cleanup_indices(), self.selected_index),
+            Screen::LargeFiles => (self.visible_large_file_indices(), self.large_files_selected_index),
+            _ => return false,
+        };
+
+        if let Some(index) = step_visible(&visible, current, delta) {
+            match self.current_screen {
+                Screen::StorageCleanup => self.selected_index = index,
+                _ => self.large_files_selected_index = index,
+            }
+        }
+        true
+    }
+
+    /// Make sure the selection sits on a visible entry after the filter changed
+    fn search_select_first_visible(&mut self) {
+        match self.current_screen {
+            Screen::StorageCleanup => {
+                let visible = self.visible_cleanup_indices();
+                if !visible.contains(&self.selected_index) {
+                    self.selected_index = visible.first().copied().unwrap_or(0);
+                }
+            }
+            Screen::LargeFiles => {
+                let visible = self.visible_large_file_indices();
+                if !visible.contains(&self.large_files_selected_index) {
+                    self.large_files_selected_index = visible.first().copied().unwrap_or(0);
+                }
+            }
+            Screen::DuplicateFinder => {
+                let visible = self.visible_duplicate_groups();
+                if !visible.contains(&self.duplicate_selected_group) {
+                    self.duplicate_selected_group = visible.first().copied().unwrap_or(0);
+                    self.duplicate_selected_file = 0;
+                }
+            }
+            Screen::DiskTreeMap => self.treemap_selected_index = 0,
+            _ => {}
+        }
+    }
+
+    fn duplicate_step_group(&mut self, forward: bool) {
+        let visible = self.visible_duplicate_groups();
+        let delta = if forward { 1 } else { -1 };
+        if let Some(group) = step_visible(&visible, self.duplicate_selected_group, delta) {
+            self.duplicate_selected_group = group;
+            self.duplicate_selected_file = 0;
+        }
+    }
+
+    // Security Scan methods
+
+    /// Run the security checks in the background
+    pub fn start_security_scan(&mut self) {
+        let database = match &self.security_database {
+            Some(database) => database.clone(),
+            None => match SignatureStore::open().and_then(|store| store.database()) {
+                Ok(database) => Arc::new(database),
+                Err(err) => {
+                    self.error_message = Some(format!("{:#}", err));
+                    return;
+                }
+            },
+        };
+        self.security_database = Some(database.clone());
+        self.security_scanning = true;
+        self.security_confirm_quarantine = false;
+        self.status_message = Some("Scanning...".to_string());
+
+        let (tx, rx) = channel();
+        self.security_receiver = Some(rx);
+
+        thread::spawn(move || {
+            let errors = ScanErrors::new();
+            let result = IocList::load(&database)
+                .and_then(|iocs| security::scan_threats(database, iocs, Vec::new(), &errors));
+            let _ = tx.send((result.map_err(|e| format!("{:#}", e)), errors.take()));
+        });
+    }
+
+    pub fn security_selected_threat(&self) -> Option<&SecurityThreat> {
+        self.security_threats.get(self.security_selected_index)
+    }
+
+    fn security_step(&mut self, delta: isize) {
+        let last = self.security_threats.len().saturating_sub(1);
+        self.security_selected_index = self.security_selected_index.saturating_add_signed(delta).min(last);
+    }
+
+    /// Drop the highlighted threat from the list
+    fn security_take_selected(&mut self) -> Option<SecurityThreat> {
+        if self.security_selected_index >= self.security_threats.len() {
+            return None;
+        }
+        let threat = self.security_threats.remove(self.security_selected_index);
+        self.security_step(0);
+        Some(threat)
+    }
+
+    pub fn security_request_quarantine(&mut self) {
+        let Some(threat) = self.security_selected_threat() else {
+            return;
+        };
+        // These files hold more than the flagged line
+        let shared = threat.threat_type == ThreatType::ShellStartup
+            || threat.path == Path::new("/etc/crontab")
+            || threat.path.starts_with("/var/spool/cron");
+        if shared {
+            self.error_message = Some(format!(
+                "Edit {} to remove the flagged line; quarantining would move the whole file",
+                threat.path.display()
+            ));
+            return;
+        }
+        // The file is fine, its permissions aren't
+        if matches!(threat.threat_type, ThreatType::PrivateKey | ThreatType::WorldWritable) {
+            self.error_message = Some("Press f to fix its permissions instead".to_string());
+            return;
+        }
+        self.error_message = None;
+        self.security_confirm_quarantine = true;
+    }
+
+    /// Open the highlighted threat's file, to review or edit out a secret
+    pub fn security_open_file(&mut self) {
+        let Some(threat) = self.security_selected_threat() else {
+            return;
+        };
+        if !threat.path.is_file() {
+            self.error_message = Some(format!("{} is not a file", threat.path.display()));
+            return;
+        }
+        let path = threat.path.clone();
+        open_path(&path);
+        self.error_message = None;
+        self.status_message = Some(format!("Opening: {}", path.display()));
+    }
+
+    pub fn security_cancel_quarantine(&mut self) {
+        self.security_confirm_quarantine = false;
+    }
+
+    /// Move the highlighted threat into the quarantine
+    pub fn security_quarantine(&mut self) {
+        self.security_confirm_quarantine = false;
+        let path = match self.security_selected_threat() {
+            Some(threat) => threat.path.clone(),
+            None => return,
+        };
+
+        // Quarantine is a reversible move, like the trash; threats in the
+        // user's own home may go, their keys and other users' homes may not
+        let sanitizer = PathSanitizer::default()
+            .without_age_protection()
+            .with_allowed_root(Path::new("~"));
+        if let Err(e) = sanitizer.sanitize_path(&path) {
+            self.error_message = Some(e.to_string());
+            return;
+        }
+        if self.preview_mode {
+            self.status_message = Some(format!("Preview mode: Would quarantine {}", path.display()));
+            return;
+        }
+
+        match Quarantine::open().and_then(|quarantine| quarantine.add(&path)) {
+            Ok(item) => {
+                self.security_take_selected();
+                self.status_message = Some(format!(
+                    "✓ Quarantined {} ({}) to {}",
+                    path.display(),
+                    humansize::format_size(item.size, humansize::BINARY),
+                    item.quarantine_path.display()
+                ));
+                self.error_message = None;
+            }
+            Err(e) => self.error_message = Some(format!("{}: {:#}", path.display(), e)),
+        }
+    }
+
+    /// Apply the highlighted finding's permission fix
+    pub fn security_fix_permissions(&mut self) {
+        let threat = match self.security_selected_threat() {
+            Some(threat) => threat.clone(),
+            None => return,
+        };
+        let Some(mode) = threat.fix_mode else {
+            self.error_message = Some(match &threat.remediation {
+                Some(remediation) => format!("No automatic fix. {}", remediation),
+                None => "No permission fix for this threat".to_string(),
+            });
+            return;
+        };
+        if self.preview_mode {
+            self.status_message = Some(format!(
+                "Preview mode: Would chmod {:04o} {}",
+                mode,
+                threat.path.display()
+            ));
+            return;
+        }
+
+        match permissions::apply_fix(&threat) {
+            Ok(mode) => {
+                self.security_take_selected();
+                self.status_message = Some(format!("✓ chmod {:04o} {}", mode, threat.path.display()));
+                self.error_message = None;
+            }
+            Err(e) if is_permission_denied(&e) => {
+                self.error_message = Some(format!(
+                    "{}: needs root; run surge with sudo to fix it",
+                    threat.path.display()
+                ));
+            }
+            Err(e) => self.error_message = Some(format!("{}: {:#}", threat.path.display(), e)),
+        }
+    }
+
+    /// Hide the highlighted threat until the next scan
+    pub fn security_ignore(&mut self) {
+        if let Some(threat) = self.security_take_selected() {
+            self.status_message = Some(format!("Ignoring {} until the next scan", threat.path.display()));
+        }
+    }
+
+    /// Ask why and for how long to suppress the highlighted threat
+    pub fn security_request_suppress(&mut self) {
+        if self.security_selected_threat().is_some() {
+            self.error_message = None;
+            self.security_suppress = Some(SuppressDraft::default());
+        }
+    }
+
+    pub fn security_suppress_push(&mut self, c: char) {
+        if let Some(draft) = self.security_suppress.as_mut() {
+            draft.reason.push(c);
+        }
+    }
+
+    pub fn security_suppress_pop(&mut self) {
+        if let Some(draft) = self.security_suppress.as_mut() {
+            draft.reason.pop();
+        }
+    }
+
+    pub fn security_suppress_cycle_expiry(&mut self) {
+        if let Some(draft) = self.security_suppress.as_mut() {
+            draft.expiry = (draft.expiry + 1) % SUPPRESS_EXPIRIES.len();
+        }
+    }
+
+    pub fn security_suppress_toggle_key(&mut self) {
+        if let Some(draft) = self.security_suppress.as_mut() {
+            draft.by_content = !draft.by_content;
+        }
+    }
+
+    pub fn security_cancel_suppress(&mut self) {
+        self.security_suppress = None;
+    }
+
+    /// Save the suppression and stop reporting the highlighted threat
+    pub fn security_suppress(&mut self) {
+        let Some(draft) = self.security_suppress.take() else {
+            return;
+        };
+        let threat = match self.security_selected_threat() {
+            Some(threat) => threat.clone(),
+            None => return,
+        };
+        let (expiry_name, days) = SUPPRESS_EXPIRIES[draft.expiry];
+        let result = AllowlistEntry::for_threat(&threat, draft.by_content).and_then(|entry| {
+            let entry = entry
+                .with_reason(Some(draft.reason))
+                .with_expiry(days.map(|days| chrono::Local::now() + chrono::Duration::days(days)));
+            let mut allowlist = Allowlist::load()?;
+            allowlist.add(entry)?;
+            Ok(allowlist.file().to_path_buf())
+        });
+        match result {
+            Ok(file) => {
+                self.security_take_selected();
+                let until = match days {
+                    Some(_) => format!("for {}", expiry_name),
+                    None => "for good".to_string(),
+                };
+                self.status_message = Some(format!(
+                    "Suppressed {} {} (allowlist: {})",
+                    threat.path.display(),
+                    until,
+                    file.display()
+                ));
+                self.error_message = None;
+            }
+            Err(e) => self.error_message = Some(format!("{:#}", e)),
+        }
+    }
+
+    /// Open or close the list of saved suppressions
+    pub fn toggle_security_allowlist(&mut self) {
+        if self.security_allowlist.is_some() {
+            self.security_allowlist = None;
+            return;
+        }
+        match Allowlist::load() {
+            Ok(allowlist) => {
+                self.security_allowlist = Some(allowlist.entries);
+                self.security_allowlist_index = 0;
+            }
+            Err(e) => self.error_message = Some(format!("{:#}", e)),
+        }
+    }
+
+    pub fn security_allowlist_step(&mut self, delta: isize) {
+        let count = self.security_allowlist.as_ref().map_or(0, |entries| entries.len());
+        self.security_allowlist_index = self
+            .security_allowlist_index
+            .saturating_add_signed(delta)
+            .min(count.saturating_sub(1));
+    }
+
+    /// Delete the highlighted suppression; its findings come back on rescan
+    pub fn security_allowlist_remove(&mut self) {
+        let selected = match self
+            .security_allowlist
+            .as_ref()
+            .and_then(|entries| entries.get(self.security_allowlist_index))
+        {
+            Some(entry) => entry.clone(),
+            None => return,
+        };
+        // The file may have changed since the panel was opened
+        let result = Allowlist::load().and_then(|mut allowlist| {
+            let removed = allowlist.remove_entry(&selected)?;
+            Ok((removed, allowlist.entries))
+        });
+        match result {
+            Ok((removed, entries)) => {
+                self.status_message = Some(format!(
+                    "Removed the suppression of {}; press r to rescan",
+                    removed.path.display()
+                ));
+                self.security_allowlist = Some(entries);
+                self.security_allowlist_step(0);
+            }
+            Err(e) => {
+                self.error_message = Some(format!("{:#}", e));
+                // Show what the file holds now
+                if let Ok(allowlist) = Allowlist::load() {
+                    self.security_allowlist = Some(allowlist.entries);
+                    self.security_allowlist_step(0);
                 }
-                self.status_message = Some(format!("Opening: {}", item.name));
             }
         }
     }
@@ -859,6 +2164,11 @@ impl App {
     }
 
     pub fn duplicate_move_up(&mut self) {
+        if self.search_pattern.is_some() {
+            self.duplicate_step_group(false);
+            return;
+        }
+
         if self.duplicate_selected_file > 0 {
             self.duplicate_selected_file -= 1;
         } else if self.duplicate_selected_group > 0 {
@@ -871,6 +2181,11 @@ impl App {
     }
 
     pub fn duplicate_move_down(&mut self) {
+        if self.search_pattern.is_some() {
+            self.duplicate_step_group(true);
+            return;
+        }
+
         if let Some(group) = self.duplicate_groups.get(self.duplicate_selected_group) {
             if self.duplicate_selected_file < group.files.len() - 1 {
                 self.duplicate_selected_file += 1;
@@ -891,7 +2206,13 @@ impl App {
     }
 
     pub fn duplicate_select_all_but_newest(&mut self) {
-        for group in &mut self.duplicate_groups {
+        let visible = self.visible_duplicate_groups();
+        for (_, group) in self
+            .duplicate_groups
+            .iter_mut()
+            .enumerate()
+            .filter(|(idx, _)| visible.contains(idx))
+        {
             if !group.files.is_empty() {
                 // Keep the newest file (last in list after sorting by modified time)
                 let newest_idx = group.files.len() - 1;
@@ -1099,3 +2420,62 @@ impl App {
             .sum()
     }
 }
+
+/// Expand a leading `~` in user-typed paths
+fn expand_home(input: &str) -> PathBuf {
+    match (input.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(input),
+    }
+}
+
+/// Step `delta` positions through `visible` from `current`, wrapping for single
+/// steps (n/N) and clamping for page jumps
+fn step_visible(visible: &[usize], current: usize, delta: isize) -> Option<usize> {
+    if visible.is_empty() {
+        return None;
+    }
+
+    let len = visible.len() as isize;
+    let pos = match visible.iter().position(|&i| i == current) {
+        Some(pos) => pos as isize,
+        // Current entry is filtered out: start just before the next visible one
+        None => visible.iter().position(|&i| i > current).unwrap_or(visible.len()) as isize
+            - if delta > 0 { 1 } else { 0 },
+    };
+
+    let next = if delta.abs() == 1 {
+        (pos + delta).rem_euclid(len)
+    } else {
+        (pos + delta).clamp(0, len - 1)
+    };
+    Some(visible[next as usize])
+}
+
+/// Every path in the tree matching `pattern`, in display order
+fn collect_tree_matches(item: &TreeMapItem, pattern: &Pattern) -> Vec<PathBuf> {
+    let mut matches = Vec::new();
+    for child in &item.children {
+        if pattern.matches_path(&child.path) {
+            matches.push(child.path.clone());
+        }
+        matches.extend(collect_tree_matches(child, pattern));
+    }
+    matches
+}
+
+/// Open a file with the system's default application
+fn open_path(path: &Path) {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = std::process::Command::new("open")
+            .arg(path)
+            .spawn();
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let _ = std::process::Command::new("xdg-open")
+            .arg(path)
+            .spawn();
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use clap::Parser;
+use clap::{Args as ClapArgs, Parser, Subcommand};
 use crossterm::{
     event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     execute,
@@ -10,6 +10,7 @@ use ratatui::{
     Terminal,
 };
 use std::io;
+use std::path::PathBuf;
 
 mod app;
 mod models;
@@ -20,6 +21,12 @@ mod system;
 mod ui;
 
 use app::App;
+use operations::system_clean;
+use scanner::errors::ScanErrors;
+use scanner::ncdu;
+use scanner::treemap::TreeMapScanner;
+use security::report;
+use ui::widgets::image::{GraphicsOverlay, GraphicsProtocol};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -33,8 +40,152 @@ struct Args {
     scan: Option<String>,
 
     /// Preview mode (dry-run, no deletion)
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     preview: bool,
+
+    /// Scan without the TUI and write the tree as an ncdu JSON dump
+    #[arg(long, value_name = "FILE", conflicts_with = "import")]
+    export: Option<PathBuf>,
+
+    /// Browse an ncdu JSON dump (from `ncdu -o` or `surge --export`) in the TreeMap
+    #[arg(long, value_name = "FILE")]
+    import: Option<PathBuf>,
+
+    /// Directory depth to scan when exporting
+    #[arg(long, default_value_t = 3, requires = "export")]
+    depth: usize,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Clean without the TUI
+    Clean(CleanArgs),
+    /// Security checks without the TUI
+    #[command(subcommand)]
+    Security(SecurityCommand),
+    /// Install or roll back signature database updates
+    #[command(subcommand)]
+    Signatures(SignaturesCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum SecurityCommand {
+    /// Look for known adware and malware and list what matched
+    Scan {
+        /// Signature database to use instead of the bundled one
+        #[arg(long, value_name = "FILE")]
+        signatures: Option<PathBuf>,
+
+        /// IOC hash list (plain text or CSV) to check files against, besides
+        /// the lists in ~/.config/surge/ioc
+        #[arg(long = "hashes", value_name = "FILE")]
+        hash_lists: Vec<PathBuf>,
+
+        /// Folders to scan instead of the usual install locations
+        paths: Vec<PathBuf>,
+    },
+    /// List autostart entries, enabled systemd units, cron jobs, shell startup
+    /// lines, ld.so.preload and udev rules, flagging suspicious ones
+    #[cfg(target_os = "linux")]
+    Persistence,
+    /// List SUID/SGID programs outside package-managed paths, world-writable
+    /// files and directories, files with orphaned owners and exposed private keys
+    Permissions,
+    /// List plaintext credentials (AWS and GitHub tokens, .env, .netrc,
+    /// .git-credentials, docker and kubeconfig auth) with masked previews
+    Secrets {
+        /// Project trees to scan instead of the home directories
+        paths: Vec<PathBuf>,
+    },
+    /// Manage suppressed findings: list, add, remove, export and import them
+    Allow {
+        #[command(subcommand)]
+        command: AllowCommand,
+    },
+    /// List the extensions of every browser profile with their permissions,
+    /// risk and signature matches
+    Extensions {
+        /// Print the inventory as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AllowCommand {
+    /// List the suppressions, numbered, with their reasons and expiry
+    List,
+    /// Suppress findings for a path (`~/` and globs allowed) by signature ID,
+    /// by contents, or both
+    Add {
+        path: PathBuf,
+
+        /// Signature ID the finding reports (e.g. adload, rule:tmp_dropper)
+        #[arg(long, value_name = "ID", required_unless_present_any = ["sha256", "content"])]
+        signature: Option<String>,
+
+        /// Only while the file has this SHA-256
+        #[arg(long, value_name = "HEX", conflicts_with = "content")]
+        sha256: Option<String>,
+
+        /// Only while the file has its current contents
+        #[arg(long)]
+        content: bool,
+
+        /// Why it's safe, shown in listings
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// When it runs out: 30d, 12w, 6m, 1y or YYYY-MM-DD
+        #[arg(long, value_name = "WHEN")]
+        expires: Option<String>,
+    },
+    /// Remove a suppression by its number in `list`
+    Remove { number: usize },
+    /// Write the suppressions that haven't expired to a file for others to import
+    Export { file: PathBuf },
+    /// Merge suppressions exported elsewhere, updating ones with the same key
+    Import { file: PathBuf },
+}
+
+#[derive(Subcommand, Debug)]
+enum SignaturesCommand {
+    /// Install a signature update from a directory or file, checked for
+    /// corruption against its SHA-256 manifest (SHA256SUMS or <file>.sha256;
+    /// an integrity check, not a signature), keeping the current database
+    /// for rollback
+    Update {
+        /// Update directory, or the signatures JSON file in it
+        #[arg(long, value_name = "FILE_OR_DIR")]
+        from: PathBuf,
+
+        /// Install even if it isn't newer than the signatures in use
+        #[arg(long)]
+        force: bool,
+    },
+    /// Go back to the signatures in use before the last update
+    Rollback,
+}
+
+#[derive(ClapArgs, Debug)]
+struct CleanArgs {
+    /// Clean system locations the TUI can't, re-running the deletion through sudo or pkexec
+    #[arg(long, required = true)]
+    system: bool,
+
+    /// Clean every listed item without asking
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Internal: the root side of `--system`, cleaning only the given paths
+    #[arg(long, hide = true)]
+    elevated: bool,
+
+    #[arg(hide = true, requires = "elevated", last = true)]
+    paths: Vec<PathBuf>,
 }
 
 fn main() -> Result<()> {
@@ -55,6 +206,83 @@ fn main() -> Result<()> {
         std::thread::sleep(std::time::Duration::from_secs(2));
     }
 
+    match args.command {
+        Some(Commands::Clean(clean)) => {
+            return if clean.elevated {
+                system_clean::clean_approved(&clean.paths)
+            } else {
+                system_clean::clean_system(args.preview, clean.yes)
+            };
+        }
+        Some(Commands::Security(SecurityCommand::Scan {
+            signatures,
+            hash_lists,
+            paths,
+        })) => {
+            return report::scan(signatures.as_deref(), &hash_lists, paths);
+        }
+        #[cfg(target_os = "linux")]
+        Some(Commands::Security(SecurityCommand::Persistence)) => return report::persistence(),
+        Some(Commands::Security(SecurityCommand::Permissions)) => return report::permissions(),
+        Some(Commands::Security(SecurityCommand::Secrets { paths })) => return report::secrets(paths),
+        Some(Commands::Security(SecurityCommand::Allow { command })) => {
+            return match command {
+                AllowCommand::List => report::list_allowed(),
+                AllowCommand::Add {
+                    path,
+                    signature,
+                    sha256,
+                    content,
+                    reason,
+                    expires,
+                } => report::allow(path, signature, sha256, content, reason, expires.as_deref()),
+                AllowCommand::Remove { number } => report::disallow(number),
+                AllowCommand::Export { file } => report::export_allowed(&file),
+                AllowCommand::Import { file } => report::import_allowed(&file),
+            };
+        }
+        Some(Commands::Security(SecurityCommand::Extensions { json })) => return report::extensions(json),
+        Some(Commands::Signatures(SignaturesCommand::Update { from, force })) => {
+            return report::update_signatures(&from, force);
+        }
+        Some(Commands::Signatures(SignaturesCommand::Rollback)) => return report::rollback_signatures(),
+        _ => {}
+    }
+
+    // Headless export - scan and write the dump without starting the TUI
+    if let Some(ref export_path) = args.export {
+        let scan_path = args
+            .scan
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(TreeMapScanner::get_default_scan_path);
+
+        eprintln!("Scanning {}...", scan_path.display());
+        let errors = ScanErrors::new();
+        let root = TreeMapScanner::new()
+            .with_max_depth(args.depth)
+            .with_errors(errors.clone())
+            .scan(&scan_path)?;
+        ncdu::export_to_file(&root, export_path)?;
+        eprintln!(
+            "Exported {} ({}) to {}",
+            scan_path.display(),
+            humansize::format_size(root.size, humansize::BINARY),
+            export_path.display()
+        );
+        // Sizes are lower bounds if anything was skipped
+        for error in errors.take() {
+            eprintln!("Skipped {}: {}", error.path.display(), error.message);
+        }
+        return Ok(());
+    }
+
+    // Load the dump before touching the terminal so parse errors are readable
+    let imported_tree = match args.import {
+        Some(ref import_path) => Some(ncdu::import_from_file(import_path)?),
+        None => None,
+    };
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -64,9 +292,14 @@ fn main() -> Result<()> {
 
     // Create app state with optional custom scan path
     let mut app = App::new(args.preview, args.scan.clone());
+    app.graphics_protocol = GraphicsProtocol::detect();
 
-    // If scan path was provided, auto-navigate to TreeMap and start scanning
-    if args.scan.is_some() {
+    // If a dump was imported, show it in the TreeMap instead of scanning
+    if let Some(root) = imported_tree {
+        app.load_treemap(root);
+        app.navigate_to_screen(2); // 2 = Disk TreeMap
+    } else if args.scan.is_some() {
+        // If scan path was provided, auto-navigate to TreeMap and start scanning
         app.navigate_to_screen(2); // 2 = Disk TreeMap
     }
 
@@ -89,9 +322,23 @@ fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
 ) -> Result<()> {
+    let mut graphics = GraphicsOverlay::new(app.graphics_protocol);
+
     loop {
         terminal.draw(|f| app.render(f))?;
 
+        // Overlay images (kitty/iTerm/sixel) are drawn outside ratatui's buffer
+        let wanted = app.treemap_image().map(|(path, _, area)| (path.to_path_buf(), area));
+        if !graphics.is_current(wanted.as_ref().map(|(path, area)| (path.as_path(), *area))) {
+            if graphics.erase()? {
+                terminal.clear()?;
+                terminal.draw(|f| app.render(f))?;
+            }
+            if let Some((path, image, area)) = app.treemap_image() {
+                graphics.show(path, image, area);
+            }
+        }
+
         if event::poll(std::time::Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
@@ -108,7 +355,76 @@ fn run_app<B: ratatui::backend::Backend>(
 }
 
 fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
-    use crate::app::state::Screen;
+    use crate::app::state::{Screen, TreeMapAction};
+
+    // A pending TreeMap action captures all keys until confirmed or cancelled
+    if let Some(action) = app.treemap_pending_action {
+        handle_treemap_action_key(app, action, key);
+        return Ok(false);
+    }
+
+    // A pending quarantine captures all keys until confirmed or cancelled
+    if app.security_confirm_quarantine {
+        match key.code {
+            KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => app.security_quarantine(),
+            KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => app.security_cancel_quarantine(),
+            _ => {}
+        }
+        return Ok(false);
+    }
+
+    // The suppression prompt captures typing until saved or cancelled
+    if app.security_suppress.is_some() {
+        match key.code {
+            KeyCode::Esc => app.security_cancel_suppress(),
+            KeyCode::Enter => app.security_suppress(),
+            KeyCode::Tab => app.security_suppress_cycle_expiry(),
+            KeyCode::Up | KeyCode::Down => app.security_suppress_toggle_key(),
+            KeyCode::Backspace => app.security_suppress_pop(),
+            KeyCode::Char(c) => app.security_suppress_push(c),
+            _ => {}
+        }
+        return Ok(false);
+    }
+
+    // The suppressions panel captures keys until closed
+    if app.security_allowlist.is_some() {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => app.security_allowlist_step(-1),
+            KeyCode::Down | KeyCode::Char('j') => app.security_allowlist_step(1),
+            KeyCode::Char('d') | KeyCode::Delete => app.security_allowlist_remove(),
+            KeyCode::Esc | KeyCode::Char('a') | KeyCode::Char('A') => app.toggle_security_allowlist(),
+            KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(true),
+            _ => {}
+        }
+        return Ok(false);
+    }
+
+    // The skipped-paths panel captures keys until closed
+    if app.scan_errors_open {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => app.scan_errors_step(-1),
+            KeyCode::Down | KeyCode::Char('j') => app.scan_errors_step(1),
+            KeyCode::PageUp => app.scan_errors_step(-10),
+            KeyCode::PageDown => app.scan_errors_step(10),
+            KeyCode::Esc | KeyCode::Char('e') | KeyCode::Char('E') => app.toggle_scan_errors(),
+            KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(true),
+            _ => {}
+        }
+        return Ok(false);
+    }
+
+    // The `/` search prompt captures typing until confirmed or cancelled
+    if app.search_input.is_some() {
+        match key.code {
+            KeyCode::Esc => app.search_cancel(),
+            KeyCode::Enter => app.search_commit(),
+            KeyCode::Backspace => app.search_pop(),
+            KeyCode::Char(c) => app.search_push(c),
+            _ => {}
+        }
+        return Ok(false);
+    }
 
     // Block most interactions during deletion
     if app.deleting && !matches!(key.code, KeyCode::Char('q') | KeyCode::Char('Q')) {
@@ -117,6 +433,7 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
 
     match key.code {
         KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(true),
+        KeyCode::Esc if app.search_is_active() => app.search_cancel(),
         KeyCode::Esc => {
             app.clear_number_buffer();
             match app.current_screen {
@@ -142,11 +459,9 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
         KeyCode::Char(c) if c.is_ascii_digit() => {
             match app.current_screen {
                 Screen::Home => {
-                    // On home screen: navigate to feature (only 1-2 available)
-                    if let Some(digit) = c.to_digit(10) {
-                        if digit >= 1 && digit <= 2 {
-                            app.navigate_to_screen(digit as usize);
-                        }
+                    // On home screen: navigate to feature (only 1-3 available)
+                    if let Some(digit @ 1..=3) = c.to_digit(10) {
+                        app.navigate_to_screen(digit as usize);
                     }
                 }
                 Screen::StorageCleanup => {
@@ -197,10 +512,19 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
             app.clear_number_buffer();
             app.toggle_selection();
         }
+        KeyCode::Char('a') | KeyCode::Char('A') if app.current_screen == Screen::SecurityScan => {
+            app.toggle_security_allowlist();
+        }
         KeyCode::Char('a') => {
             app.clear_number_buffer();
             app.select_all();
         }
+        KeyCode::Char('/') => {
+            app.clear_number_buffer();
+            app.search_start();
+        }
+        KeyCode::Char('n') if app.search_is_active() => app.search_next(true),
+        KeyCode::Char('N') if app.search_is_active() => app.search_next(false),
         KeyCode::Char('n') => {
             app.clear_number_buffer();
             app.select_none();
@@ -218,8 +542,37 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
         }
         KeyCode::Char('d') => {
             app.clear_number_buffer();
-            app.delete_selected()?;
+            match app.current_screen {
+                Screen::DiskTreeMap => app.treemap_request_action(TreeMapAction::Delete),
+                _ => app.delete_selected()?,
+            }
+        }
+        KeyCode::Char('t') | KeyCode::Char('T') if app.current_screen == Screen::DiskTreeMap => {
+            app.treemap_request_action(TreeMapAction::Trash);
+        }
+        KeyCode::Char('m') | KeyCode::Char('M') if app.current_screen == Screen::DiskTreeMap => {
+            app.treemap_request_action(TreeMapAction::Move);
+        }
+        KeyCode::Char('c') | KeyCode::Char('C') if app.current_screen == Screen::StorageCleanup => {
+            app.compress_selected_logs();
+        }
+        KeyCode::Char('x') | KeyCode::Char('X') if app.current_screen == Screen::SecurityScan => {
+            app.security_request_quarantine();
         }
+        KeyCode::Char('f') | KeyCode::Char('F') if app.current_screen == Screen::SecurityScan => {
+            app.security_fix_permissions();
+        }
+        KeyCode::Char('i') if app.current_screen == Screen::SecurityScan => app.security_ignore(),
+        KeyCode::Char('I') if app.current_screen == Screen::SecurityScan => app.security_request_suppress(),
+        KeyCode::Char('r') | KeyCode::Char('R')
+            if app.current_screen == Screen::SecurityScan && !app.security_scanning =>
+        {
+            app.start_security_scan();
+        }
+        KeyCode::Char('b') | KeyCode::Char('B') if app.current_screen == Screen::DiskTreeMap => {
+            app.treemap_toggle_breakdown();
+        }
+        KeyCode::Char('e') | KeyCode::Char('E') => app.toggle_scan_errors(),
         KeyCode::Char('s') | KeyCode::Char('S') => {
             app.clear_number_buffer();
             app.toggle_sort();
@@ -233,6 +586,8 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
         KeyCode::Char('o') | KeyCode::Char('O') => {
             match app.current_screen {
                 Screen::DiskTreeMap => app.treemap_open_file(),
+                Screen::StorageCleanup => app.select_stale_artifacts(),
+                Screen::SecurityScan => app.security_open_file(),
                 _ => {}
             }
         }
@@ -240,3 +595,22 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
     }
     Ok(false)
 }
+
+fn handle_treemap_action_key(app: &mut App, action: crate::app::state::TreeMapAction, key: KeyEvent) {
+    use crate::app::state::TreeMapAction;
+
+    match (action, key.code) {
+        (_, KeyCode::Esc) => app.treemap_cancel_action(),
+        (_, KeyCode::Enter) => app.treemap_confirm_action(),
+
+        // Move takes a typed destination directory
+        (TreeMapAction::Move, KeyCode::Backspace) => {
+            app.treemap_move_destination.pop();
+        }
+        (TreeMapAction::Move, KeyCode::Char(c)) => app.treemap_move_destination.push(c),
+
+        (_, KeyCode::Char('y')) | (_, KeyCode::Char('Y')) => app.treemap_confirm_action(),
+        (_, KeyCode::Char('n')) | (_, KeyCode::Char('N')) => app.treemap_cancel_action(),
+        _ => {}
+    }
+}
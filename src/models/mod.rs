@@ -1,6 +1,6 @@
 use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CleanableItem {
@@ -9,6 +9,135 @@ pub struct CleanableItem {
     pub category: CleanupCategory,
     pub modified: DateTime<Local>,
     pub selected: bool,
+    /// Extra context shown next to the path (e.g. which project owns it)
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Command that removes this item properly (e.g. `apt-get clean`),
+    /// used instead of deleting the path directly
+    #[serde(default)]
+    pub remove_command: Option<Vec<String>>,
+    /// Nesting level in the cleanup tree; see `subtree_end`
+    #[serde(default)]
+    pub depth: usize,
+    /// Can't be removed by the current user; cleaned through `surge clean --system`
+    #[serde(default)]
+    pub needs_privileges: bool,
+}
+
+impl CleanableItem {
+    /// A folder the scan couldn't read, listed so the result isn't silently
+    /// incomplete; its size is unknown until scanned as root
+    pub fn unreadable(path: PathBuf, category: CleanupCategory, depth: usize) -> Self {
+        Self {
+            path,
+            size: 0,
+            category,
+            modified: Local::now(),
+            selected: false,
+            note: Some("Needs elevated privileges to read".to_string()),
+            remove_command: None,
+            depth,
+            needs_privileges: true,
+        }
+    }
+}
+
+// Cleanup results are a forest stored in pre-order: every item is directly
+// followed by its descendants, which have a greater `depth`. Sizes are
+// rollups, so a parent's size already includes its children's.
+
+/// End (exclusive) of the subtree rooted at `index`
+pub fn subtree_end(items: &[CleanableItem], index: usize) -> usize {
+    let depth = items[index].depth;
+    items[index + 1..]
+        .iter()
+        .position(|i| i.depth <= depth)
+        .map_or(items.len(), |pos| index + 1 + pos)
+}
+
+/// Whether the item at `index` has any children
+pub fn has_children(items: &[CleanableItem], index: usize) -> bool {
+    items.get(index + 1).is_some_and(|next| next.depth > items[index].depth)
+}
+
+/// Indices of the ancestors of `index`, nearest first
+pub fn ancestors(items: &[CleanableItem], index: usize) -> Vec<usize> {
+    let mut result = Vec::new();
+    let mut depth = items[index].depth;
+    for i in (0..index).rev() {
+        if depth == 0 {
+            break;
+        }
+        if items[i].depth < depth {
+            result.push(i);
+            depth = items[i].depth;
+        }
+    }
+    result
+}
+
+/// Selected items that aren't inside another selected item; deleting these
+/// covers the whole selection without counting anything twice
+pub fn selected_roots(items: &[CleanableItem]) -> Vec<usize> {
+    let mut roots = Vec::new();
+    let mut index = 0;
+    while index < items.len() {
+        if items[index].selected {
+            roots.push(index);
+            index = subtree_end(items, index);
+        } else {
+            index += 1;
+        }
+    }
+    roots
+}
+
+/// Remove the item at `index` along with its subtree, subtracting its size
+/// from every ancestor
+pub fn remove_subtree(items: &mut Vec<CleanableItem>, index: usize) -> CleanableItem {
+    let size = items[index].size;
+    for ancestor in ancestors(items, index) {
+        items[ancestor].size = items[ancestor].size.saturating_sub(size);
+    }
+    let end = subtree_end(items, index);
+    items.drain(index..end).next().expect("subtree contains its root")
+}
+
+/// Sort siblings at every level, keeping each subtree right behind its root
+pub fn sort_tree<F>(items: &mut Vec<CleanableItem>, compare: F)
+where
+    F: Fn(&CleanableItem, &CleanableItem) -> std::cmp::Ordering + Copy,
+{
+    let sorted = sort_forest(std::mem::take(items), compare);
+    *items = sorted;
+}
+
+fn sort_forest<F>(items: Vec<CleanableItem>, compare: F) -> Vec<CleanableItem>
+where
+    F: Fn(&CleanableItem, &CleanableItem) -> std::cmp::Ordering + Copy,
+{
+    let base = match items.iter().map(|i| i.depth).min() {
+        Some(depth) => depth,
+        None => return items,
+    };
+
+    let mut trees: Vec<Vec<CleanableItem>> = Vec::new();
+    for item in items {
+        match trees.last_mut() {
+            Some(tree) if item.depth > base => tree.push(item),
+            _ => trees.push(vec![item]),
+        }
+    }
+
+    trees.sort_by(|a, b| compare(&a[0], &b[0]));
+    trees
+        .into_iter()
+        .flat_map(|mut tree| {
+            let children = sort_forest(tree.split_off(1), compare);
+            tree.extend(children);
+            tree
+        })
+        .collect()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -19,6 +148,10 @@ pub enum CleanupCategory {
     Trash,
     Downloads,
     DeveloperCaches,
+    ProjectArtifacts,
+    PackageCaches,
+    Thumbnails,
+    CrashDumps,
     BrowserData,
     ApplicationSupport,
 }
@@ -32,11 +165,20 @@ impl CleanupCategory {
             Self::Trash,
             Self::Downloads,
             Self::DeveloperCaches,
+            Self::ProjectArtifacts,
+            Self::PackageCaches,
+            Self::Thumbnails,
+            Self::CrashDumps,
             Self::BrowserData,
             Self::ApplicationSupport,
         ]
     }
 
+    /// Categories under system locations, cleaned by `surge clean --system`
+    pub fn system() -> Vec<Self> {
+        vec![Self::SystemCaches, Self::Logs, Self::PackageCaches, Self::CrashDumps]
+    }
+
     pub fn name(&self) -> &'static str {
         match self {
             Self::SystemCaches => "System Caches",
@@ -45,6 +187,10 @@ impl CleanupCategory {
             Self::Trash => "Trash",
             Self::Downloads => "Downloads",
             Self::DeveloperCaches => "Developer Caches",
+            Self::ProjectArtifacts => "Project Artifacts",
+            Self::PackageCaches => "Package Caches",
+            Self::Thumbnails => "Thumbnails",
+            Self::CrashDumps => "Crash Dumps",
             Self::BrowserData => "Browser Data",
             Self::ApplicationSupport => "Application Support",
         }
@@ -54,11 +200,15 @@ impl CleanupCategory {
         match self {
             Self::SystemCaches => "System-wide cache files",
             Self::UserCaches => "User application caches",
-            Self::Logs => "Application and system logs",
+            Self::Logs => "Rotated logs older than 30 days, journal usage",
             Self::Trash => "Deleted files in trash",
             Self::Downloads => "Downloaded files",
-            Self::DeveloperCaches => "npm, cargo, gradle, pip caches",
-            Self::BrowserData => "Browser caches and data",
+            Self::DeveloperCaches => "Package, build and compiler caches of dev tools, unused rustup toolchains",
+            Self::ProjectArtifacts => "target/, node_modules/, .venv, build/ in your projects",
+            Self::PackageCaches => "apt, dnf, pacman, zypper caches, unused flatpak runtimes, disabled snaps",
+            Self::Thumbnails => "Orphaned thumbnails, and ones unused for 90+ days",
+            Self::CrashDumps => "Core dumps, crash reports and crash-reporter databases",
+            Self::BrowserData => "Cache folders of Chrome, Chromium, Brave and Firefox profiles",
             Self::ApplicationSupport => "Application support files",
         }
     }
@@ -71,6 +221,7 @@ pub struct TreeMapItem {
     pub size: u64,
     pub children: Vec<TreeMapItem>,
     pub is_file: bool,
+    pub selected: bool,
 }
 
 impl TreeMapItem {
@@ -87,6 +238,7 @@ impl TreeMapItem {
             size,
             children: Vec::new(),
             is_file,
+            selected: false,
         }
     }
 
@@ -97,6 +249,97 @@ impl TreeMapItem {
             (self.size as f64 / total as f64) * 100.0
         }
     }
+
+    /// Find this item or one of its descendants by path
+    pub fn find(&self, path: &Path) -> Option<&TreeMapItem> {
+        if self.path == path {
+            return Some(self);
+        }
+        self.children
+            .iter()
+            .find(|c| path.starts_with(&c.path))
+            .and_then(|c| c.find(path))
+    }
+
+    /// Mutable version of `find`
+    pub fn find_mut(&mut self, path: &Path) -> Option<&mut TreeMapItem> {
+        if self.path == path {
+            return Some(self);
+        }
+        self.children
+            .iter_mut()
+            .find(|c| path.starts_with(&c.path))
+            .and_then(|c| c.find_mut(path))
+    }
+
+    /// Detach a descendant from the tree, subtracting its size from every ancestor
+    pub fn remove(&mut self, path: &Path) -> Option<TreeMapItem> {
+        if let Some(pos) = self.children.iter().position(|c| c.path == path) {
+            let removed = self.children.remove(pos);
+            self.size = self.size.saturating_sub(removed.size);
+            return Some(removed);
+        }
+
+        let child = self.children.iter_mut().find(|c| path.starts_with(&c.path))?;
+        let removed = child.remove(path)?;
+        self.size = self.size.saturating_sub(removed.size);
+        Some(removed)
+    }
+
+    /// Attach an item under the directory at `parent`, adding its size to every
+    /// ancestor. Returns false if `parent` isn't a directory in this tree.
+    pub fn insert(&mut self, parent: &Path, mut item: TreeMapItem) -> bool {
+        if self.path == parent {
+            if self.is_file {
+                return false;
+            }
+            item.rebase(&parent.join(&item.name));
+            self.size += item.size;
+            let pos = self.children.partition_point(|c| c.size >= item.size);
+            self.children.insert(pos, item);
+            return true;
+        }
+
+        let size = item.size;
+        let inserted = match self.children.iter_mut().find(|c| parent.starts_with(&c.path)) {
+            Some(child) => child.insert(parent, item),
+            None => false,
+        };
+        if inserted {
+            self.size += size;
+        }
+        inserted
+    }
+
+    /// Point this item (and its whole subtree) at a new location
+    pub fn rebase(&mut self, new_path: &Path) {
+        for child in &mut self.children {
+            let child_path = new_path.join(&child.name);
+            child.rebase(&child_path);
+        }
+        self.path = new_path.to_path_buf();
+    }
+
+    /// Paths of all selected items, skipping anything inside a selected directory
+    pub fn selected_paths(&self) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        for child in &self.children {
+            if child.selected {
+                paths.push(child.path.clone());
+            } else {
+                paths.extend(child.selected_paths());
+            }
+        }
+        paths
+    }
+
+    /// Clear the selection flag across the whole subtree
+    pub fn clear_selection(&mut self) {
+        self.selected = false;
+        for child in &mut self.children {
+            child.clear_selection();
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -132,6 +375,15 @@ pub struct SecurityThreat {
     pub severity: ThreatSeverity,
     pub description: String,
     pub detected_at: DateTime<Local>,
+    /// Id of the signature that matched
+    #[serde(default)]
+    pub signature_id: String,
+    /// What to do about it, for findings removing the file doesn't settle
+    #[serde(default)]
+    pub remediation: Option<String>,
+    /// Permission bits that fix it, applied with `f` in the Security Scan
+    #[serde(default)]
+    pub fix_mode: Option<u32>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -142,9 +394,50 @@ pub enum ThreatType {
     LoginItem,
     KernelExtension,
     BrowserExtension,
+    SystemdUnit,
+    CronJob,
+    ShellStartup,
+    /// A library in /etc/ld.so.preload or LD_PRELOAD
+    Preload,
+    UdevRule,
+    /// A set-user-ID or set-group-ID program
+    SetuidBinary,
+    /// Writable by everyone, or a shared directory without the sticky bit
+    WorldWritable,
+    /// Owned by a user or group that doesn't exist
+    OrphanedFile,
+    /// A private key others can read
+    PrivateKey,
+    /// A token or password stored in plaintext
+    Credential,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+impl ThreatType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SuspiciousFile => "Suspicious file",
+            Self::LaunchAgent => "Launch agent",
+            Self::LaunchDaemon => "Launch daemon",
+            Self::LoginItem => "Login item",
+            Self::KernelExtension => "Kernel extension",
+            Self::BrowserExtension => "Browser extension",
+            Self::SystemdUnit => "systemd unit",
+            Self::CronJob => "Cron job",
+            Self::ShellStartup => "Shell startup",
+            Self::Preload => "Preload",
+            Self::UdevRule => "udev rule",
+            Self::SetuidBinary => "SUID/SGID binary",
+            Self::WorldWritable => "World-writable",
+            Self::OrphanedFile => "Orphaned owner",
+            Self::PrivateKey => "Exposed key",
+            Self::Credential => "Plaintext credential",
+        }
+    }
+}
+
+/// Ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ThreatSeverity {
     Low,
     Medium,
@@ -152,6 +445,17 @@ pub enum ThreatSeverity {
     Critical,
 }
 
+impl ThreatSeverity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Low => "Low",
+            Self::Medium => "Medium",
+            Self::High => "High",
+            Self::Critical => "Critical",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SystemStats {
     pub cpu_usage: f32,
@@ -179,10 +483,134 @@ impl SystemStats {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuarantineItem {
     pub original_path: PathBuf,
     pub quarantine_path: PathBuf,
     pub quarantined_at: DateTime<Local>,
     pub size: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tree() -> TreeMapItem {
+        let mut root = TreeMapItem::new(PathBuf::from("/data"), 0, false);
+        let mut videos = TreeMapItem::new(PathBuf::from("/data/videos"), 0, false);
+        videos.children.push(TreeMapItem::new(PathBuf::from("/data/videos/a.mkv"), 300, true));
+        videos.children.push(TreeMapItem::new(PathBuf::from("/data/videos/b.mkv"), 200, true));
+        videos.size = 500;
+        root.children.push(videos);
+        root.children.push(TreeMapItem::new(PathBuf::from("/data/notes.txt"), 10, true));
+        root.size = 510;
+        root
+    }
+
+    #[test]
+    fn test_remove_updates_ancestor_sizes() {
+        let mut root = sample_tree();
+
+        let removed = root.remove(Path::new("/data/videos/a.mkv")).unwrap();
+
+        assert_eq!(removed.size, 300);
+        assert_eq!(root.size, 210);
+        assert_eq!(root.find(Path::new("/data/videos")).unwrap().size, 200);
+        assert!(root.find(Path::new("/data/videos/a.mkv")).is_none());
+    }
+
+    #[test]
+    fn test_insert_rebases_paths() {
+        let mut root = sample_tree();
+
+        let videos = root.remove(Path::new("/data/videos")).unwrap();
+        let mut archive = TreeMapItem::new(PathBuf::from("/data/archive"), 0, false);
+        archive.children.push(TreeMapItem::new(PathBuf::from("/data/archive/old.zip"), 5, true));
+        archive.size = 5;
+        root.children.push(archive);
+        root.size += 5;
+
+        assert!(root.insert(Path::new("/data/archive"), videos));
+        assert_eq!(root.size, 515);
+        assert_eq!(root.find(Path::new("/data/archive")).unwrap().size, 505);
+        assert!(root.find(Path::new("/data/archive/videos/b.mkv")).is_some());
+    }
+
+    fn cleanup_item(path: &str, size: u64, depth: usize) -> CleanableItem {
+        CleanableItem {
+            path: PathBuf::from(path),
+            size,
+            category: CleanupCategory::UserCaches,
+            modified: Local::now(),
+            selected: false,
+            note: None,
+            remove_command: None,
+            depth,
+            needs_privileges: false,
+        }
+    }
+
+    fn sample_cleanup_tree() -> Vec<CleanableItem> {
+        vec![
+            cleanup_item("/c/a", 100, 0),
+            cleanup_item("/c/a/x", 30, 1),
+            cleanup_item("/c/a/y", 60, 1),
+            cleanup_item("/c/a/y/z", 50, 2),
+            cleanup_item("/c/b", 200, 0),
+        ]
+    }
+
+    fn paths(items: &[CleanableItem]) -> Vec<&str> {
+        items.iter().map(|i| i.path.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_cleanup_subtrees() {
+        let items = sample_cleanup_tree();
+
+        assert_eq!(subtree_end(&items, 0), 4);
+        assert_eq!(subtree_end(&items, 2), 4);
+        assert_eq!(subtree_end(&items, 4), 5);
+        assert!(has_children(&items, 2));
+        assert!(!has_children(&items, 3));
+        assert_eq!(ancestors(&items, 3), vec![2, 0]);
+        assert!(ancestors(&items, 4).is_empty());
+    }
+
+    #[test]
+    fn test_cleanup_selected_roots_and_removal() {
+        let mut items = sample_cleanup_tree();
+        items[2].selected = true;
+        items[3].selected = true;
+        items[4].selected = true;
+
+        assert_eq!(selected_roots(&items), vec![2, 4]);
+
+        let removed = remove_subtree(&mut items, 2);
+        assert_eq!(removed.path, PathBuf::from("/c/a/y"));
+        assert_eq!(paths(&items), vec!["/c/a", "/c/a/x", "/c/b"]);
+        assert_eq!(items[0].size, 40);
+    }
+
+    #[test]
+    fn test_cleanup_sort_keeps_subtrees() {
+        let mut items = sample_cleanup_tree();
+
+        sort_tree(&mut items, |a, b| a.size.cmp(&b.size));
+
+        assert_eq!(paths(&items), vec!["/c/a", "/c/a/x", "/c/a/y", "/c/a/y/z", "/c/b"]);
+
+        sort_tree(&mut items, |a, b| b.size.cmp(&a.size));
+
+        assert_eq!(paths(&items), vec!["/c/b", "/c/a", "/c/a/y", "/c/a/y/z", "/c/a/x"]);
+    }
+
+    #[test]
+    fn test_selected_paths_skip_nested() {
+        let mut root = sample_tree();
+        root.find_mut(Path::new("/data/videos")).unwrap().selected = true;
+        root.find_mut(Path::new("/data/videos/a.mkv")).unwrap().selected = true;
+
+        assert_eq!(root.selected_paths(), vec![PathBuf::from("/data/videos")]);
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{anyhow, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Gzip a file in place (`name` becomes `name.gz`), keeping its modification
+/// time so retention rules still see the original age
+pub fn gzip_file(path: &Path) -> Result<PathBuf> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_file() {
+        return Err(anyhow!("Not a regular file: {}", path.display()));
+    }
+
+    let mut target = path.as_os_str().to_owned();
+    target.push(".gz");
+    let target = PathBuf::from(target);
+    if target.exists() {
+        return Err(anyhow!("Destination already exists: {}", target.display()));
+    }
+
+    let result = (|| -> Result<()> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut encoder = GzEncoder::new(File::create(&target)?, Compression::default());
+        io::copy(&mut reader, &mut encoder)?;
+        let file = encoder.finish()?;
+        file.sync_all()?;
+        file.set_modified(metadata.modified()?)?;
+        Ok(())
+    })();
+
+    // Never leave a half-written archive next to the original
+    if let Err(e) = result {
+        let _ = fs::remove_file(&target);
+        return Err(e);
+    }
+
+    fs::remove_file(path)?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_gzip_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("syslog.1");
+        fs::write(&path, "line\n".repeat(1000)).unwrap();
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+
+        let target = gzip_file(&path).unwrap();
+
+        assert_eq!(target, temp_dir.path().join("syslog.1.gz"));
+        assert!(!path.exists());
+        assert_eq!(fs::metadata(&target).unwrap().modified().unwrap(), modified);
+
+        let mut contents = String::new();
+        GzDecoder::new(File::open(&target).unwrap())
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "line\n".repeat(1000));
+    }
+}
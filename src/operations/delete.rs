@@ -0,0 +1,154 @@
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+/// Permanently remove a file or directory tree
+pub fn delete_path(path: &Path) -> Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Run a tool's own cleanup command (e.g. `apt-get clean`), failing with its stderr
+pub fn run_remove_command(command: &[String]) -> Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| anyhow!("Empty remove command"))?;
+
+    let output = Command::new(program).args(args).output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} failed: {}",
+            command.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Move a file or directory into `dest_dir`, keeping its name.
+///
+/// Falls back to copy + delete when the destination is on another filesystem.
+pub fn move_path(path: &Path, dest_dir: &Path) -> Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Cannot move a path without a name: {}", path.display()))?;
+
+    if !dest_dir.is_dir() {
+        return Err(anyhow!("Destination is not a directory: {}", dest_dir.display()));
+    }
+
+    let target = dest_dir.join(name);
+    if target.exists() {
+        return Err(anyhow!("Destination already exists: {}", target.display()));
+    }
+    if dest_dir.starts_with(path) {
+        return Err(anyhow!("Cannot move a directory into itself: {}", path.display()));
+    }
+
+    rename_or_copy(path, &target)?;
+    Ok(target)
+}
+
+/// Rename `from` to `to`, copying when they're on different filesystems.
+/// Any other rename error (permissions, a vanished source) is returned as is.
+pub fn rename_or_copy(from: &Path, to: &Path) -> Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {}
+        Err(e) => {
+            return Err(anyhow::Error::new(e)
+                .context(format!("Failed to move {} to {}", from.display(), to.display())))
+        }
+    }
+
+    let metadata = fs::symlink_metadata(from)?;
+    if metadata.is_dir() {
+        copy_dir_recursive(from, to)?;
+    } else {
+        fs::copy(from, to)?;
+    }
+    delete_path(from)
+}
+
+fn copy_dir_recursive(from: &Path, to: &Path) -> Result<()> {
+    for entry in WalkDir::new(from).follow_links(false) {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(from)?;
+        let target = to.join(relative);
+
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if entry.file_type().is_symlink() {
+            let link = fs::read_link(entry.path())?;
+            std::os::unix::fs::symlink(link, &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_move_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("data");
+        let dest = temp_dir.path().join("archive");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        fs::write(source.join("nested/file.txt"), b"content").unwrap();
+        fs::create_dir(&dest).unwrap();
+
+        let moved = move_path(&source, &dest).unwrap();
+
+        assert_eq!(moved, dest.join("data"));
+        assert!(!source.exists());
+        assert!(moved.join("nested/file.txt").exists());
+    }
+
+    #[test]
+    fn test_move_into_itself_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("data");
+        fs::create_dir_all(source.join("inner")).unwrap();
+
+        assert!(move_path(&source, &source.join("inner")).is_err());
+        assert!(source.exists());
+    }
+
+    #[test]
+    fn test_rename_errors_are_not_copied() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("missing");
+        let target = temp_dir.path().join("target");
+
+        let err = rename_or_copy(&missing, &target).unwrap_err();
+        let io_err = err.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(io_err.kind(), io::ErrorKind::NotFound);
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn test_copy_dir_recursive() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("src");
+        let target = temp_dir.path().join("dst");
+        fs::create_dir_all(source.join("a/b")).unwrap();
+        fs::write(source.join("a/b/c.txt"), b"deep").unwrap();
+
+        copy_dir_recursive(&source, &target).unwrap();
+
+        assert_eq!(fs::read(target.join("a/b/c.txt")).unwrap(), b"deep");
+    }
+}
@@ -1,2 +1,5 @@
-// Operations module - file deletion, quarantine, etc.
-// Will be implemented in future phases
+// Operations module - file deletion, trash, moving, etc.
+pub mod compress;
+pub mod delete;
+pub mod system_clean;
+pub mod trash;
@@ -0,0 +1,364 @@
+// `surge clean --system`: list what a normal user can't clean under system
+// locations, then re-run just the approved items as root. The TUI itself
+// never asks for privileges.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local};
+use humansize::{format_size, BINARY};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::models::{self, CleanableItem, CleanupCategory};
+use crate::operations::delete::{delete_path, run_remove_command};
+use crate::scanner::cleanup::CleanupScanner;
+use crate::security::sanitizer::PathSanitizer;
+use crate::system::privileges;
+
+/// Sizes measured by the last run as root. It only holds sizes of folders
+/// the listing names anyway, so it's world-readable for the next
+/// unprivileged listing to estimate what it can't read.
+#[cfg(target_os = "linux")]
+const KNOWN_SIZES_FILE: &str = "/var/cache/surge/system-sizes.json";
+#[cfg(target_os = "macos")]
+const KNOWN_SIZES_FILE: &str = "/Library/Caches/surge/system-sizes.json";
+
+/// Scan the system categories, ask which items to clean and clean them as root
+pub fn clean_system(preview: bool, assume_yes: bool) -> Result<()> {
+    eprintln!("Scanning system locations...");
+    let items = scan_system()?;
+    let mut known = KnownSizes::load_from(PathBuf::from(KNOWN_SIZES_FILE));
+
+    // Everything is in reach as root; otherwise only what needs it
+    let root = privileges::is_root();
+    if root {
+        known.record(&items);
+        let _ = known.save();
+    }
+    let candidates: Vec<&CleanableItem> = items
+        .iter()
+        .filter(|i| i.depth == 0 && (root || i.needs_privileges))
+        .collect();
+
+    if candidates.is_empty() {
+        println!("Nothing under system locations needs elevated privileges.");
+        return Ok(());
+    }
+
+    for (number, item) in candidates.iter().enumerate() {
+        println!(
+            "{:>3}. {} ({}) - {}",
+            number + 1,
+            item.path.display(),
+            size_label(item, &known),
+            item.note.as_deref().unwrap_or(item.category.name())
+        );
+    }
+
+    if preview {
+        println!("Preview mode: nothing cleaned.");
+        return Ok(());
+    }
+
+    let approved: Vec<PathBuf> = if assume_yes {
+        candidates.iter().map(|i| i.path.clone()).collect()
+    } else {
+        print!("Clean which items? [all, numbers like 1 3 5, or none]: ");
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        parse_selection(&answer, candidates.len())?
+            .into_iter()
+            .map(|number| candidates[number - 1].path.clone())
+            .collect()
+    };
+
+    if approved.is_empty() {
+        println!("Nothing selected.");
+        return Ok(());
+    }
+
+    if root {
+        return clean_approved(&approved);
+    }
+
+    let tool = privileges::elevation_tool().ok_or_else(|| {
+        anyhow!("Neither sudo, doas nor pkexec was found; run `surge clean --system` as root")
+    })?;
+    let status = Command::new(&tool)
+        .arg(std::env::current_exe()?)
+        .args(["clean", "--system", "--elevated", "--"])
+        .args(&approved)
+        .status()?;
+    if !status.success() {
+        return Err(anyhow!("Elevated cleanup through {} failed", tool.display()));
+    }
+    Ok(())
+}
+
+/// The root side: re-scan and clean only scanned items under `approved`, so
+/// the arguments can't point the privileged process anywhere else. Each
+/// approved path must also lie inside a system-category root and pass the
+/// sanitizer; one that doesn't is reported and left alone.
+pub fn clean_approved(approved: &[PathBuf]) -> Result<()> {
+    if !privileges::is_root() {
+        return Err(anyhow!("--elevated must run as root"));
+    }
+
+    let mut items = scan_system()?;
+    let roots: Vec<PathBuf> = CleanupScanner::system_roots()
+        .into_iter()
+        .map(|root| root.canonicalize().unwrap_or(root))
+        .collect();
+    let commands: Vec<PathBuf> = items
+        .iter()
+        .filter(|i| i.remove_command.is_some())
+        .map(|i| i.path.clone())
+        .collect();
+    let mut failed = 0;
+    let approved: Vec<PathBuf> = approved
+        .iter()
+        .filter_map(|path| match check_approved(path, &roots, &commands) {
+            Ok(checked) => Some(checked),
+            Err(err) => {
+                failed += 1;
+                println!("✗ {}: {:#}", path.display(), err);
+                None
+            }
+        })
+        .collect();
+
+    for item in &mut items {
+        item.selected = approved.iter().any(|path| item.path.starts_with(path));
+    }
+
+    // This scan sees everything; what gets cleaned below is forgotten again
+    let mut known = KnownSizes::load_from(PathBuf::from(KNOWN_SIZES_FILE));
+    known.record(&items);
+
+    let mut freed = 0;
+    for index in models::selected_roots(&items) {
+        let item = &items[index];
+        let result = match &item.remove_command {
+            Some(command) => run_remove_command(command),
+            None => delete_path(&item.path),
+        };
+        match result {
+            Ok(()) => {
+                known.forget(&item.path);
+                freed += item.size;
+                println!("✓ {} ({})", item.path.display(), format_size(item.size, BINARY));
+            }
+            Err(err) => {
+                failed += 1;
+                println!("✗ {}: {}", item.path.display(), err);
+            }
+        }
+    }
+
+    for path in &approved {
+        if !items.iter().any(|i| i.selected && i.path.starts_with(path)) {
+            println!("- {}: nothing left to clean", path.display());
+        }
+    }
+
+    let _ = known.save();
+    println!("Freed {}", format_size(freed, BINARY));
+    if failed > 0 {
+        return Err(anyhow!("{} items could not be cleaned", failed));
+    }
+    Ok(())
+}
+
+/// Canonical form of an approved path: inside the most specific system root
+/// containing it, and allowed by the sanitizer, which keeps blacklisted
+/// system directories such as `/var/lib` blocked. Items cleaned by a command (journal vacuum, `apt-get clean`)
+/// name the directory the command manages, so they only need the root check.
+fn check_approved(path: &Path, roots: &[PathBuf], commands: &[PathBuf]) -> Result<PathBuf> {
+    let canonical = path
+        .canonicalize()
+        .with_context(|| format!("Approved path {} can't be resolved", path.display()))?;
+    let root = roots
+        .iter()
+        .filter(|root| canonical.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .ok_or_else(|| anyhow!("{} is outside the system cleanup locations", canonical.display()))?;
+
+    if commands.contains(&canonical) {
+        return Ok(canonical);
+    }
+    if canonical == *root {
+        return Err(anyhow!("{} is a cleanup location itself, not an item in it", canonical.display()));
+    }
+    PathSanitizer::default()
+        .without_age_protection()
+        .with_allowed_root(root)
+        .sanitize_path(&canonical)
+}
+
+fn scan_system() -> Result<Vec<CleanableItem>> {
+    // sudo and pkexec may give root its own $HOME; the listing the user
+    // approved from was scanned with theirs
+    if privileges::is_root() {
+        if let Some(home) = privileges::invoking_user_home() {
+            std::env::set_var("HOME", home);
+        }
+    }
+
+    let scanner = CleanupScanner::new();
+    let mut items = Vec::new();
+    for category in CleanupCategory::system() {
+        items.extend(scanner.scan_category(category)?);
+    }
+    Ok(items)
+}
+
+/// Unreadable folders have no size until scanned as root; the last root
+/// run's measurement stands in as an estimate
+fn size_label(item: &CleanableItem, known: &KnownSizes) -> String {
+    if item.size > 0 || !item.needs_privileges {
+        return format_size(item.size, BINARY);
+    }
+    match known.entries.get(&item.path) {
+        Some(measured) => format!(
+            "est. {}, measured as root on {}",
+            format_size(measured.size, BINARY),
+            measured.measured.format("%Y-%m-%d")
+        ),
+        None => "size unknown".to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct KnownSize {
+    size: u64,
+    measured: DateTime<Local>,
+}
+
+/// Sizes of listed system items as last measured by a run as root
+struct KnownSizes {
+    file: PathBuf,
+    entries: HashMap<PathBuf, KnownSize>,
+}
+
+impl KnownSizes {
+    /// A missing or unreadable file starts empty
+    fn load_from(file: PathBuf) -> Self {
+        let entries = fs::read_to_string(&file)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self { file, entries }
+    }
+
+    /// Remember the sizes of a complete scan's top-level items
+    fn record(&mut self, items: &[CleanableItem]) {
+        let measured = Local::now();
+        for item in items.iter().filter(|i| i.depth == 0) {
+            self.entries.insert(item.path.clone(), KnownSize { size: item.size, measured });
+        }
+    }
+
+    /// Drop `path` and everything below it once it's been cleaned
+    fn forget(&mut self, path: &Path) {
+        self.entries.retain(|known, _| !known.starts_with(path));
+    }
+
+    fn save(&mut self) -> Result<()> {
+        self.entries.retain(|path, _| path.exists());
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.file, serde_json::to_string(&self.entries)?)
+            .with_context(|| format!("Failed to write {}", self.file.display()))
+    }
+}
+
+/// 1-based item numbers from "all", "none" or a list like "1 3, 5"
+fn parse_selection(answer: &str, count: usize) -> Result<Vec<usize>> {
+    let answer = answer.trim().to_lowercase();
+    match answer.as_str() {
+        "all" | "a" => return Ok((1..=count).collect()),
+        "" | "none" | "n" => return Ok(Vec::new()),
+        _ => {}
+    }
+
+    let mut numbers = Vec::new();
+    for part in answer.split(|c: char| c == ',' || c.is_whitespace()).filter(|p| !p.is_empty()) {
+        let number: usize = part
+            .parse()
+            .map_err(|_| anyhow!("Not an item number: {}", part))?;
+        if number == 0 || number > count {
+            return Err(anyhow!("No item {}; pick 1 to {}", number, count));
+        }
+        if !numbers.contains(&number) {
+            numbers.push(number);
+        }
+    }
+    Ok(numbers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(parse_selection("all\n", 3).unwrap(), vec![1, 2, 3]);
+        assert_eq!(parse_selection("\n", 3).unwrap(), Vec::<usize>::new());
+        assert_eq!(parse_selection("3, 1 3", 3).unwrap(), vec![3, 1]);
+        assert!(parse_selection("4", 3).is_err());
+        assert!(parse_selection("0", 3).is_err());
+        assert!(parse_selection("one", 3).is_err());
+    }
+
+    #[test]
+    fn test_unreadable_sizes_are_estimated_from_root_scans() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("cache");
+        fs::create_dir(&dir).unwrap();
+        let file = temp_dir.path().join("sizes/system-sizes.json");
+
+        let unreadable = CleanableItem::unreadable(dir.clone(), CleanupCategory::SystemCaches, 0);
+        let known = KnownSizes::load_from(file.clone());
+        assert_eq!(size_label(&unreadable, &known), "size unknown");
+
+        let mut measured = unreadable.clone();
+        measured.size = 4096;
+        let mut known = KnownSizes::load_from(file.clone());
+        known.record(&[measured]);
+        known.save().unwrap();
+
+        let known = KnownSizes::load_from(file.clone());
+        assert!(size_label(&unreadable, &known).starts_with("est. 4 KiB, measured as root on "));
+
+        let mut known = KnownSizes::load_from(file);
+        known.forget(&dir);
+        assert_eq!(size_label(&unreadable, &known), "size unknown");
+    }
+
+    #[test]
+    fn test_approved_paths_stay_inside_system_roots() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap().join("cache");
+        fs::create_dir_all(root.join("app")).unwrap();
+        fs::create_dir_all(root.join("journal")).unwrap();
+        let outside = temp_dir.path().join("elsewhere");
+        fs::create_dir(&outside).unwrap();
+        let roots = vec![root.clone()];
+        let commands = vec![root.join("journal")];
+
+        assert_eq!(check_approved(&root.join("app"), &roots, &commands).unwrap(), root.join("app"));
+        assert_eq!(
+            check_approved(&root.join("app/../journal"), &roots, &commands).unwrap(),
+            root.join("journal")
+        );
+        assert!(check_approved(&root.join("../elsewhere"), &roots, &commands).is_err());
+        assert!(check_approved(&root, &roots, &commands).is_err());
+        assert!(check_approved(&root.join("missing"), &roots, &commands).is_err());
+    }
+}
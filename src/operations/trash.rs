@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::delete::rename_or_copy;
+
+/// Move a file or directory to the user's trash, returning where it ended up
+#[cfg(target_os = "macos")]
+pub fn move_to_trash(path: &Path) -> Result<PathBuf> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
+    let trash_dir = home.join(".Trash");
+    fs::create_dir_all(&trash_dir)?;
+
+    let name = trash_name(path)?;
+    let target = unique_target(&trash_dir, &name);
+    rename_or_copy(path, &target)?;
+    Ok(target)
+}
+
+/// Move a file or directory to the freedesktop.org trash, returning where it ended up
+#[cfg(target_os = "linux")]
+pub fn move_to_trash(path: &Path) -> Result<PathBuf> {
+    let data_dir = dirs::data_dir().ok_or_else(|| anyhow!("Could not determine data directory"))?;
+    move_to_trash_in(path, &data_dir.join("Trash"))
+}
+
+/// Trash `path` into a freedesktop.org trash directory (`files/` + `info/`)
+#[cfg(target_os = "linux")]
+fn move_to_trash_in(path: &Path, trash_dir: &Path) -> Result<PathBuf> {
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    fs::create_dir_all(&files_dir)?;
+    fs::create_dir_all(&info_dir)?;
+
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+
+    let name = trash_name(path)?;
+    let target = unique_target(&files_dir, &name);
+    let trashed_name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or(name);
+
+    // Write the .trashinfo first so file managers can always restore the item
+    let info_path = info_dir.join(format!("{}.trashinfo", trashed_name));
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode_path(&absolute),
+        Local::now().format("%Y-%m-%dT%H:%M:%S")
+    );
+    fs::write(&info_path, info)?;
+
+    if let Err(e) = rename_or_copy(path, &target) {
+        let _ = fs::remove_file(&info_path);
+        return Err(e);
+    }
+
+    Ok(target)
+}
+
+fn trash_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow!("Cannot trash a path without a name: {}", path.display()))
+}
+
+/// Pick a name in `dir` that doesn't collide with anything already trashed
+fn unique_target(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    (2..)
+        .map(|n| dir.join(format!("{}.{}", name, n)))
+        .find(|p| !p.exists())
+        .unwrap_or(candidate)
+}
+
+/// Percent-encode a path for the `Path=` key of a .trashinfo file
+#[cfg(target_os = "linux")]
+fn percent_encode_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_move_to_trash_writes_info() {
+        let temp_dir = TempDir::new().unwrap();
+        let trash_dir = temp_dir.path().join("Trash");
+        let file = temp_dir.path().join("big file.iso");
+        fs::write(&file, b"data").unwrap();
+
+        let trashed = move_to_trash_in(&file, &trash_dir).unwrap();
+
+        assert!(!file.exists());
+        assert_eq!(trashed, trash_dir.join("files/big file.iso"));
+        let info = fs::read_to_string(trash_dir.join("info/big file.iso.trashinfo")).unwrap();
+        assert!(info.contains("Path="));
+        assert!(info.contains("big%20file.iso"));
+    }
+
+    #[test]
+    fn test_trash_name_collisions() {
+        let temp_dir = TempDir::new().unwrap();
+        let trash_dir = temp_dir.path().join("Trash");
+
+        for _ in 0..2 {
+            let file = temp_dir.path().join("dup.txt");
+            fs::write(&file, b"data").unwrap();
+            move_to_trash_in(&file, &trash_dir).unwrap();
+        }
+
+        assert!(trash_dir.join("files/dup.txt").exists());
+        assert!(trash_dir.join("files/dup.txt.2").exists());
+        assert!(trash_dir.join("info/dup.txt.2.trashinfo").exists());
+    }
+}
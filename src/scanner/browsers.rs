@@ -0,0 +1,441 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use sysinfo::System;
+use walkdir::WalkDir;
+
+use crate::models::{CleanableItem, CleanupCategory};
+use crate::scanner::errors::ScanErrors;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    Chromium,
+    Firefox,
+}
+
+/// Whether a profile subdirectory can go without losing anything
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrowserDataKind {
+    /// Rebuilt by the browser on demand
+    Cache,
+    /// Cookies, logins, history, site storage: never offered
+    UserData,
+}
+
+/// Chromium profile subdirectories holding only caches
+const CHROMIUM_CACHES: &[&str] = &[
+    "Cache",
+    "Code Cache",
+    "GPUCache",
+    "DawnCache",
+    "DawnGraphiteCache",
+    "DawnWebGPUCache",
+    "Service Worker/CacheStorage",
+    "Service Worker/ScriptCache",
+];
+
+/// Firefox profile subdirectories holding only caches
+const FIREFOX_CACHES: &[&str] = &["cache2", "startupCache", "shader-cache", "OfflineCache"];
+
+/// A browser's install locations: profiles live under `data_dir`, and some
+/// caches are split out under `cache_dir`
+pub struct Browser {
+    pub name: &'static str,
+    pub engine: Engine,
+    pub data_dir: PathBuf,
+    pub cache_dir: Option<PathBuf>,
+    /// Lowercase process names of the running browser
+    pub processes: &'static [&'static str],
+}
+
+/// A profile as listed by the browser itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// Directory name relative to the data/cache dir (e.g. `Default`, `abcd.default-release`)
+    pub dir: PathBuf,
+    /// Display name shown in the browser
+    pub name: String,
+}
+
+/// Finds cache directories of browser profiles, leaving user data alone
+pub struct BrowserScanner {
+    browsers: Vec<Browser>,
+    errors: ScanErrors,
+}
+
+impl BrowserScanner {
+    pub fn new() -> Self {
+        Self {
+            browsers: dirs::home_dir().map(|home| known_browsers(&home)).unwrap_or_default(),
+            errors: ScanErrors::new(),
+        }
+    }
+
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Top-level cache directories; the generic UserCaches scan leaves them
+    /// to this scanner
+    pub fn cache_roots(&self) -> Vec<PathBuf> {
+        self.browsers.iter().filter_map(|b| b.cache_dir.clone()).collect()
+    }
+
+    pub fn scan(&self) -> Result<Vec<CleanableItem>> {
+        Ok(self.scan_with_running(&running_processes()))
+    }
+
+    fn scan_with_running(&self, running: &HashSet<String>) -> Vec<CleanableItem> {
+        let mut items = Vec::new();
+
+        for browser in &self.browsers {
+            let is_running = browser.processes.iter().any(|p| running.contains(*p));
+            let roots: Vec<&Path> = std::iter::once(browser.data_dir.as_path())
+                .chain(browser.cache_dir.as_deref())
+                .collect();
+
+            for profile in browser.profiles() {
+                for root in &roots {
+                    let profile_dir = root.join(&profile.dir);
+                    // Caches sit at most one level down (Service Worker/CacheStorage)
+                    let mut walker = WalkDir::new(&profile_dir)
+                        .min_depth(1)
+                        .max_depth(2)
+                        .follow_links(false)
+                        .into_iter();
+
+                    while let Some(entry) = walker.next() {
+                        let entry = match self.errors.ok(entry) {
+                            Some(entry) => entry,
+                            None => continue,
+                        };
+                        if !entry.file_type().is_dir() {
+                            continue;
+                        }
+                        let relative = entry.path().strip_prefix(&profile_dir).unwrap_or(entry.path());
+                        if classify(browser.engine, relative) != BrowserDataKind::Cache {
+                            continue;
+                        }
+                        walker.skip_current_dir();
+
+                        let size = dir_size(entry.path(), &self.errors);
+                        if size == 0 || items.iter().any(|i: &CleanableItem| i.path == entry.path()) {
+                            continue;
+                        }
+
+                        let mut note = format!(
+                            "{} profile '{}' • {}",
+                            browser.name,
+                            profile.name,
+                            relative.display()
+                        );
+                        if is_running {
+                            note.push_str(&format!(" • {} is running, close it first", browser.name));
+                        }
+
+                        items.push(CleanableItem {
+                            path: entry.path().to_path_buf(),
+                            size,
+                            category: CleanupCategory::BrowserData,
+                            modified: modified_time(entry.path()),
+                            selected: false,
+                            note: Some(note),
+                            remove_command: None,
+                            depth: 0,
+                            needs_privileges: false,
+                        });
+                    }
+                }
+            }
+        }
+
+        items.sort_by_key(|i| std::cmp::Reverse(i.size));
+        items
+    }
+}
+
+impl Browser {
+    /// Profiles the browser knows about, from `Local State` or `profiles.ini`
+    pub fn profiles(&self) -> Vec<Profile> {
+        match self.engine {
+            Engine::Chromium => fs::read_to_string(self.data_dir.join("Local State"))
+                .map(|s| parse_local_state(&s))
+                .unwrap_or_default(),
+            Engine::Firefox => fs::read_to_string(self.data_dir.join("profiles.ini"))
+                .map(|s| parse_profiles_ini(&s))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Whether `relative` (a path inside a profile) is a safe cache or user data
+pub fn classify(engine: Engine, relative: &Path) -> BrowserDataKind {
+    let caches = match engine {
+        Engine::Chromium => CHROMIUM_CACHES,
+        Engine::Firefox => FIREFOX_CACHES,
+    };
+    if caches.iter().any(|cache| relative.starts_with(cache)) {
+        BrowserDataKind::Cache
+    } else {
+        BrowserDataKind::UserData
+    }
+}
+
+/// Profiles from Chromium's `Local State` (`profile.info_cache`)
+pub fn parse_local_state(contents: &str) -> Vec<Profile> {
+    let json: serde_json::Value = match serde_json::from_str(contents) {
+        Ok(json) => json,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut profiles: Vec<Profile> = json["profile"]["info_cache"]
+        .as_object()
+        .map(|cache| {
+            cache
+                .iter()
+                .map(|(dir, info)| Profile {
+                    dir: PathBuf::from(dir),
+                    name: info["name"].as_str().unwrap_or(dir).to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    profiles.sort_by(|a, b| a.dir.cmp(&b.dir));
+    profiles
+}
+
+/// Profiles from Firefox's `profiles.ini`; absolute paths are kept as-is
+pub fn parse_profiles_ini(contents: &str) -> Vec<Profile> {
+    let mut profiles = Vec::new();
+    let mut in_profile = false;
+    let mut name = None;
+    let mut path = None;
+
+    let mut finish = |name: &mut Option<String>, path: &mut Option<String>| {
+        if let Some(path) = path.take() {
+            profiles.push(Profile {
+                name: name.take().unwrap_or_else(|| path.clone()),
+                dir: PathBuf::from(path),
+            });
+        }
+        *name = None;
+    };
+
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            if in_profile {
+                finish(&mut name, &mut path);
+            }
+            in_profile = line.starts_with("[Profile");
+        } else if in_profile {
+            match line.split_once('=') {
+                Some(("Name", value)) => name = Some(value.to_string()),
+                Some(("Path", value)) => path = Some(value.to_string()),
+                _ => {}
+            }
+        }
+    }
+    if in_profile {
+        finish(&mut name, &mut path);
+    }
+
+    profiles
+}
+
+/// Browsers surge knows where to find, under `home`
+#[cfg(target_os = "linux")]
+pub fn known_browsers(home: &Path) -> Vec<Browser> {
+    vec![
+        Browser {
+            name: "Chrome",
+            engine: Engine::Chromium,
+            data_dir: home.join(".config/google-chrome"),
+            cache_dir: Some(home.join(".cache/google-chrome")),
+            processes: &["chrome"],
+        },
+        Browser {
+            name: "Chromium",
+            engine: Engine::Chromium,
+            data_dir: home.join(".config/chromium"),
+            cache_dir: Some(home.join(".cache/chromium")),
+            processes: &["chromium", "chromium-browser"],
+        },
+        Browser {
+            name: "Brave",
+            engine: Engine::Chromium,
+            data_dir: home.join(".config/BraveSoftware/Brave-Browser"),
+            cache_dir: Some(home.join(".cache/BraveSoftware/Brave-Browser")),
+            processes: &["brave", "brave-browser"],
+        },
+        Browser {
+            name: "Firefox",
+            engine: Engine::Firefox,
+            data_dir: home.join(".mozilla/firefox"),
+            cache_dir: Some(home.join(".cache/mozilla/firefox")),
+            processes: &["firefox", "firefox-bin", "firefox-esr"],
+        },
+    ]
+}
+
+/// Browsers surge knows where to find, under `home`
+#[cfg(target_os = "macos")]
+pub fn known_browsers(home: &Path) -> Vec<Browser> {
+    vec![
+        Browser {
+            name: "Chrome",
+            engine: Engine::Chromium,
+            data_dir: home.join("Library/Application Support/Google/Chrome"),
+            cache_dir: Some(home.join("Library/Caches/Google/Chrome")),
+            processes: &["google chrome"],
+        },
+        Browser {
+            name: "Chromium",
+            engine: Engine::Chromium,
+            data_dir: home.join("Library/Application Support/Chromium"),
+            cache_dir: Some(home.join("Library/Caches/Chromium")),
+            processes: &["chromium"],
+        },
+        Browser {
+            name: "Brave",
+            engine: Engine::Chromium,
+            data_dir: home.join("Library/Application Support/BraveSoftware/Brave-Browser"),
+            cache_dir: Some(home.join("Library/Caches/BraveSoftware/Brave-Browser")),
+            processes: &["brave browser"],
+        },
+        Browser {
+            name: "Firefox",
+            engine: Engine::Firefox,
+            data_dir: home.join("Library/Application Support/Firefox"),
+            cache_dir: Some(home.join("Library/Caches/Firefox")),
+            processes: &["firefox"],
+        },
+    ]
+}
+
+/// Lowercase names of running processes
+fn running_processes() -> HashSet<String> {
+    let mut sys = System::new();
+    sys.refresh_processes();
+    sys.processes()
+        .values()
+        .map(|p| p.name().to_lowercase())
+        .collect()
+}
+
+fn modified_time(path: &Path) -> DateTime<Local> {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(DateTime::<Local>::from)
+        .unwrap_or_else(|_| Local::now())
+}
+
+fn dir_size(path: &Path, errors: &ScanErrors) -> u64 {
+    WalkDir::new(path)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| errors.ok(e))
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| errors.ok(e.metadata()))
+        .map(|m| m.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, size: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0u8; size]).unwrap();
+    }
+
+    #[test]
+    fn test_parse_profiles() {
+        let local_state = r#"{"profile": {"info_cache": {
+            "Default": {"name": "Person 1"},
+            "Profile 2": {"name": "Work"}
+        }}}"#;
+        assert_eq!(
+            parse_local_state(local_state),
+            vec![
+                Profile { dir: PathBuf::from("Default"), name: "Person 1".to_string() },
+                Profile { dir: PathBuf::from("Profile 2"), name: "Work".to_string() },
+            ]
+        );
+
+        let ini = "[Install4F96D1932A9F858E]\nDefault=abcd.default-release\n\n\
+                   [Profile0]\nName=default-release\nIsRelative=1\nPath=abcd.default-release\n\n\
+                   [General]\nVersion=2\n";
+        assert_eq!(
+            parse_profiles_ini(ini),
+            vec![Profile {
+                dir: PathBuf::from("abcd.default-release"),
+                name: "default-release".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(Engine::Chromium, Path::new("Code Cache/js")), BrowserDataKind::Cache);
+        assert_eq!(
+            classify(Engine::Chromium, Path::new("Service Worker/CacheStorage")),
+            BrowserDataKind::Cache
+        );
+        assert_eq!(
+            classify(Engine::Chromium, Path::new("Service Worker/Database")),
+            BrowserDataKind::UserData
+        );
+        assert_eq!(classify(Engine::Chromium, Path::new("Cookies")), BrowserDataKind::UserData);
+        assert_eq!(classify(Engine::Firefox, Path::new("cache2/entries")), BrowserDataKind::Cache);
+        assert_eq!(classify(Engine::Firefox, Path::new("cookies.sqlite")), BrowserDataKind::UserData);
+    }
+
+    #[test]
+    fn test_only_profile_caches_offered() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().join("config/chromium");
+        let cache_dir = temp_dir.path().join("cache/chromium");
+
+        fs::create_dir_all(&data_dir).unwrap();
+        fs::write(
+            data_dir.join("Local State"),
+            r#"{"profile": {"info_cache": {"Default": {"name": "Person 1"}}}}"#,
+        )
+        .unwrap();
+        write(&data_dir.join("Default/Cookies"), 100);
+        write(&data_dir.join("Default/Local Storage/leveldb/000003.log"), 100);
+        write(&data_dir.join("Default/Code Cache/js/index"), 300);
+        write(&cache_dir.join("Default/Cache/Cache_Data/data_0"), 500);
+        // Not listed in Local State
+        write(&data_dir.join("Profile 9/Cache/data_0"), 100);
+
+        let scanner = BrowserScanner {
+            browsers: vec![Browser {
+                name: "Chromium",
+                engine: Engine::Chromium,
+                data_dir: data_dir.clone(),
+                cache_dir: Some(cache_dir.clone()),
+                processes: &["chromium"],
+            }],
+            errors: ScanErrors::new(),
+        };
+
+        let items = scanner.scan_with_running(&HashSet::new());
+        let paths: Vec<&Path> = items.iter().map(|i| i.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![cache_dir.join("Default/Cache"), data_dir.join("Default/Code Cache")]
+        );
+        assert_eq!(items[0].note.as_deref(), Some("Chromium profile 'Person 1' • Cache"));
+
+        let running: HashSet<String> = ["chromium".to_string()].into_iter().collect();
+        let items = scanner.scan_with_running(&running);
+        assert!(items[0].note.as_ref().unwrap().ends_with("Chromium is running, close it first"));
+    }
+}
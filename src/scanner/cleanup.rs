@@ -1,15 +1,54 @@
 use anyhow::Result;
-use chrono::Local;
+use chrono::{DateTime, Local};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-use crate::models::{CleanableItem, CleanupCategory};
+use crate::models::{ancestors, remove_subtree, CleanableItem, CleanupCategory};
+use crate::scanner::browsers::BrowserScanner;
+use crate::scanner::crashes::CrashDumpScanner;
+use crate::scanner::errors::{ScanError, ScanErrorKind, ScanErrors};
+#[cfg(target_os = "linux")]
+use crate::scanner::logs::JOURNAL_DIRS;
+use crate::scanner::logs::LogScanner;
+#[cfg(target_os = "linux")]
+use crate::scanner::packages::PackageCacheScanner;
+use crate::scanner::projects::ProjectScanner;
+use crate::scanner::thumbnails::ThumbnailScanner;
+use crate::scanner::toolchains::ToolchainScanner;
+use crate::security::blacklist::developer_cache_paths;
+use crate::system::privileges;
 
-pub struct CleanupScanner;
+/// Directory levels listed under each scanned path; deeper files still
+/// count towards their listed ancestors
+const TREE_DEPTH: usize = 4;
+
+/// Entries smaller than this aren't listed, but count towards their parent
+const MIN_ITEM_SIZE: u64 = 100 * 1024;
+
+pub struct CleanupScanner {
+    /// Where to look for projects; defaults to the home directory
+    project_roots: Option<Vec<PathBuf>>,
+    errors: ScanErrors,
+}
 
 impl CleanupScanner {
     pub fn new() -> Self {
-        Self
+        Self {
+            project_roots: None,
+            errors: ScanErrors::new(),
+        }
+    }
+
+    pub fn with_project_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.project_roots = Some(roots);
+        self
+    }
+
+    /// Where paths the scan had to skip are recorded
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
     }
 
     /// Get paths to scan for a given category
@@ -51,31 +90,10 @@ impl CleanupScanner {
                     vec![]
                 }
             }
-            CleanupCategory::DeveloperCaches => {
-                if let Some(home) = dirs::home_dir() {
-                    vec![
-                        home.join(".npm"),
-                        home.join(".yarn"),
-                        home.join(".cargo/registry"),
-                        home.join(".gradle/caches"),
-                        home.join("Library/Developer/Xcode/DerivedData"),
-                        home.join("Library/Developer/CoreSimulator/Caches"),
-                    ]
-                } else {
-                    vec![]
-                }
-            }
-            CleanupCategory::BrowserData => {
-                if let Some(home) = dirs::home_dir() {
-                    vec![
-                        home.join("Library/Caches/Google/Chrome"),
-                        home.join("Library/Caches/Firefox"),
-                        home.join("Library/Safari"),
-                    ]
-                } else {
-                    vec![]
-                }
-            }
+            // From the toolchain registry, see ToolchainScanner
+            CleanupCategory::DeveloperCaches => vec![],
+            // Profile-aware, see BrowserScanner
+            CleanupCategory::BrowserData => vec![],
             CleanupCategory::ApplicationSupport => {
                 if let Some(home) = dirs::home_dir() {
                     vec![home.join("Library/Application Support")]
@@ -83,6 +101,14 @@ impl CleanupScanner {
                     vec![]
                 }
             }
+            // Found by walking project roots, see ProjectScanner
+            CleanupCategory::ProjectArtifacts => vec![],
+            // Linux package managers only
+            CleanupCategory::PackageCaches => vec![],
+            // freedesktop thumbnails are a Linux desktop thing
+            CleanupCategory::Thumbnails => vec![],
+            // Found by CrashDumpScanner
+            CleanupCategory::CrashDumps => vec![],
         }
     }
 
@@ -112,37 +138,89 @@ impl CleanupScanner {
                     vec![]
                 }
             }
-            CleanupCategory::DeveloperCaches => {
-                if let Some(home) = dirs::home_dir() {
-                    vec![
-                        home.join(".npm"),
-                        home.join(".yarn"),
-                        home.join(".cargo/registry"),
-                        home.join(".gradle/caches"),
-                        home.join(".cache/pip"),
-                    ]
-                } else {
-                    vec![]
-                }
-            }
-            CleanupCategory::BrowserData => {
+            // From the toolchain registry, see ToolchainScanner
+            CleanupCategory::DeveloperCaches => vec![],
+            // Profile-aware, see BrowserScanner
+            CleanupCategory::BrowserData => vec![],
+            CleanupCategory::ApplicationSupport => vec![],
+            // Found by walking project roots, see ProjectScanner
+            CleanupCategory::ProjectArtifacts => vec![],
+            // Package-manager aware, see PackageCacheScanner
+            CleanupCategory::PackageCaches => vec![],
+            // Found by CrashDumpScanner
+            CleanupCategory::CrashDumps => vec![],
+            CleanupCategory::Thumbnails => {
                 if let Some(home) = dirs::home_dir() {
-                    vec![
-                        home.join(".cache/google-chrome"),
-                        home.join(".mozilla/firefox"),
-                        home.join(".cache/chromium"),
-                    ]
+                    vec![home.join(".cache/thumbnails")]
                 } else {
                     vec![]
                 }
             }
-            CleanupCategory::ApplicationSupport => vec![],
         }
     }
 
+    /// Directories the system categories report items under; the root side
+    /// of `surge clean --system` refuses anything outside them
+    pub fn system_roots() -> Vec<PathBuf> {
+        let mut roots: Vec<PathBuf> = CleanupCategory::system()
+            .iter()
+            .flat_map(Self::get_category_paths)
+            .collect();
+        #[cfg(target_os = "linux")]
+        {
+            roots.extend(PackageCacheScanner::roots());
+            roots.extend(JOURNAL_DIRS.iter().map(PathBuf::from));
+        }
+        roots.extend(CrashDumpScanner::new().roots());
+        roots
+    }
+
     /// Scan a category for cleanable items
     pub fn scan_category(&self, category: CleanupCategory) -> Result<Vec<CleanableItem>> {
+        let mut items = self.scan_category_items(category)?;
+        mark_privileged(&mut items);
+        Ok(items)
+    }
+
+    fn scan_category_items(&self, category: CleanupCategory) -> Result<Vec<CleanableItem>> {
+        if category == CleanupCategory::ProjectArtifacts {
+            let scanner = match &self.project_roots {
+                Some(roots) => ProjectScanner::new().with_roots(roots.clone()),
+                None => ProjectScanner::new(),
+            };
+            return scanner.with_errors(self.errors.clone()).scan();
+        }
+        // Stray core files turn up in the same places as projects
+        if category == CleanupCategory::CrashDumps {
+            let scanner = match &self.project_roots {
+                Some(roots) => CrashDumpScanner::new().with_roots(roots.clone()),
+                None => CrashDumpScanner::new(),
+            };
+            return scanner.with_errors(self.errors.clone()).scan();
+        }
+        if category == CleanupCategory::DeveloperCaches {
+            return ToolchainScanner::new().with_errors(self.errors.clone()).scan();
+        }
+        // Only caches inside known profiles, never cookies or logins
+        if category == CleanupCategory::BrowserData {
+            return BrowserScanner::new().with_errors(self.errors.clone()).scan();
+        }
+        #[cfg(target_os = "linux")]
+        if category == CleanupCategory::PackageCaches {
+            return PackageCacheScanner::new().with_errors(self.errors.clone()).scan();
+        }
+
         let paths = Self::get_category_paths(&category);
+
+        // Only rotated logs past retention, never the live ones
+        if category == CleanupCategory::Logs {
+            return LogScanner::new().with_errors(self.errors.clone()).scan(&paths);
+        }
+        // Only orphaned or long-unused thumbnails, not the whole cache
+        if category == CleanupCategory::Thumbnails {
+            return ThumbnailScanner::new().with_errors(self.errors.clone()).scan(&paths);
+        }
+
         let mut items = Vec::new();
 
         for path in paths {
@@ -156,54 +234,118 @@ impl CleanupScanner {
         Ok(items)
     }
 
-    /// Scan a specific path recursively
+    /// Paths another scanner reports for `category`'s directories, with the
+    /// right way to clear them; the generic walk skips them entirely
+    fn excluded_paths(category: &CleanupCategory) -> Vec<PathBuf> {
+        let mut excluded = Vec::new();
+
+        // Package manager caches are reported by PackageCacheScanner
+        #[cfg(target_os = "linux")]
+        if *category == CleanupCategory::SystemCaches {
+            excluded.extend(PackageCacheScanner::managed_paths());
+        }
+        if *category == CleanupCategory::UserCaches {
+            // Thumbnails have their own category that keeps recent ones
+            excluded.extend(Self::get_category_paths(&CleanupCategory::Thumbnails));
+            excluded.extend(BrowserScanner::new().cache_roots());
+            if let Some(home) = dirs::home_dir() {
+                excluded.extend(
+                    developer_cache_paths()
+                        .filter_map(|p| p.strip_prefix("~/"))
+                        .map(|p| home.join(p)),
+                );
+            }
+        }
+
+        excluded
+    }
+
+    /// Scan a path into a tree of directories with rolled-up sizes: the
+    /// path's subdirectories (and large files directly inside it) at the top,
+    /// their subdirectories nested below
     fn scan_path(&self, path: &Path, category: &CleanupCategory) -> Result<Vec<CleanableItem>> {
-        let mut items = Vec::new();
-        let mut dir_sizes: std::collections::HashMap<PathBuf, u64> = std::collections::HashMap::new();
+        let excluded = Self::excluded_paths(category);
+        let mut sizes: HashMap<PathBuf, u64> = HashMap::new();
+        let mut loose_files = Vec::new();
+        let mut unreadable = HashSet::new();
 
-        // Walk through all files and accumulate directory sizes
-        for entry in WalkDir::new(path)
-            .max_depth(5) // Scan deeper
+        let walker = WalkDir::new(path)
             .follow_links(false)
-        {
+            .into_iter()
+            .filter_entry(|e| !excluded.iter().any(|x| e.path().starts_with(x)));
+
+        for entry in walker {
             let entry = match entry {
-                Ok(e) => e,
-                Err(_) => continue,
+                Ok(entry) => entry,
+                Err(err) => {
+                    unreadable.extend(privileges::permission_denied(&err));
+                    self.errors.record_walk(&err);
+                    continue;
+                }
             };
+            self.errors.check_symlink(&entry);
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let size = match self.errors.ok(entry.metadata()) {
+                Some(metadata) => metadata.len(),
+                None => continue,
+            };
+            if entry.depth() == 1 {
+                loose_files.push((entry.path().to_path_buf(), size));
+                continue;
+            }
 
-            if entry.file_type().is_file() {
-                if let Ok(metadata) = entry.metadata() {
-                    let size = metadata.len();
+            // Count the file into every listed ancestor, so parents include
+            // their children and deeper files aren't lost
+            let parents = match entry.path().strip_prefix(path).ok().and_then(Path::parent) {
+                Some(parents) => parents,
+                None => continue,
+            };
+            let mut dir = path.to_path_buf();
+            for component in parents.components().take(TREE_DEPTH) {
+                dir.push(component);
+                *sizes.entry(dir.clone()).or_insert(0) += size;
+            }
+        }
 
-                    // Add to parent directory size
-                    if let Some(parent) = entry.path().parent() {
-                        *dir_sizes.entry(parent.to_path_buf()).or_insert(0) += size;
-                    }
-                }
+        if unreadable.contains(path) {
+            return Ok(vec![CleanableItem::unreadable(path.to_path_buf(), *category, 0)]);
+        }
+        // Unreadable folders are listed at their deepest listed level, along
+        // with their ancestors, however small their readable part is
+        let unreadable: HashSet<PathBuf> = unreadable
+            .iter()
+            .filter_map(|dir| dir.strip_prefix(path).ok())
+            .map(|relative| path.join(relative.iter().take(TREE_DEPTH).collect::<PathBuf>()))
+            .collect();
+        for dir in &unreadable {
+            for ancestor in dir.ancestors().take_while(|a| *a != path) {
+                sizes.entry(ancestor.to_path_buf()).or_insert(0);
             }
         }
 
-        // Convert directory sizes to CleanableItems
-        for (dir_path, total_size) in dir_sizes.iter() {
-            if *total_size > 100 * 1024 { // Only show dirs > 100KB
-                if let Ok(metadata) = std::fs::metadata(dir_path) {
-                    let modified = metadata
-                        .modified()
-                        .ok()
-                        .map(|t| chrono::DateTime::<Local>::from(t))
-                        .unwrap_or_else(Local::now);
-
-                    items.push(CleanableItem {
-                        path: dir_path.clone(),
-                        size: *total_size,
-                        category: *category,
-                        modified,
-                        selected: false,
-                    });
-                }
+        sizes.extend(loose_files);
+        // A parent is never smaller than its child, so this leaves no orphans
+        sizes.retain(|dir, size| *size >= MIN_ITEM_SIZE || unreadable.iter().any(|u| u.starts_with(dir)));
+
+        let mut children: HashMap<PathBuf, Vec<(PathBuf, u64)>> = HashMap::new();
+        for (entry, size) in sizes {
+            if let Some(parent) = entry.parent() {
+                children.entry(parent.to_path_buf()).or_default().push((entry, size));
             }
         }
 
+        let mut items = Vec::new();
+        push_subtree(&mut items, &mut children, path, 0, category);
+        for item in &mut items {
+            if unreadable.contains(&item.path) && item.size == 0 {
+                *item = CleanableItem::unreadable(item.path.clone(), *category, item.depth);
+            } else if unreadable.iter().any(|u| u.starts_with(&item.path)) {
+                item.needs_privileges = true;
+                item.note = Some("Partly unreadable without elevated privileges; real size is larger".to_string());
+            }
+        }
         Ok(items)
     }
 
@@ -211,9 +353,16 @@ impl CleanupScanner {
     pub fn scan_all(&self) -> Result<Vec<CleanableItem>> {
         let mut all_items = Vec::new();
 
-        // Scan all predefined categories
+        // Scan all predefined categories; one failing doesn't hide the others
         for category in CleanupCategory::all() {
-            all_items.extend(self.scan_category(category)?);
+            match self.scan_category(category) {
+                Ok(items) => all_items.extend(items),
+                Err(err) => self.errors.push(ScanError {
+                    path: Self::get_category_paths(&category).into_iter().next().unwrap_or_default(),
+                    kind: ScanErrorKind::Io,
+                    message: format!("{} scan failed: {:#}", category.name(), err),
+                }),
+            }
         }
 
         // If we found nothing, do a broader scan of the home directory
@@ -235,6 +384,175 @@ impl CleanupScanner {
             }
         }
 
+        drop_nested_roots(&mut all_items);
         Ok(all_items)
     }
 }
+
+/// Append the entries under `parent`, largest first, each followed by its own subtree
+fn push_subtree(
+    items: &mut Vec<CleanableItem>,
+    children: &mut HashMap<PathBuf, Vec<(PathBuf, u64)>>,
+    parent: &Path,
+    depth: usize,
+    category: &CleanupCategory,
+) {
+    let mut entries = children.remove(parent).unwrap_or_default();
+    entries.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+
+    for (path, size) in entries {
+        let modified = std::fs::symlink_metadata(&path)
+            .and_then(|m| m.modified())
+            .map(DateTime::<Local>::from)
+            .unwrap_or_else(|_| Local::now());
+
+        items.push(CleanableItem {
+            path: path.clone(),
+            size,
+            category: *category,
+            modified,
+            selected: false,
+            note: None,
+            remove_command: None,
+            depth,
+            needs_privileges: false,
+        });
+        push_subtree(items, children, &path, depth + 1, category);
+    }
+}
+
+/// Flag items the current user can't remove, and every parent of one,
+/// since removing the parent would stop partway
+fn mark_privileged(items: &mut [CleanableItem]) {
+    for index in 0..items.len() {
+        let item = &mut items[index];
+        if !item.needs_privileges && item.remove_command.is_none() {
+            item.needs_privileges = !privileges::can_remove(&item.path);
+        }
+        if items[index].needs_privileges {
+            for parent in ancestors(items, index) {
+                items[parent].needs_privileges = true;
+            }
+        }
+    }
+}
+
+/// Drop top-level items that lie inside another top-level item, so no
+/// space is listed twice across categories
+fn drop_nested_roots(items: &mut Vec<CleanableItem>) {
+    let roots: Vec<PathBuf> = items.iter().filter(|i| i.depth == 0).map(|i| i.path.clone()).collect();
+
+    let mut index = 0;
+    while index < items.len() {
+        let path = &items[index].path;
+        let nested = items[index].depth == 0 && roots.iter().any(|r| r != path && path.starts_with(r));
+        if nested {
+            remove_subtree(items, index);
+        } else {
+            index += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, size: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0u8; size]).unwrap();
+    }
+
+    #[test]
+    fn test_scan_path_builds_rollups() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let kb = 1024;
+
+        write(&root.join("app/blobs/a"), 150 * kb);
+        write(&root.join("app/blobs/deep/er/still/b"), 50 * kb);
+        write(&root.join("app/index"), 60 * kb);
+        write(&root.join("tiny/x"), kb);
+        write(&root.join("big.bin"), 120 * kb);
+
+        let items = CleanupScanner::new()
+            .scan_path(root, &CleanupCategory::UserCaches)
+            .unwrap();
+        let tree: Vec<(PathBuf, u64, usize)> = items
+            .iter()
+            .map(|i| (i.path.strip_prefix(root).unwrap().to_path_buf(), i.size / 1024, i.depth))
+            .collect();
+
+        assert_eq!(
+            tree,
+            vec![
+                (PathBuf::from("app"), 260, 0),
+                (PathBuf::from("app/blobs"), 200, 1),
+                (PathBuf::from("big.bin"), 120, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drop_nested_roots() {
+        let item = |path: &str, depth| CleanableItem {
+            path: PathBuf::from(path),
+            size: 10,
+            category: CleanupCategory::UserCaches,
+            modified: Local::now(),
+            selected: false,
+            note: None,
+            remove_command: None,
+            depth,
+            needs_privileges: false,
+        };
+        let mut items = vec![
+            item("/home/me/.cache", 0),
+            item("/home/me/.cache/pip", 1),
+            item("/home/me/.cache/pip", 0),
+            item("/home/me/.npm", 0),
+        ];
+
+        drop_nested_roots(&mut items);
+
+        let paths: Vec<&Path> = items.iter().map(|i| i.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![Path::new("/home/me/.cache"), Path::new("/home/me/.cache/pip"), Path::new("/home/me/.npm")]
+        );
+    }
+
+    #[test]
+    fn test_scan_path_lists_unreadable_folders() {
+        use std::os::unix::fs::PermissionsExt;
+
+        if privileges::is_root() {
+            return;
+        }
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(&root.join("app/big"), 200 * 1024);
+        write(&root.join("app/locked/secret"), 10);
+        fs::set_permissions(root.join("app/locked"), fs::Permissions::from_mode(0o000)).unwrap();
+
+        let mut items = CleanupScanner::new()
+            .scan_path(root, &CleanupCategory::SystemCaches)
+            .unwrap();
+        mark_privileged(&mut items);
+        fs::set_permissions(root.join("app/locked"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        let flagged: Vec<(PathBuf, u64, bool)> = items
+            .iter()
+            .map(|i| (i.path.strip_prefix(root).unwrap().to_path_buf(), i.size, i.needs_privileges))
+            .collect();
+        assert_eq!(
+            flagged,
+            vec![
+                (PathBuf::from("app"), 200 * 1024, true),
+                (PathBuf::from("app/locked"), 0, true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::models::TreeMapItem;
+
+/// Broad content class of a file, used for previews and size breakdowns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ContentClass {
+    Video,
+    Image,
+    Audio,
+    Archive,
+    Document,
+    Source,
+    Binary,
+    Cache,
+    #[default]
+    Other,
+}
+
+impl ContentClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Video => "Video",
+            Self::Image => "Images",
+            Self::Audio => "Audio",
+            Self::Archive => "Archives",
+            Self::Document => "Documents",
+            Self::Source => "Source code",
+            Self::Binary => "Binaries",
+            Self::Cache => "Caches & build output",
+            Self::Other => "Other",
+        }
+    }
+}
+
+pub fn is_text_extension(ext: &str) -> bool {
+    is_source_extension(ext) || is_document_text_extension(ext)
+}
+
+pub fn is_image_extension(ext: &str) -> bool {
+    matches!(
+        ext,
+        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "svg" | "webp" | "ico" | "tiff" | "tif" | "heic"
+            | "raw" | "cr2" | "nef" | "psd"
+    )
+}
+
+pub fn is_video_extension(ext: &str) -> bool {
+    matches!(
+        ext,
+        "mp4" | "avi" | "mkv" | "mov" | "wmv" | "flv" | "webm" | "m4v" | "mpg" | "mpeg" | "m2ts"
+    )
+}
+
+pub fn is_audio_extension(ext: &str) -> bool {
+    matches!(
+        ext,
+        "mp3" | "wav" | "flac" | "aac" | "ogg" | "m4a" | "wma" | "opus" | "alac" | "ape"
+    )
+}
+
+pub fn is_archive_extension(ext: &str) -> bool {
+    matches!(
+        ext,
+        "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "jar" | "war"
+            | "deb" | "rpm" | "dmg" | "iso" | "pkg" | "apk" | "whl" | "crate" | "snap"
+    )
+}
+
+fn is_source_extension(ext: &str) -> bool {
+    matches!(
+        ext,
+        "rs" | "toml" | "json" | "xml" | "yaml" | "yml" | "sh" | "bash" | "zsh" | "py" | "js"
+            | "mjs" | "jsx" | "ts" | "tsx" | "html" | "css" | "scss" | "c" | "cc" | "cpp" | "h" | "hpp"
+            | "java" | "go" | "rb" | "php" | "swift" | "kt" | "kts" | "cs" | "lua" | "sql"
+            | "gradle" | "cmake" | "mk"
+    )
+}
+
+fn is_document_text_extension(ext: &str) -> bool {
+    matches!(
+        ext,
+        "txt" | "md" | "rst" | "log" | "csv" | "tsv" | "ini" | "conf" | "cfg" | "srt" | "vtt"
+            | "ass" | "sub"
+    )
+}
+
+fn is_document_extension(ext: &str) -> bool {
+    is_document_text_extension(ext)
+        || matches!(
+            ext,
+            "pdf" | "doc" | "docx" | "odt" | "rtf" | "xls" | "xlsx" | "ods" | "ppt" | "pptx"
+                | "odp" | "epub"
+        )
+}
+
+fn is_binary_extension(ext: &str) -> bool {
+    matches!(
+        ext,
+        "so" | "dylib" | "dll" | "exe" | "o" | "a" | "lib" | "rlib" | "rmeta" | "class" | "pyc"
+            | "wasm" | "bin" | "app"
+    )
+}
+
+/// Directory names whose contents are regenerable caches or build output
+fn is_cache_component(name: &str) -> bool {
+    matches!(
+        name,
+        ".cache" | "cache" | "Cache" | "Caches" | "__pycache__" | "node_modules" | "target"
+            | ".gradle" | "DerivedData" | ".npm" | ".yarn" | ".venv" | "CMakeFiles"
+    )
+}
+
+/// Classify by extension alone (lowercase, without the dot)
+pub fn classify_extension(ext: &str) -> Option<ContentClass> {
+    if is_video_extension(ext) {
+        Some(ContentClass::Video)
+    } else if is_image_extension(ext) {
+        Some(ContentClass::Image)
+    } else if is_audio_extension(ext) {
+        Some(ContentClass::Audio)
+    } else if is_archive_extension(ext) {
+        Some(ContentClass::Archive)
+    } else if is_source_extension(ext) {
+        Some(ContentClass::Source)
+    } else if is_document_extension(ext) {
+        Some(ContentClass::Document)
+    } else if is_binary_extension(ext) {
+        Some(ContentClass::Binary)
+    } else {
+        None
+    }
+}
+
+/// Classify from the first bytes of a file (magic numbers)
+pub fn classify_magic(header: &[u8]) -> Option<ContentClass> {
+    let starts = |magic: &[u8]| header.starts_with(magic);
+    let at = |offset: usize, magic: &[u8]| {
+        header.len() >= offset + magic.len() && &header[offset..offset + magic.len()] == magic
+    };
+
+    if starts(b"\x89PNG") || starts(b"\xFF\xD8\xFF") || starts(b"GIF8") || starts(b"II*\0")
+        || starts(b"MM\0*") || (starts(b"RIFF") && at(8, b"WEBP"))
+    {
+        Some(ContentClass::Image)
+    } else if starts(b"\x1A\x45\xDF\xA3") || starts(b"FLV") || (starts(b"RIFF") && at(8, b"AVI ")) {
+        Some(ContentClass::Video)
+    } else if at(4, b"ftyp") {
+        // ISO media: M4A/M4B brands are audio-only
+        if at(8, b"M4A") || at(8, b"M4B") {
+            Some(ContentClass::Audio)
+        } else {
+            Some(ContentClass::Video)
+        }
+    } else if starts(b"ID3") || starts(b"fLaC") || starts(b"OggS") || starts(b"\xFF\xFB")
+        || starts(b"\xFF\xF3") || (starts(b"RIFF") && at(8, b"WAVE"))
+    {
+        Some(ContentClass::Audio)
+    } else if starts(b"PK\x03\x04") || starts(b"\x1F\x8B") || starts(b"BZh")
+        || starts(b"\xFD7zXZ\0") || starts(b"\x28\xB5\x2F\xFD") || starts(b"7z\xBC\xAF\x27\x1C")
+        || starts(b"Rar!") || at(257, b"ustar")
+    {
+        Some(ContentClass::Archive)
+    } else if starts(b"\x7FELF") || starts(b"\xFE\xED\xFA\xCE") || starts(b"\xFE\xED\xFA\xCF")
+        || starts(b"\xCE\xFA\xED\xFE") || starts(b"\xCF\xFA\xED\xFE") || starts(b"\xCA\xFE\xBA\xBE")
+        || starts(b"MZ") || starts(b"\0asm")
+    {
+        Some(ContentClass::Binary)
+    } else if starts(b"%PDF") {
+        Some(ContentClass::Document)
+    } else if starts(b"#!") {
+        Some(ContentClass::Source)
+    } else if !header.is_empty() && is_probably_text(header) {
+        Some(ContentClass::Document)
+    } else {
+        None
+    }
+}
+
+/// Heuristic: no NUL bytes and valid UTF-8 (allowing a truncated final character)
+pub fn is_probably_text(bytes: &[u8]) -> bool {
+    if bytes.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// Read the first bytes of a file for magic-number sniffing
+pub fn read_header(path: &Path, len: usize) -> Option<Vec<u8>> {
+    let file = File::open(path).ok()?;
+    let mut header = Vec::with_capacity(len);
+    file.take(len as u64).read_to_end(&mut header).ok()?;
+    Some(header)
+}
+
+/// Classify a file: cache/build locations first, then extension, then magic bytes.
+/// Only directories below `root` count as cache locations, so scanning e.g.
+/// `/srv/target/app` doesn't mark every file in it as cache.
+pub fn classify(path: &Path, root: &Path) -> ContentClass {
+    if in_cache(path, root) {
+        return ContentClass::Cache;
+    }
+
+    classify_type(path)
+}
+
+/// Like `classify`, but never touches the disk; files with an unknown
+/// extension are `Other`. Used for imported trees, whose paths aren't local.
+pub fn classify_name(path: &Path, root: &Path) -> ContentClass {
+    if in_cache(path, root) {
+        return ContentClass::Cache;
+    }
+
+    classify_extension(&extension_of(path)).unwrap_or(ContentClass::Other)
+}
+
+fn in_cache(path: &Path, root: &Path) -> bool {
+    path.parent()
+        .and_then(|parent| parent.strip_prefix(root).ok())
+        .map(|relative| {
+            relative
+                .components()
+                .any(|c| is_cache_component(&c.as_os_str().to_string_lossy()))
+        })
+        .unwrap_or(false)
+}
+
+/// Classify by file type only (extension, then magic bytes), ignoring location
+pub fn classify_type(path: &Path) -> ContentClass {
+    let ext = extension_of(path);
+    if let Some(class) = classify_extension(&ext) {
+        return class;
+    }
+
+    read_header(path, 512)
+        .and_then(|header| classify_magic(&header))
+        .unwrap_or(ContentClass::Other)
+}
+
+pub fn extension_of(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+/// Bytes and file count for one bucket of a breakdown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsageTotals {
+    pub bytes: u64,
+    pub count: usize,
+}
+
+/// Aggregated content breakdown for a directory subtree
+#[derive(Debug, Clone, Default)]
+pub struct ContentBreakdown {
+    pub by_class: Vec<(ContentClass, UsageTotals)>,
+    pub by_extension: Vec<(String, UsageTotals)>,
+    pub total: UsageTotals,
+    /// Size of directories past the scan depth, whose files weren't listed
+    pub unscanned_bytes: u64,
+}
+
+impl ContentBreakdown {
+    /// Build a breakdown from the files in a scanned TreeMap subtree; `root`
+    /// is the top of the scan, which cache detection is relative to. With
+    /// `read_files` unset, files are classified by name only.
+    pub fn from_tree(item: &TreeMapItem, root: &Path, read_files: bool) -> Self {
+        let mut classes: HashMap<ContentClass, UsageTotals> = HashMap::new();
+        let mut extensions: HashMap<String, UsageTotals> = HashMap::new();
+        let mut breakdown = Self::default();
+
+        let mut stack = vec![item];
+        while let Some(current) = stack.pop() {
+            if current.is_file {
+                let class = if read_files {
+                    classify(&current.path, root)
+                } else {
+                    classify_name(&current.path, root)
+                };
+                let ext = match extension_of(&current.path) {
+                    ext if ext.is_empty() => "(none)".to_string(),
+                    ext => ext,
+                };
+
+                for totals in [
+                    classes.entry(class).or_default(),
+                    extensions.entry(ext).or_default(),
+                    &mut breakdown.total,
+                ] {
+                    totals.bytes += current.size;
+                    totals.count += 1;
+                }
+            } else if current.children.is_empty() {
+                breakdown.unscanned_bytes += current.size;
+            } else {
+                stack.extend(current.children.iter());
+            }
+        }
+
+        breakdown.by_class = classes.into_iter().collect();
+        breakdown.by_class.sort_by_key(|(_, t)| std::cmp::Reverse(t.bytes));
+        breakdown.by_extension = extensions.into_iter().collect();
+        breakdown.by_extension.sort_by_key(|(_, t)| std::cmp::Reverse(t.bytes));
+
+        breakdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_classify_by_extension() {
+        assert_eq!(classify_extension("mkv"), Some(ContentClass::Video));
+        assert_eq!(classify_extension("rs"), Some(ContentClass::Source));
+        assert_eq!(classify_extension("pdf"), Some(ContentClass::Document));
+        assert_eq!(classify_extension("xyz"), None);
+    }
+
+    #[test]
+    fn test_classify_magic() {
+        assert_eq!(classify_magic(b"\x89PNG\r\n\x1a\n"), Some(ContentClass::Image));
+        assert_eq!(classify_magic(b"\x7FELF\x02\x01"), Some(ContentClass::Binary));
+        assert_eq!(classify_magic(b"\0\0\0\x20ftypisom"), Some(ContentClass::Video));
+        assert_eq!(classify_magic(b"\0\0\0\x20ftypM4A "), Some(ContentClass::Audio));
+        assert_eq!(classify_magic(b"PK\x03\x04"), Some(ContentClass::Archive));
+        assert_eq!(classify_magic(b"#!/bin/sh\n"), Some(ContentClass::Source));
+        assert_eq!(classify_magic(b"\x00\x01\x02\xFF"), None);
+    }
+
+    #[test]
+    fn test_classify_sniffs_files_without_extension() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("mystery");
+        fs::write(&file, b"\x7FELF\x02\x01\x01\0\0\0").unwrap();
+
+        assert_eq!(classify(&file, temp_dir.path()), ContentClass::Binary);
+    }
+
+    #[test]
+    fn test_cache_components_above_root_are_ignored() {
+        let root = Path::new("/srv/target/app");
+        assert_eq!(classify(Path::new("/srv/target/app/song.mp3"), root), ContentClass::Audio);
+        assert_eq!(classify(Path::new("/srv/target/app/cache/song.mp3"), root), ContentClass::Cache);
+    }
+
+    #[test]
+    fn test_classify_name_does_not_read_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("mystery");
+        fs::write(&file, b"\x7FELF\x02\x01\x01\0\0\0").unwrap();
+
+        assert_eq!(classify_name(&file, temp_dir.path()), ContentClass::Other);
+        assert_eq!(classify_name(&temp_dir.path().join("a.mkv"), temp_dir.path()), ContentClass::Video);
+    }
+
+    #[test]
+    fn test_breakdown_from_tree() {
+        let mut root = TreeMapItem::new(PathBuf::from("/p"), 0, false);
+        root.children.push(TreeMapItem::new(PathBuf::from("/p/a.mp4"), 700, true));
+        root.children.push(TreeMapItem::new(PathBuf::from("/p/b.mp4"), 100, true));
+        root.children.push(TreeMapItem::new(PathBuf::from("/p/target/debug/app.o"), 150, true));
+        root.children.push(TreeMapItem::new(PathBuf::from("/p/deep"), 50, false));
+        root.size = 1000;
+
+        let breakdown = ContentBreakdown::from_tree(&root, Path::new("/p"), true);
+
+        assert_eq!(breakdown.total, UsageTotals { bytes: 950, count: 3 });
+        assert_eq!(breakdown.by_class[0], (ContentClass::Video, UsageTotals { bytes: 800, count: 2 }));
+        assert_eq!(breakdown.by_class[1].0, ContentClass::Cache);
+        assert_eq!(breakdown.by_extension[0].0, "mp4");
+        assert_eq!(breakdown.unscanned_bytes, 50);
+    }
+}
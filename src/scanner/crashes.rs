@@ -0,0 +1,536 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::models::{CleanableItem, CleanupCategory};
+use crate::scanner::errors::ScanErrors;
+use crate::system::privileges;
+
+/// Directories never worth descending into while looking for stray core files
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "venv", "__pycache__", "Library", "Applications"];
+
+/// Where the system collects dumps and reports
+#[cfg(target_os = "linux")]
+const DUMP_DIRS: &[&str] = &["/var/lib/systemd/coredump", "/var/lib/apport/coredump", "/var/crash"];
+#[cfg(target_os = "macos")]
+const DUMP_DIRS: &[&str] = &["/cores", "/Library/Logs/DiagnosticReports"];
+
+/// Names crash reporters give their directories (Chrome, Electron apps)
+const REPORTER_DIRS: &[&str] = &["Crashpad", "Crash Reports"];
+
+/// ELF note type holding the process name and arguments
+const NT_PRPSINFO: u32 = 3;
+
+/// Note segments larger than this aren't read
+const MAX_NOTE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Finds core dumps (stray `core` files in working directories and the
+/// system's dump directories), crash reports and crash-reporter databases
+pub struct CrashDumpScanner {
+    /// Where to look for stray core files; defaults to the home directory
+    roots: Vec<PathBuf>,
+    max_depth: usize,
+    errors: ScanErrors,
+}
+
+impl CrashDumpScanner {
+    pub fn new() -> Self {
+        Self {
+            roots: dirs::home_dir().into_iter().collect(),
+            max_depth: 8,
+            errors: ScanErrors::new(),
+        }
+    }
+
+    pub fn with_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.roots = roots;
+        self
+    }
+
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Every directory this scanner reports items under
+    pub fn roots(&self) -> Vec<PathBuf> {
+        let mut roots = self.roots.clone();
+        roots.extend(DUMP_DIRS.iter().map(PathBuf::from));
+        if let Some(home) = dirs::home_dir() {
+            roots.extend(app_data_dirs(&home));
+            #[cfg(target_os = "macos")]
+            roots.push(home.join("Library/Logs/DiagnosticReports"));
+        }
+        roots
+    }
+
+    pub fn scan(&self) -> Result<Vec<CleanableItem>> {
+        let mut items = Vec::new();
+
+        for root in &self.roots {
+            items.extend(self.scan_core_files(root));
+        }
+        for dir in DUMP_DIRS {
+            items.extend(scan_dump_dir(Path::new(dir), &self.errors));
+        }
+        if let Some(home) = dirs::home_dir() {
+            items.extend(scan_reporter_dirs(&app_data_dirs(&home), &self.errors));
+            #[cfg(target_os = "macos")]
+            items.extend(scan_dump_dir(&home.join("Library/Logs/DiagnosticReports"), &self.errors));
+        }
+
+        items.sort_by_key(|i| std::cmp::Reverse(i.size));
+        Ok(items)
+    }
+
+    /// `core` and `core.<pid>` files left by debugging sessions, confirmed by
+    /// their header so a source file named `core` is never offered
+    fn scan_core_files(&self, root: &Path) -> Vec<CleanableItem> {
+        let walker = WalkDir::new(root)
+            .max_depth(self.max_depth)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !is_skipped_dir(e));
+
+        walker
+            .filter_map(|e| self.errors.ok(e))
+            .filter(|e| e.file_type().is_file() && is_core_name(&e.file_name().to_string_lossy()))
+            .filter(|e| is_core_file(e.path()))
+            .filter_map(|e| {
+                let note = match core_executable(e.path()) {
+                    Some(exe) => format!("Core dump of {}", exe),
+                    None => "Core dump".to_string(),
+                };
+                file_item(e.path(), note)
+            })
+            .collect()
+    }
+}
+
+/// Dumps and reports in a system directory, one item per file
+fn scan_dump_dir(dir: &Path, errors: &ScanErrors) -> Vec<CleanableItem> {
+    let mut unreadable = Vec::new();
+    let mut items: Vec<CleanableItem> = WalkDir::new(dir)
+        .max_depth(2)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| {
+            e.map_err(|err| {
+                unreadable.extend(privileges::permission_denied(&err));
+                errors.record_walk(&err);
+            })
+            .ok()
+        })
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy();
+            let exe = if name.ends_with(".crash") || name.ends_with(".ips") {
+                report_executable(e.path())
+            } else if is_core_file(e.path()) {
+                core_executable(e.path())
+            } else {
+                coredump_name_executable(&name)
+            };
+            let kind = if name.starts_with("core") { "Core dump" } else { "Crash report" };
+            let note = match exe {
+                Some(exe) => format!("{} of {}", kind, exe),
+                None => kind.to_string(),
+            };
+            file_item(e.path(), note)
+        })
+        .collect();
+
+    items.extend(
+        unreadable
+            .into_iter()
+            .map(|dir| CleanableItem::unreadable(dir, CleanupCategory::CrashDumps, 0)),
+    );
+    items
+}
+
+/// Crashpad / "Crash Reports" databases, one item per app
+fn scan_reporter_dirs(app_dirs: &[PathBuf], errors: &ScanErrors) -> Vec<CleanableItem> {
+    let mut items = Vec::new();
+
+    for base in app_dirs {
+        // <base>/<App>/Crashpad, or one level deeper (<base>/Google/Chrome/Crashpad)
+        for entry in WalkDir::new(base)
+            .min_depth(2)
+            .max_depth(3)
+            .follow_links(false)
+            .into_iter()
+            .filter_map(|e| errors.ok(e))
+        {
+            let name = entry.file_name().to_string_lossy();
+            if !entry.file_type().is_dir() || !REPORTER_DIRS.contains(&name.as_ref()) {
+                continue;
+            }
+
+            let (size, dumps) = dump_stats(entry.path(), errors);
+            if size == 0 {
+                continue;
+            }
+            let app = entry
+                .path()
+                .strip_prefix(base)
+                .ok()
+                .and_then(|p| p.parent())
+                .map(|p| p.display().to_string())
+                .unwrap_or_default();
+
+            items.push(CleanableItem {
+                path: entry.path().to_path_buf(),
+                size,
+                category: CleanupCategory::CrashDumps,
+                modified: modified_time(entry.path()),
+                selected: false,
+                note: Some(format!(
+                    "Crash reporter of {} ({} dump{})",
+                    app,
+                    dumps,
+                    if dumps == 1 { "" } else { "s" }
+                )),
+                remove_command: None,
+                depth: 0,
+                needs_privileges: false,
+            });
+        }
+    }
+
+    items
+}
+
+#[cfg(target_os = "linux")]
+fn app_data_dirs(home: &Path) -> Vec<PathBuf> {
+    vec![home.join(".config")]
+}
+
+#[cfg(target_os = "macos")]
+fn app_data_dirs(home: &Path) -> Vec<PathBuf> {
+    vec![home.join("Library/Application Support")]
+}
+
+fn file_item(path: &Path, note: String) -> Option<CleanableItem> {
+    let metadata = fs::metadata(path).ok()?;
+    Some(CleanableItem {
+        path: path.to_path_buf(),
+        size: metadata.len(),
+        category: CleanupCategory::CrashDumps,
+        modified: metadata
+            .modified()
+            .map(DateTime::<Local>::from)
+            .unwrap_or_else(|_| Local::now()),
+        selected: false,
+        note: Some(note),
+        remove_command: None,
+        depth: 0,
+        needs_privileges: false,
+    })
+}
+
+fn is_skipped_dir(entry: &walkdir::DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+    entry.file_type().is_dir() && (name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()))
+}
+
+/// `core` or `core.<pid>`
+pub fn is_core_name(name: &str) -> bool {
+    match name.strip_prefix("core") {
+        Some("") => true,
+        Some(rest) => rest
+            .strip_prefix('.')
+            .is_some_and(|pid| !pid.is_empty() && pid.bytes().all(|b| b.is_ascii_digit())),
+        None => false,
+    }
+}
+
+/// ELF `ET_CORE` or Mach-O `MH_CORE`
+pub fn is_core_file(path: &Path) -> bool {
+    let mut header = [0u8; 18];
+    let read = File::open(path).and_then(|mut f| f.read_exact(&mut header));
+    if read.is_err() {
+        return false;
+    }
+
+    if header.starts_with(b"\x7fELF") {
+        let big_endian = header[5] == 2;
+        read_u16(&header[16..18], big_endian) == 4
+    } else if header[..4] == [0xcf, 0xfa, 0xed, 0xfe] || header[..4] == [0xce, 0xfa, 0xed, 0xfe] {
+        // Little-endian Mach-O; filetype follows magic, cputype and cpusubtype
+        let mut filetype = [0u8; 4];
+        let read = File::open(path).and_then(|mut f| {
+            f.seek(SeekFrom::Start(12))?;
+            f.read_exact(&mut filetype)
+        });
+        read.is_ok() && u32::from_le_bytes(filetype) == 4
+    } else {
+        false
+    }
+}
+
+/// Command line (or name) of the process in an ELF core, from its
+/// `NT_PRPSINFO` note
+pub fn core_executable(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let mut header = [0u8; 64];
+    file.read_exact(&mut header).ok()?;
+    if !header.starts_with(b"\x7fELF") {
+        return None;
+    }
+
+    let is_64 = header[4] == 2;
+    let be = header[5] == 2;
+    let (phoff, phentsize, phnum) = if is_64 {
+        (read_u64(&header[32..40], be), read_u16(&header[54..56], be), read_u16(&header[56..58], be))
+    } else {
+        (read_u32(&header[28..32], be) as u64, read_u16(&header[42..44], be), read_u16(&header[44..46], be))
+    };
+
+    let mut phdrs = vec![0u8; phentsize as usize * phnum as usize];
+    file.seek(SeekFrom::Start(phoff)).ok()?;
+    file.read_exact(&mut phdrs).ok()?;
+
+    for phdr in phdrs.chunks_exact(phentsize as usize) {
+        // PT_NOTE
+        if read_u32(&phdr[0..4], be) != 4 {
+            continue;
+        }
+        let (offset, size) = if is_64 {
+            (read_u64(&phdr[8..16], be), read_u64(&phdr[32..40], be))
+        } else {
+            (read_u32(&phdr[4..8], be) as u64, read_u32(&phdr[16..20], be) as u64)
+        };
+        if size > MAX_NOTE_SIZE {
+            continue;
+        }
+
+        let mut notes = vec![0u8; size as usize];
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        file.read_exact(&mut notes).ok()?;
+
+        if let Some(desc) = find_note(&notes, NT_PRPSINFO, be) {
+            // pr_fname follows state, flags, uid/gid and four pids
+            let fname_at = if is_64 { 40 } else { 28 };
+            let fname = c_string(desc.get(fname_at..fname_at + 16)?);
+            let psargs = c_string(desc.get(fname_at + 16..).unwrap_or_default());
+            return Some(if psargs.is_empty() { fname } else { psargs }).filter(|s| !s.is_empty());
+        }
+    }
+
+    None
+}
+
+/// Descriptor of the first note of type `kind`
+fn find_note(notes: &[u8], kind: u32, be: bool) -> Option<&[u8]> {
+    let align = |n: usize| (n + 3) & !3;
+    let mut pos = 0;
+
+    while pos + 12 <= notes.len() {
+        let namesz = read_u32(&notes[pos..pos + 4], be) as usize;
+        let descsz = read_u32(&notes[pos + 4..pos + 8], be) as usize;
+        let note_type = read_u32(&notes[pos + 8..pos + 12], be);
+        let desc_start = pos + 12 + align(namesz);
+        let desc_end = desc_start.checked_add(descsz)?;
+        if desc_end > notes.len() {
+            return None;
+        }
+        if note_type == kind {
+            return Some(&notes[desc_start..desc_end]);
+        }
+        pos = desc_start + align(descsz);
+    }
+
+    None
+}
+
+/// systemd-coredump and apport encode the executable in the file name:
+/// `core.<comm>.<uid>.<boot-id>.<pid>.<time>.zst`, `core._usr_bin_foo.<uid>...`
+pub fn coredump_name_executable(name: &str) -> Option<String> {
+    let comm = name.strip_prefix("core.")?.split('.').next()?;
+    if comm.is_empty() || comm.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let comm = comm.replace("\\x2f", "/");
+    Some(if comm.starts_with("_usr_") || comm.starts_with("_opt_") {
+        comm.replace('_', "/")
+    } else {
+        comm
+    })
+}
+
+/// Executable named in an apport `.crash` or macOS `.crash`/`.ips` report
+pub fn report_executable(path: &Path) -> Option<String> {
+    let mut head = Vec::new();
+    File::open(path).ok()?.take(16 * 1024).read_to_end(&mut head).ok()?;
+    let head = String::from_utf8_lossy(&head);
+
+    for line in head.lines() {
+        for key in ["ExecutablePath:", "Path:", "Process:"] {
+            if let Some(value) = line.strip_prefix(key) {
+                return Some(value.trim().to_string());
+            }
+        }
+        // .ips reports start with a one-line JSON header
+        if line.starts_with('{') {
+            if let Ok(json) = serde_json::from_str::<serde_json::Value>(line) {
+                if let Some(name) = json["app_name"].as_str().or(json["name"].as_str()) {
+                    return Some(name.to_string());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Total size and number of minidumps under a reporter directory
+fn dump_stats(dir: &Path, errors: &ScanErrors) -> (u64, usize) {
+    WalkDir::new(dir)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| errors.ok(e))
+        .filter(|e| e.file_type().is_file())
+        .fold((0, 0), |(size, dumps), e| {
+            let len = e.metadata().map(|m| m.len()).unwrap_or(0);
+            let is_dump = e.path().extension().is_some_and(|ext| ext == "dmp");
+            (size + len, dumps + is_dump as usize)
+        })
+}
+
+fn modified_time(path: &Path) -> DateTime<Local> {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(DateTime::<Local>::from)
+        .unwrap_or_else(|_| Local::now())
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+fn read_u16(bytes: &[u8], be: bool) -> u16 {
+    let bytes = [bytes[0], bytes[1]];
+    if be { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) }
+}
+
+fn read_u32(bytes: &[u8], be: bool) -> u32 {
+    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+    if be { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+}
+
+fn read_u64(bytes: &[u8], be: bool) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[..8]);
+    if be { u64::from_be_bytes(buf) } else { u64::from_le_bytes(buf) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Minimal 64-bit little-endian ELF core with one PT_NOTE holding NT_PRPSINFO
+    fn fake_core(fname: &str, psargs: &str) -> Vec<u8> {
+        let mut prpsinfo = vec![0u8; 136];
+        prpsinfo[40..40 + fname.len()].copy_from_slice(fname.as_bytes());
+        prpsinfo[56..56 + psargs.len()].copy_from_slice(psargs.as_bytes());
+
+        let mut note = Vec::new();
+        note.extend_from_slice(&5u32.to_le_bytes());
+        note.extend_from_slice(&(prpsinfo.len() as u32).to_le_bytes());
+        note.extend_from_slice(&NT_PRPSINFO.to_le_bytes());
+        note.extend_from_slice(b"CORE\0\0\0\0");
+        note.extend_from_slice(&prpsinfo);
+
+        let mut elf = vec![0u8; 64];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[4] = 2; // 64-bit
+        elf[5] = 1; // little-endian
+        elf[16..18].copy_from_slice(&4u16.to_le_bytes()); // ET_CORE
+        elf[32..40].copy_from_slice(&64u64.to_le_bytes()); // e_phoff
+        elf[54..56].copy_from_slice(&56u16.to_le_bytes()); // e_phentsize
+        elf[56..58].copy_from_slice(&1u16.to_le_bytes()); // e_phnum
+
+        let mut phdr = vec![0u8; 56];
+        phdr[0..4].copy_from_slice(&4u32.to_le_bytes()); // PT_NOTE
+        phdr[8..16].copy_from_slice(&120u64.to_le_bytes()); // p_offset
+        phdr[32..40].copy_from_slice(&(note.len() as u64).to_le_bytes()); // p_filesz
+        elf.extend(phdr);
+        elf.extend(note);
+        elf
+    }
+
+    #[test]
+    fn test_core_names() {
+        assert!(is_core_name("core"));
+        assert!(is_core_name("core.12345"));
+        assert!(!is_core_name("core.rs"));
+        assert!(!is_core_name("core."));
+        assert!(!is_core_name("corelib"));
+    }
+
+    #[test]
+    fn test_core_executable() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("core.4242");
+        fs::write(&path, fake_core("myserver", "./myserver --port 8080")).unwrap();
+
+        assert!(is_core_file(&path));
+        assert_eq!(core_executable(&path).as_deref(), Some("./myserver --port 8080"));
+    }
+
+    #[test]
+    fn test_only_real_cores_in_projects() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("proj/src")).unwrap();
+        fs::write(root.join("proj/core.99"), fake_core("proj", "")).unwrap();
+        // A source module named `core`, not a dump
+        fs::write(root.join("proj/src/core"), "fn main() {}").unwrap();
+
+        let scanner = CrashDumpScanner::new().with_roots(vec![root.to_path_buf()]);
+        let items = scanner.scan_core_files(root);
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, root.join("proj/core.99"));
+        assert_eq!(items[0].note.as_deref(), Some("Core dump of proj"));
+    }
+
+    #[test]
+    fn test_report_executables() {
+        assert_eq!(
+            coredump_name_executable("core.firefox.1000.0123abcd.4242.1700000000000000.zst").as_deref(),
+            Some("firefox")
+        );
+        assert_eq!(
+            coredump_name_executable("core._usr_bin_python3.1000.uuid.77.1700000000").as_deref(),
+            Some("/usr/bin/python3")
+        );
+
+        let temp_dir = TempDir::new().unwrap();
+        let report = temp_dir.path().join("_usr_bin_gedit.1000.crash");
+        fs::write(&report, "ProblemType: Crash\nDate: today\nExecutablePath: /usr/bin/gedit\n").unwrap();
+        assert_eq!(report_executable(&report).as_deref(), Some("/usr/bin/gedit"));
+    }
+
+    #[test]
+    fn test_reporter_dirs() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = temp_dir.path().join(".config");
+        fs::create_dir_all(config.join("Slack/Crashpad/completed")).unwrap();
+        fs::write(config.join("Slack/Crashpad/completed/a.dmp"), vec![0u8; 100]).unwrap();
+        fs::write(config.join("Slack/Crashpad/settings.dat"), vec![0u8; 10]).unwrap();
+        fs::create_dir_all(config.join("Slack/Cache")).unwrap();
+
+        let items = scan_reporter_dirs(std::slice::from_ref(&config), &ScanErrors::new());
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, config.join("Slack/Crashpad"));
+        assert_eq!(items[0].size, 110);
+        assert_eq!(items[0].note.as_deref(), Some("Crash reporter of Slack (1 dump)"));
+    }
+}
@@ -132,21 +132,7 @@ impl DuplicateScanner {
 
     /// Calculate SHA-256 hash of a file
     fn hash_file(&self, path: &Path) -> Result<String> {
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-        let mut hasher = Sha256::new();
-        let mut buffer = [0; 8192]; // 8KB buffer
-
-        loop {
-            let bytes_read = reader.read(&mut buffer)?;
-            if bytes_read == 0 {
-                break;
-            }
-            hasher.update(&buffer[..bytes_read]);
-        }
-
-        let hash = hasher.finalize();
-        Ok(format!("{:x}", hash))
+        sha256_file(path)
     }
 
     /// Calculate total duplicate space across all duplicate groups
@@ -155,6 +141,29 @@ impl DuplicateScanner {
     }
 }
 
+/// Stream the contents of a file through `consume`, a buffer at a time
+pub fn read_chunks(path: &Path, mut consume: impl FnMut(&[u8])) -> Result<()> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+    let mut buffer = [0; 8192]; // 8KB buffer
+
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        consume(&buffer[..bytes_read]);
+    }
+    Ok(())
+}
+
+/// Lowercase hex SHA-256 of a file's contents
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    read_chunks(path, |chunk| hasher.update(chunk))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,170 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Why a path couldn't be scanned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanErrorKind {
+    PermissionDenied,
+    BrokenSymlink,
+    /// A symlink pointing back into its own ancestors
+    SymlinkLoop,
+    Io,
+}
+
+impl ScanErrorKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PermissionDenied => "Permission denied",
+            Self::BrokenSymlink => "Broken symlink",
+            Self::SymlinkLoop => "Symlink loop",
+            Self::Io => "I/O error",
+        }
+    }
+}
+
+/// A path a scan skipped, so its result is known to be incomplete
+#[derive(Debug, Clone)]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub kind: ScanErrorKind,
+    pub message: String,
+}
+
+impl ScanError {
+    pub fn from_walkdir(err: &walkdir::Error) -> Self {
+        let path = err.path().map(Path::to_path_buf).unwrap_or_default();
+        if err.loop_ancestor().is_some() {
+            return Self {
+                path,
+                kind: ScanErrorKind::SymlinkLoop,
+                message: err.to_string(),
+            };
+        }
+        match err.io_error() {
+            Some(io) => Self::from_io(&path, io),
+            None => Self {
+                path,
+                kind: ScanErrorKind::Io,
+                message: err.to_string(),
+            },
+        }
+    }
+
+    pub fn from_io(path: &Path, err: &io::Error) -> Self {
+        let is_symlink = fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+        let kind = match err.kind() {
+            ErrorKind::PermissionDenied => ScanErrorKind::PermissionDenied,
+            ErrorKind::NotFound if is_symlink => ScanErrorKind::BrokenSymlink,
+            _ => ScanErrorKind::Io,
+        };
+        Self {
+            path: path.to_path_buf(),
+            kind,
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Errors collected while scanning, shared between a scanner, the scanners it
+/// delegates to and its worker threads. Clones record into the same list.
+#[derive(Debug, Clone, Default)]
+pub struct ScanErrors(Arc<Mutex<Vec<ScanError>>>);
+
+impl ScanErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, error: ScanError) {
+        if let Ok(mut errors) = self.0.lock() {
+            errors.push(error);
+        }
+    }
+
+    /// Record a walker error. Paths that don't exist (yet, or any more) aren't
+    /// missing anything, unless they're a symlink to nowhere.
+    pub fn record_walk(&self, err: &walkdir::Error) {
+        let error = ScanError::from_walkdir(err);
+        let gone = err.io_error().is_some_and(|io| io.kind() == ErrorKind::NotFound);
+        if !gone || error.kind == ScanErrorKind::BrokenSymlink {
+            self.push(error);
+        }
+    }
+
+    /// Like `record_walk`, for plain I/O on `path`
+    pub fn record_io(&self, path: &Path, err: &io::Error) {
+        let error = ScanError::from_io(path, err);
+        if err.kind() != ErrorKind::NotFound || error.kind == ScanErrorKind::BrokenSymlink {
+            self.push(error);
+        }
+    }
+
+    /// Keep a walker entry, recording why it's missing; for `filter_map`
+    pub fn ok<T>(&self, result: walkdir::Result<T>) -> Option<T> {
+        result.map_err(|err| self.record_walk(&err)).ok()
+    }
+
+    /// Like `ok`, for plain I/O on `path`
+    pub fn ok_io<T>(&self, path: &Path, result: io::Result<T>) -> Option<T> {
+        result.map_err(|err| self.record_io(path, &err)).ok()
+    }
+
+    /// Record `entry` if it's a symlink whose target is gone
+    pub fn check_symlink(&self, entry: &walkdir::DirEntry) {
+        if entry.path_is_symlink() {
+            if let Err(err) = fs::metadata(entry.path()) {
+                self.record_io(entry.path(), &err);
+            }
+        }
+    }
+
+    /// The collected errors by path, leaving the list empty. Scanners
+    /// sharing a directory report it once.
+    pub fn take(&self) -> Vec<ScanError> {
+        let mut errors = self
+            .0
+            .lock()
+            .map_or_else(|_| Vec::new(), |mut errors| std::mem::take(&mut *errors));
+        errors.sort_by(|a, b| a.path.cmp(&b.path));
+        errors.dedup_by(|a, b| a.path == b.path && a.kind == b.kind);
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use walkdir::WalkDir;
+
+    #[test]
+    fn test_collects_broken_symlinks_and_walk_errors() {
+        let tmp = TempDir::new().unwrap();
+        std::os::unix::fs::symlink(tmp.path().join("gone"), tmp.path().join("dangling")).unwrap();
+        std::os::unix::fs::symlink(tmp.path(), tmp.path().join("loop")).unwrap();
+
+        let errors = ScanErrors::new();
+        let shared = errors.clone();
+        let entries: Vec<_> = WalkDir::new(tmp.path())
+            .follow_links(true)
+            .into_iter()
+            .filter_map(|e| shared.ok(e))
+            .collect();
+        assert!(!entries.is_empty());
+
+        let mut kinds: Vec<ScanErrorKind> = errors.take().into_iter().map(|e| e.kind).collect();
+        kinds.sort_by_key(|k| k.name());
+        assert_eq!(kinds, vec![ScanErrorKind::BrokenSymlink, ScanErrorKind::SymlinkLoop]);
+        assert!(errors.take().is_empty());
+
+        // Nothing is missed by a path that doesn't exist
+        let missing = tmp.path().join("missing");
+        assert!(errors.ok_io(&missing, fs::metadata(&missing)).is_none());
+        assert!(errors.take().is_empty());
+
+        assert!(errors.ok_io(tmp.path(), fs::read_to_string(tmp.path())).is_none());
+        assert_eq!(errors.take()[0].kind, ScanErrorKind::Io);
+    }
+}
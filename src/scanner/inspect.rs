@@ -0,0 +1,750 @@
+use anyhow::{anyhow, Result};
+use flate2::read::GzDecoder;
+use image::DynamicImage;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::scanner::content::{self, ContentClass};
+
+/// How many bytes of a binary file to show in the hex dump
+const HEX_DUMP_BYTES: usize = 256;
+
+/// How many lines of a text file the preview shows
+const TEXT_PREVIEW_LINES: usize = 50;
+
+/// Only this much of a text file is read for the preview, so a huge file
+/// without newlines isn't read whole
+const TEXT_PREVIEW_BYTES: u64 = 64 * 1024;
+
+/// Archive listings stop after this many entries
+const MAX_ARCHIVE_ENTRIES: usize = 500;
+
+/// Images are downscaled to this size once, then fitted to the preview area
+const THUMBNAIL_SIZE: u32 = 512;
+
+/// Larger images aren't decoded for a thumbnail; their header still gives the
+/// dimensions
+const MAX_IMAGE_PIXELS: u64 = 64 * 1024 * 1024;
+
+/// Memory the decoder may allocate for one image
+const MAX_IMAGE_ALLOC: u64 = 512 * 1024 * 1024;
+
+/// Everything the preview panel knows about a file beyond its name and size
+#[derive(Debug, Clone, Default)]
+pub struct FileInspection {
+    pub path: PathBuf,
+    /// File type from the extension, or the magic bytes for unknown files
+    pub class: ContentClass,
+    /// First lines of a text file; when set, nothing below applies
+    pub text: Option<String>,
+    /// One-line file type, e.g. "ELF 64-bit shared object, x86-64"
+    pub description: Option<String>,
+    /// EXIF and audio/video container fields, in display order
+    pub metadata: Vec<(String, String)>,
+    pub archive: Option<ArchiveListing>,
+    pub hex_dump: Vec<String>,
+    pub thumbnail: Option<DynamicImage>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveListing {
+    pub format: &'static str,
+    pub entries: Vec<ArchiveEntry>,
+    /// True when the listing stopped at MAX_ARCHIVE_ENTRIES
+    pub truncated: bool,
+    pub uncompressed_size: u64,
+}
+
+/// Gather preview data for a file. Slow work (image decoding, archive
+/// walking) happens here so it can run off the UI thread.
+pub fn inspect(path: &Path) -> FileInspection {
+    let mut inspection = FileInspection {
+        path: path.to_path_buf(),
+        ..Default::default()
+    };
+
+    let header = match content::read_header(path, 4096) {
+        Some(header) => header,
+        None => {
+            inspection.error = Some("Unable to read file".to_string());
+            return inspection;
+        }
+    };
+    inspection.description = describe(&header);
+
+    let extension = content::extension_of(path);
+    inspection.class = content::classify_extension(&extension)
+        .or_else(|| content::classify_magic(&header))
+        .unwrap_or(ContentClass::Other);
+
+    let is_text = content::is_text_extension(&extension)
+        || (extension.is_empty() && inspection.class == ContentClass::Source)
+        || (inspection.class == ContentClass::Document && content::is_probably_text(&header));
+    if is_text {
+        inspection.text = Some(read_text_preview(path, TEXT_PREVIEW_LINES));
+        return inspection;
+    }
+
+    let result = match inspection.class {
+        ContentClass::Image => inspect_image(path, &mut inspection),
+        ContentClass::Audio | ContentClass::Video => {
+            inspection.metadata = media_metadata(path, &header).unwrap_or_default();
+            Ok(())
+        }
+        ContentClass::Archive => list_archive(path, &header).map(|listing| {
+            inspection.archive = Some(listing);
+        }),
+        _ => {
+            inspection.hex_dump = hex_dump(&header[..header.len().min(HEX_DUMP_BYTES)]);
+            Ok(())
+        }
+    };
+
+    if let Err(e) = result {
+        inspection.error = Some(e.to_string());
+    }
+    inspection
+}
+
+fn read_text_preview(path: &Path, max_lines: usize) -> String {
+    let mut data = Vec::new();
+    match File::open(path).and_then(|file| file.take(TEXT_PREVIEW_BYTES).read_to_end(&mut data)) {
+        Ok(0) => "(Empty file)".to_string(),
+        Ok(_) => {
+            let text = String::from_utf8_lossy(&data);
+            text.lines().take(max_lines).collect::<Vec<_>>().join("\n")
+        }
+        Err(_) => "(Unable to read file)".to_string(),
+    }
+}
+
+fn inspect_image(path: &Path, inspection: &mut FileInspection) -> Result<()> {
+    inspection.metadata = exif_metadata(path).unwrap_or_default();
+
+    // The header alone says how big the decoded image would be
+    let (width, height) = image::io::Reader::open(path)?
+        .with_guessed_format()?
+        .into_dimensions()?;
+    inspection
+        .metadata
+        .insert(0, ("Dimensions".to_string(), format!("{}x{}", width, height)));
+    if u64::from(width) * u64::from(height) > MAX_IMAGE_PIXELS {
+        return Err(anyhow!("Image too large to preview ({}x{})", width, height));
+    }
+
+    let mut limits = image::io::Limits::default();
+    limits.max_image_width = Some(width);
+    limits.max_image_height = Some(height);
+    limits.max_alloc = Some(MAX_IMAGE_ALLOC);
+    let mut reader = image::io::Reader::open(path)?.with_guessed_format()?;
+    reader.limits(limits);
+    let image = reader.decode()?;
+    inspection.thumbnail = Some(image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE));
+    Ok(())
+}
+
+/// Classic `xxd`-style dump: offset, 16 hex bytes, printable ASCII
+pub fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:08x}  {:<47}  |{}|", row * 16, hex.join(" "), ascii)
+        })
+        .collect()
+}
+
+/// Describe executables and other binary formats from their header
+pub fn describe(header: &[u8]) -> Option<String> {
+    if header.starts_with(b"\x7FELF") && header.len() >= 20 {
+        let bits = if header[4] == 2 { "64-bit" } else { "32-bit" };
+        let little_endian = header[5] == 1;
+        let read_u16 = |offset: usize| {
+            let bytes = [header[offset], header[offset + 1]];
+            if little_endian {
+                u16::from_le_bytes(bytes)
+            } else {
+                u16::from_be_bytes(bytes)
+            }
+        };
+
+        let kind = match read_u16(16) {
+            1 => "relocatable object",
+            2 => "executable",
+            3 => "shared object",
+            4 => "core dump",
+            _ => "file",
+        };
+        let machine = match read_u16(18) {
+            0x03 => "x86",
+            0x3E => "x86-64",
+            0x28 => "ARM",
+            0xB7 => "AArch64",
+            0xF3 => "RISC-V",
+            0x08 => "MIPS",
+            0x15 => "PowerPC64",
+            _ => "unknown architecture",
+        };
+        return Some(format!("ELF {} {}, {}", bits, kind, machine));
+    }
+
+    let description = if header.starts_with(b"\xCF\xFA\xED\xFE") || header.starts_with(b"\xCE\xFA\xED\xFE") {
+        "Mach-O binary"
+    } else if header.starts_with(b"\xCA\xFE\xBA\xBE") {
+        "Mach-O universal binary or Java class"
+    } else if header.starts_with(b"MZ") {
+        "Windows PE executable"
+    } else if header.starts_with(b"\0asm") {
+        "WebAssembly module"
+    } else if header.starts_with(b"SQLite format 3\0") {
+        "SQLite database"
+    } else if header.starts_with(b"%PDF") {
+        "PDF document"
+    } else if header.starts_with(b"!<arch>\n") {
+        "Static library (ar archive)"
+    } else {
+        return None;
+    };
+    Some(description.to_string())
+}
+
+/// List the entries of a zip, tar, tar.gz or gzip file
+pub fn list_archive(path: &Path, header: &[u8]) -> Result<ArchiveListing> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+        list_zip(File::open(path)?)
+    } else if header.starts_with(b"\x1F\x8B") {
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            list_tar(GzDecoder::new(BufReader::new(File::open(path)?)), "tar.gz")
+        } else {
+            describe_gzip(path)
+        }
+    } else if header.len() >= 262 && &header[257..262] == b"ustar" {
+        list_tar(BufReader::new(File::open(path)?), "tar")
+    } else {
+        Err(anyhow!("Listing this archive format is not supported"))
+    }
+}
+
+fn list_zip<R: Read + Seek>(reader: R) -> Result<ArchiveListing> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    let mut listing = ArchiveListing {
+        format: "zip",
+        ..Default::default()
+    };
+
+    for index in 0..archive.len() {
+        // Raw access reads headers only, so encrypted entries still list
+        let entry = archive.by_index_raw(index)?;
+        listing.uncompressed_size += entry.size();
+        if listing.entries.len() < MAX_ARCHIVE_ENTRIES {
+            listing.entries.push(ArchiveEntry {
+                name: entry.name().to_string(),
+                size: entry.size(),
+                is_dir: entry.is_dir(),
+            });
+        } else {
+            listing.truncated = true;
+        }
+    }
+
+    Ok(listing)
+}
+
+fn list_tar<R: Read>(reader: R, format: &'static str) -> Result<ArchiveListing> {
+    let mut archive = tar::Archive::new(reader);
+    let mut listing = ArchiveListing {
+        format,
+        ..Default::default()
+    };
+
+    // Tar has no index: stop reading once the listing is full
+    for entry in archive.entries()? {
+        if listing.entries.len() >= MAX_ARCHIVE_ENTRIES {
+            listing.truncated = true;
+            break;
+        }
+
+        let entry = entry?;
+        let size = entry.header().size().unwrap_or(0);
+        listing.uncompressed_size += size;
+        listing.entries.push(ArchiveEntry {
+            name: entry.path()?.to_string_lossy().to_string(),
+            size,
+            is_dir: entry.header().entry_type().is_dir(),
+        });
+    }
+
+    Ok(listing)
+}
+
+/// A plain .gz holds one file: report its stored name and original size
+fn describe_gzip(path: &Path) -> Result<ArchiveListing> {
+    let mut file = File::open(path)?;
+
+    // ISIZE trailer: uncompressed size modulo 2^32
+    let mut trailer = [0u8; 4];
+    file.seek(SeekFrom::End(-4))?;
+    file.read_exact(&mut trailer)?;
+    let size = u32::from_le_bytes(trailer) as u64;
+
+    file.seek(SeekFrom::Start(0))?;
+    let decoder = GzDecoder::new(BufReader::new(file));
+    let name = decoder
+        .header()
+        .and_then(|h| h.filename())
+        .map(|n| String::from_utf8_lossy(n).to_string())
+        .or_else(|| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .unwrap_or_default();
+
+    Ok(ArchiveListing {
+        format: "gzip",
+        entries: vec![ArchiveEntry {
+            name,
+            size,
+            is_dir: false,
+        }],
+        truncated: false,
+        uncompressed_size: size,
+    })
+}
+
+/// Camera, date and location fields from a photo's EXIF block
+fn exif_metadata(path: &Path) -> Result<Vec<(String, String)>> {
+    use exif::{In, Tag};
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let exif = exif::Reader::new().read_from_container(&mut reader)?;
+
+    let mut fields = Vec::new();
+    for (label, tag) in [
+        ("Camera make", Tag::Make),
+        ("Camera model", Tag::Model),
+        ("Taken", Tag::DateTimeOriginal),
+        ("Exposure", Tag::ExposureTime),
+        ("Aperture", Tag::FNumber),
+        ("ISO", Tag::PhotographicSensitivity),
+        ("Focal length", Tag::FocalLength),
+        ("Software", Tag::Software),
+    ] {
+        if let Some(field) = exif.get_field(tag, In::PRIMARY) {
+            let value = field.display_value().with_unit(&exif).to_string();
+            fields.push((label.to_string(), value.trim_matches('"').to_string()));
+        }
+    }
+
+    // Worth calling out before sharing or keeping a photo
+    if exif.get_field(Tag::GPSLatitude, In::PRIMARY).is_some() {
+        fields.push(("Location".to_string(), "GPS coordinates embedded".to_string()));
+    }
+
+    Ok(fields)
+}
+
+/// Container-level metadata for audio and video files
+pub fn media_metadata(path: &Path, header: &[u8]) -> Result<Vec<(String, String)>> {
+    if header.starts_with(b"RIFF") && header.len() >= 12 && &header[8..12] == b"WAVE" {
+        wav_metadata(header)
+    } else if header.starts_with(b"fLaC") {
+        flac_metadata(header)
+    } else if header.starts_with(b"ID3") {
+        Ok(id3_metadata(header))
+    } else if header.len() >= 8 && &header[4..8] == b"ftyp" {
+        mp4_metadata(&mut BufReader::new(File::open(path)?))
+    } else if header.starts_with(b"\x1A\x45\xDF\xA3") {
+        let container = if header.windows(4).any(|w| w == b"webm") { "WebM" } else { "Matroska" };
+        Ok(vec![("Container".to_string(), container.to_string())])
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.round() as u64;
+    if total >= 3600 {
+        format!("{}:{:02}:{:02}", total / 3600, (total / 60) % 60, total % 60)
+    } else {
+        format!("{}:{:02}", total / 60, total % 60)
+    }
+}
+
+fn wav_metadata(header: &[u8]) -> Result<Vec<(String, String)>> {
+    let mut fields = vec![("Container".to_string(), "WAV".to_string())];
+    let mut byte_rate = 0u32;
+    let mut offset = 12;
+
+    while offset + 8 <= header.len() {
+        let id = &header[offset..offset + 4];
+        let size = u32::from_le_bytes(header[offset + 4..offset + 8].try_into()?);
+        let body = offset + 8;
+
+        if id == b"fmt " && body + 16 <= header.len() {
+            let channels = u16::from_le_bytes(header[body + 2..body + 4].try_into()?);
+            let sample_rate = u32::from_le_bytes(header[body + 4..body + 8].try_into()?);
+            byte_rate = u32::from_le_bytes(header[body + 8..body + 12].try_into()?);
+            let bits = u16::from_le_bytes(header[body + 14..body + 16].try_into()?);
+            fields.push(("Channels".to_string(), channels.to_string()));
+            fields.push(("Sample rate".to_string(), format!("{} Hz", sample_rate)));
+            fields.push(("Bit depth".to_string(), format!("{} bit", bits)));
+        } else if id == b"data" && byte_rate > 0 {
+            fields.push(("Duration".to_string(), format_duration(size as f64 / byte_rate as f64)));
+            break;
+        }
+
+        // Chunks are padded to an even length
+        offset = body + size as usize + (size as usize & 1);
+    }
+
+    Ok(fields)
+}
+
+fn flac_metadata(header: &[u8]) -> Result<Vec<(String, String)>> {
+    // STREAMINFO is always the first metadata block: 4-byte block header after "fLaC"
+    let info = header
+        .get(8..26)
+        .ok_or_else(|| anyhow!("Truncated FLAC header"))?;
+
+    let sample_rate = (u32::from(info[10]) << 12) | (u32::from(info[11]) << 4) | (u32::from(info[12]) >> 4);
+    let channels = ((info[12] >> 1) & 0x07) + 1;
+    let bits = (((info[12] & 0x01) << 4) | (info[13] >> 4)) + 1;
+    let total_samples = (u64::from(info[13] & 0x0F) << 32)
+        | u64::from(u32::from_be_bytes(info[14..18].try_into()?));
+
+    let mut fields = vec![
+        ("Container".to_string(), "FLAC".to_string()),
+        ("Channels".to_string(), channels.to_string()),
+        ("Sample rate".to_string(), format!("{} Hz", sample_rate)),
+        ("Bit depth".to_string(), format!("{} bit", bits)),
+    ];
+    if sample_rate > 0 && total_samples > 0 {
+        fields.push((
+            "Duration".to_string(),
+            format_duration(total_samples as f64 / sample_rate as f64),
+        ));
+    }
+    Ok(fields)
+}
+
+/// Title/artist/album text frames from an ID3v2.3/2.4 tag
+fn id3_metadata(header: &[u8]) -> Vec<(String, String)> {
+    let mut fields = vec![("Container".to_string(), "MP3 (ID3v2)".to_string())];
+    if header.len() < 10 {
+        return fields;
+    }
+
+    let version = header[3];
+    let syncsafe = |b: &[u8]| {
+        b.iter().fold(0usize, |acc, &x| (acc << 7) | (x & 0x7F) as usize)
+    };
+    let tag_end = (10 + syncsafe(&header[6..10])).min(header.len());
+    let mut offset = 10;
+
+    while offset + 10 <= tag_end {
+        let id = &header[offset..offset + 4];
+        if id[0] == 0 {
+            break;
+        }
+        let size = if version >= 4 {
+            syncsafe(&header[offset + 4..offset + 8])
+        } else {
+            u32::from_be_bytes([header[offset + 4], header[offset + 5], header[offset + 6], header[offset + 7]]) as usize
+        };
+        let body = offset + 10;
+        let end = (body + size).min(tag_end);
+
+        let label = match id {
+            b"TIT2" => Some("Title"),
+            b"TPE1" => Some("Artist"),
+            b"TALB" => Some("Album"),
+            b"TYER" | b"TDRC" => Some("Year"),
+            _ => None,
+        };
+        if let (Some(label), Some(text)) = (label, header.get(body..end).and_then(decode_id3_text)) {
+            fields.push((label.to_string(), text));
+        }
+
+        offset = body + size;
+    }
+
+    fields
+}
+
+fn decode_id3_text(frame: &[u8]) -> Option<String> {
+    let (&encoding, text) = frame.split_first()?;
+    let decoded = match encoding {
+        // UTF-16 with BOM, or UTF-16BE
+        1 | 2 => {
+            let units: Vec<u16> = text
+                .chunks_exact(2)
+                .map(|c| match (encoding, c) {
+                    (1, _) if text.starts_with(&[0xFF, 0xFE]) => u16::from_le_bytes([c[0], c[1]]),
+                    _ => u16::from_be_bytes([c[0], c[1]]),
+                })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        // ISO-8859-1 or UTF-8
+        _ => String::from_utf8_lossy(text).to_string(),
+    };
+    let cleaned = decoded.trim_matches(|c| c == '\0' || c == '\u{FEFF}' || c == '\u{FFFE}').trim().to_string();
+    (!cleaned.is_empty()).then_some(cleaned)
+}
+
+/// Brand, duration and track layout from an ISO base media file (MP4/MOV/M4A)
+fn mp4_metadata<R: Read + Seek>(reader: &mut R) -> Result<Vec<(String, String)>> {
+    let mut fields = Vec::new();
+    let file_end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    for (kind, start, size) in mp4_boxes(reader, 0, file_end)? {
+        match &kind {
+            b"ftyp" => {
+                let mut brand = [0u8; 4];
+                reader.seek(SeekFrom::Start(start))?;
+                reader.read_exact(&mut brand)?;
+                fields.push(("Brand".to_string(), String::from_utf8_lossy(&brand).trim().to_string()));
+            }
+            b"moov" => read_moov(reader, start, start + size, &mut fields)?,
+            _ => {}
+        }
+    }
+
+    Ok(fields)
+}
+
+fn read_moov<R: Read + Seek>(reader: &mut R, start: u64, end: u64, fields: &mut Vec<(String, String)>) -> Result<()> {
+    for (kind, box_start, box_size) in mp4_boxes(reader, start, end)? {
+        match &kind {
+            b"mvhd" => {
+                let mut body = vec![0u8; box_size.min(32) as usize];
+                reader.seek(SeekFrom::Start(box_start))?;
+                reader.read_exact(&mut body)?;
+                // Version 1 uses 64-bit times and duration
+                let (timescale, duration) = if body.first() == Some(&1) && body.len() >= 32 {
+                    (
+                        u32::from_be_bytes(body[20..24].try_into()?) as u64,
+                        u64::from_be_bytes(body[24..32].try_into()?),
+                    )
+                } else if body.len() >= 20 {
+                    (
+                        u32::from_be_bytes(body[12..16].try_into()?) as u64,
+                        u32::from_be_bytes(body[16..20].try_into()?) as u64,
+                    )
+                } else {
+                    continue;
+                };
+                if timescale > 0 {
+                    fields.push(("Duration".to_string(), format_duration(duration as f64 / timescale as f64)));
+                }
+            }
+            b"trak" => {
+                for (inner, inner_start, inner_size) in mp4_boxes(reader, box_start, box_start + box_size)? {
+                    // tkhd ends with 16.16 fixed-point width and height
+                    if &inner == b"tkhd" && inner_size >= 8 {
+                        let mut dims = [0u8; 8];
+                        reader.seek(SeekFrom::Start(inner_start + inner_size - 8))?;
+                        reader.read_exact(&mut dims)?;
+                        let width = u32::from_be_bytes(dims[0..4].try_into()?) >> 16;
+                        let height = u32::from_be_bytes(dims[4..8].try_into()?) >> 16;
+                        if width > 0 && height > 0 {
+                            fields.push(("Video".to_string(), format!("{}x{}", width, height)));
+                        } else {
+                            fields.push(("Track".to_string(), "audio/other".to_string()));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Box type, body offset and body size of each box between start and end
+fn mp4_boxes<R: Read + Seek>(reader: &mut R, start: u64, end: u64) -> Result<Vec<([u8; 4], u64, u64)>> {
+    let mut boxes = Vec::new();
+    let mut offset = start;
+
+    while offset + 8 <= end {
+        let mut head = [0u8; 8];
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_exact(&mut head)?;
+        let kind: [u8; 4] = head[4..8].try_into()?;
+
+        let (header_len, total) = match u32::from_be_bytes(head[0..4].try_into()?) {
+            // 64-bit size follows the type
+            1 => {
+                let mut large = [0u8; 8];
+                reader.read_exact(&mut large)?;
+                (16, u64::from_be_bytes(large))
+            }
+            // Box extends to the end of its parent
+            0 => (8, end - offset),
+            size => (8, size as u64),
+        };
+        if total < header_len {
+            break;
+        }
+
+        boxes.push((kind, offset + header_len, total - header_len));
+        offset += total;
+    }
+
+    Ok(boxes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::{Cursor, Write};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hex_dump() {
+        let lines = hex_dump(b"\x7FELF hello world!\x00\x01");
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("00000000  7f 45 4c 46 20 68"));
+        assert!(lines[0].ends_with("|.ELF hello world|"));
+        assert!(lines[1].starts_with("00000010  21 00 01"));
+    }
+
+    #[test]
+    fn test_describe_elf() {
+        let mut header = vec![0u8; 64];
+        header[..4].copy_from_slice(b"\x7FELF");
+        header[4] = 2;
+        header[5] = 1;
+        header[16] = 3;
+        header[18] = 0x3E;
+
+        assert_eq!(describe(&header).unwrap(), "ELF 64-bit shared object, x86-64");
+    }
+
+    #[test]
+    fn test_list_zip() {
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut writer = zip::ZipWriter::new(&mut buffer);
+            let options = zip::write::SimpleFileOptions::default();
+            writer.add_directory("docs/", options).unwrap();
+            writer.start_file("docs/readme.txt", options).unwrap();
+            writer.write_all(b"hello").unwrap();
+            writer.finish().unwrap();
+        }
+
+        let listing = list_zip(buffer).unwrap();
+        assert_eq!(listing.entries.len(), 2);
+        assert!(listing.entries[0].is_dir);
+        assert_eq!(listing.entries[1].name, "docs/readme.txt");
+        assert_eq!(listing.uncompressed_size, 5);
+    }
+
+    #[test]
+    fn test_list_tar_gz() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("backup.tar.gz");
+
+        let encoder = flate2::write::GzEncoder::new(File::create(&path).unwrap(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(3);
+        header.set_cksum();
+        builder.append_data(&mut header, "a.txt", &b"abc"[..]).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let header = fs::read(&path).unwrap();
+        let listing = list_archive(&path, &header).unwrap();
+        assert_eq!(listing.format, "tar.gz");
+        assert_eq!(listing.entries, vec![ArchiveEntry { name: "a.txt".to_string(), size: 3, is_dir: false }]);
+    }
+
+    #[test]
+    fn test_wav_metadata() {
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF\0\0\0\0WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&2u16.to_le_bytes()); // channels
+        wav.extend_from_slice(&44100u32.to_le_bytes());
+        wav.extend_from_slice(&(44100u32 * 4).to_le_bytes());
+        wav.extend_from_slice(&4u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(44100u32 * 4 * 90).to_le_bytes());
+
+        let fields = wav_metadata(&wav).unwrap();
+        assert!(fields.contains(&("Sample rate".to_string(), "44100 Hz".to_string())));
+        assert!(fields.contains(&("Duration".to_string(), "1:30".to_string())));
+    }
+
+    #[test]
+    fn test_inspect_text_and_binary() {
+        let temp_dir = TempDir::new().unwrap();
+        let script = temp_dir.path().join("run");
+        fs::write(&script, "#!/bin/sh\necho hi\n").unwrap();
+        let binary = temp_dir.path().join("blob");
+        fs::write(&binary, b"\x7FELF\x02\x01\x01\0\0\0").unwrap();
+
+        let inspection = inspect(&script);
+        assert_eq!(inspection.class, ContentClass::Source);
+        assert_eq!(inspection.text.as_deref(), Some("#!/bin/sh\necho hi"));
+
+        // One endless line is cut at the byte limit
+        let log = temp_dir.path().join("minified.txt");
+        fs::write(&log, "x".repeat(4 * TEXT_PREVIEW_BYTES as usize)).unwrap();
+        let inspection = inspect(&log);
+        assert_eq!(inspection.text.map(|t| t.len()), Some(TEXT_PREVIEW_BYTES as usize));
+
+        let inspection = inspect(&binary);
+        assert_eq!(inspection.class, ContentClass::Binary);
+        assert!(inspection.text.is_none());
+        assert!(!inspection.hex_dump.is_empty());
+    }
+
+    #[test]
+    fn test_image_size_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let small = temp_dir.path().join("small.png");
+        image::RgbImage::new(4, 3).save(&small).unwrap();
+        let inspection = inspect(&small);
+        assert!(inspection.thumbnail.is_some());
+        assert_eq!(inspection.metadata[0], ("Dimensions".to_string(), "4x3".to_string()));
+
+        // A BMP header claiming 30000x30000 pixels, with no pixel data
+        let mut bmp = b"BM".to_vec();
+        bmp.extend_from_slice(&54u32.to_le_bytes());
+        bmp.extend_from_slice(&[0; 4]);
+        bmp.extend_from_slice(&54u32.to_le_bytes());
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&30_000i32.to_le_bytes());
+        bmp.extend_from_slice(&30_000i32.to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&24u16.to_le_bytes());
+        bmp.extend_from_slice(&[0; 24]);
+        let huge = temp_dir.path().join("huge.bmp");
+        fs::write(&huge, bmp).unwrap();
+        let inspection = inspect(&huge);
+        assert!(inspection.thumbnail.is_none());
+        assert_eq!(inspection.metadata[0].1, "30000x30000");
+        assert!(inspection.error.unwrap().contains("too large"));
+    }
+}
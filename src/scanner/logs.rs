@@ -0,0 +1,302 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::models::{CleanableItem, CleanupCategory};
+use crate::scanner::errors::ScanErrors;
+use crate::system::privileges;
+
+/// Rotated logs younger than this are kept
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+/// Size limit used for the journal's vacuum-by-size estimate
+const JOURNAL_SIZE_LIMIT: u64 = 500 * 1024 * 1024;
+
+/// Where journald keeps persistent and volatile journals
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub const JOURNAL_DIRS: &[&str] = &["/var/log/journal", "/run/log/journal"];
+
+const COMPRESSED_EXTENSIONS: &[&str] = &["gz", "bz2", "xz", "zst", "z", "zip"];
+
+/// Finds rotated logs past the retention window. Active logs are never
+/// offered; the systemd journal is reported through `journalctl --vacuum-*`.
+pub struct LogScanner {
+    retention_days: i64,
+    errors: ScanErrors,
+}
+
+impl LogScanner {
+    pub fn new() -> Self {
+        Self {
+            retention_days: DEFAULT_RETENTION_DAYS,
+            errors: ScanErrors::new(),
+        }
+    }
+
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn scan(&self, roots: &[PathBuf]) -> Result<Vec<CleanableItem>> {
+        let cutoff = Local::now() - Duration::days(self.retention_days);
+        let mut items = Vec::new();
+
+        for root in roots {
+            let walker = WalkDir::new(root)
+                .follow_links(false)
+                .into_iter()
+                // The journal is binary and managed by journald
+                .filter_entry(|e| !(e.file_type().is_dir() && e.file_name() == "journal"));
+
+            for entry in walker {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        // May hold rotated logs we can't see
+                        if let Some(dir) = privileges::permission_denied(&err) {
+                            items.push(CleanableItem::unreadable(dir, CleanupCategory::Logs, 0));
+                        }
+                        self.errors.record_walk(&err);
+                        continue;
+                    }
+                };
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let name = entry.file_name().to_string_lossy();
+                if !is_rotated(&name) {
+                    continue;
+                }
+
+                let metadata = match self.errors.ok(entry.metadata()) {
+                    Some(metadata) => metadata,
+                    None => continue,
+                };
+                let modified = match metadata.modified() {
+                    Ok(time) => DateTime::<Local>::from(time),
+                    Err(_) => continue,
+                };
+                if modified >= cutoff {
+                    continue;
+                }
+
+                let note = rotated_note(&modified, is_compressed(&name));
+
+                items.push(CleanableItem {
+                    path: entry.path().to_path_buf(),
+                    size: metadata.len(),
+                    category: CleanupCategory::Logs,
+                    modified,
+                    selected: false,
+                    note: Some(note),
+                    remove_command: None,
+                    depth: 0,
+                    needs_privileges: false,
+                });
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        items.extend(self.journal_item(&JOURNAL_DIRS.iter().map(Path::new).collect::<Vec<_>>()));
+
+        Ok(items)
+    }
+
+    /// What `journalctl --vacuum-time` would free, with the size-based alternative
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn journal_item(&self, dirs: &[&Path]) -> Option<CleanableItem> {
+        let usage = JournalUsage::read(dirs, &self.errors);
+        if usage.total == 0 {
+            return None;
+        }
+
+        let cutoff = Local::now() - Duration::days(self.retention_days);
+        let by_time = usage.vacuum_time_savings(cutoff);
+        let by_size = usage.vacuum_size_savings(JOURNAL_SIZE_LIMIT);
+
+        Some(CleanableItem {
+            path: dirs[0].to_path_buf(),
+            size: by_time,
+            category: CleanupCategory::Logs,
+            modified: usage.newest.unwrap_or_else(Local::now),
+            selected: false,
+            note: Some(format!(
+                "Journal uses {}; vacuuming to {} days frees {}, to {} frees {}",
+                humansize::format_size(usage.total, humansize::BINARY),
+                self.retention_days,
+                humansize::format_size(by_time, humansize::BINARY),
+                humansize::format_size(JOURNAL_SIZE_LIMIT, humansize::BINARY),
+                humansize::format_size(by_size, humansize::BINARY),
+            )),
+            remove_command: Some(vec![
+                "journalctl".to_string(),
+                format!("--vacuum-time={}d", self.retention_days),
+            ]),
+            depth: 0,
+            needs_privileges: !privileges::is_root(),
+        })
+    }
+}
+
+pub fn rotated_note(modified: &DateTime<Local>, compressed: bool) -> String {
+    let age = (Local::now() - *modified).num_days();
+    if compressed {
+        format!("Rotated log, {} days old", age)
+    } else {
+        format!("Rotated log, {} days old, uncompressed ('c' gzips it instead)", age)
+    }
+}
+
+pub fn is_compressed(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| COMPRESSED_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Rotated logs: compressed (`.gz`), numbered (`.1`), dated (`-20240131`) or `.old`
+pub fn is_rotated(name: &str) -> bool {
+    if is_compressed(name) {
+        return true;
+    }
+
+    let suffix = match name.rfind('.') {
+        Some(dot) => &name[dot + 1..],
+        None => "",
+    };
+    if suffix == "old" || (!suffix.is_empty() && suffix.len() <= 3 && suffix.bytes().all(|b| b.is_ascii_digit())) {
+        return true;
+    }
+
+    // messages-20240131, syslog.20240131
+    let bytes = name.as_bytes();
+    bytes.len() > 9
+        && bytes[bytes.len() - 8..].iter().all(u8::is_ascii_digit)
+        && matches!(bytes[bytes.len() - 9], b'-' | b'.')
+}
+
+/// Archived and active journal files and their sizes
+#[derive(Debug, Default)]
+pub struct JournalUsage {
+    pub total: u64,
+    /// Archived journals (`name@...journal`), oldest first; journald never
+    /// removes the active ones
+    pub archived: Vec<(PathBuf, u64, DateTime<Local>)>,
+    pub newest: Option<DateTime<Local>>,
+}
+
+impl JournalUsage {
+    pub fn read(dirs: &[&Path], errors: &ScanErrors) -> Self {
+        let mut usage = Self::default();
+
+        for dir in dirs {
+            for entry in WalkDir::new(dir).max_depth(2).into_iter().filter_map(|e| errors.ok(e)) {
+                let name = entry.file_name().to_string_lossy();
+                if !entry.file_type().is_file() || !(name.ends_with(".journal") || name.ends_with(".journal~")) {
+                    continue;
+                }
+                let metadata = match errors.ok(entry.metadata()) {
+                    Some(metadata) => metadata,
+                    None => continue,
+                };
+                let modified = metadata
+                    .modified()
+                    .map(DateTime::<Local>::from)
+                    .unwrap_or_else(|_| Local::now());
+
+                usage.total += metadata.len();
+                usage.newest = usage.newest.max(Some(modified));
+                if name.contains('@') {
+                    usage.archived.push((entry.path().to_path_buf(), metadata.len(), modified));
+                }
+            }
+        }
+
+        usage.archived.sort_by_key(|(_, _, modified)| *modified);
+        usage
+    }
+
+    /// `--vacuum-time`: archived files whose last write is before the cutoff
+    pub fn vacuum_time_savings(&self, cutoff: DateTime<Local>) -> u64 {
+        self.archived
+            .iter()
+            .filter(|(_, _, modified)| *modified < cutoff)
+            .map(|(_, size, _)| size)
+            .sum()
+    }
+
+    /// `--vacuum-size`: oldest archived files go until usage fits the limit
+    pub fn vacuum_size_savings(&self, limit: u64) -> u64 {
+        let mut remaining = self.total;
+        let mut freed = 0;
+        for (_, size, _) in &self.archived {
+            if remaining <= limit {
+                break;
+            }
+            remaining -= size;
+            freed += size;
+        }
+        freed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::time::{Duration as StdDuration, SystemTime};
+    use tempfile::TempDir;
+
+    fn write_aged(path: &Path, size: usize, days: u64) {
+        fs::write(path, vec![b'x'; size]).unwrap();
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - StdDuration::from_secs(days * 86400)).unwrap();
+    }
+
+    #[test]
+    fn test_is_rotated() {
+        for name in ["syslog.1", "syslog.2.gz", "messages-20240131", "auth.log.old", "system.log.0.bz2"] {
+            assert!(is_rotated(name), "{}", name);
+        }
+        for name in ["syslog", "Xorg.0.log", "dpkg.log", "kern.log", "lastlog"] {
+            assert!(!is_rotated(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_only_old_rotated_logs() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("journal")).unwrap();
+
+        write_aged(&root.join("syslog"), 100, 90);
+        write_aged(&root.join("syslog.1"), 100, 3);
+        write_aged(&root.join("syslog.2.gz"), 50, 45);
+        write_aged(&root.join("journal/system@abc.journal"), 100, 90);
+
+        let items = LogScanner::new().scan(&[root.to_path_buf()]).unwrap();
+        let paths: Vec<&Path> = items.iter().map(|i| i.path.as_path()).filter(|p| p.starts_with(root)).collect();
+
+        assert_eq!(paths, vec![root.join("syslog.2.gz")]);
+    }
+
+    #[test]
+    fn test_journal_vacuum_estimates() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("machine-id");
+        fs::create_dir(&dir).unwrap();
+
+        write_aged(&dir.join("system@0001.journal"), 400, 60);
+        write_aged(&dir.join("system@0002.journal"), 300, 20);
+        write_aged(&dir.join("system.journal"), 200, 0);
+
+        let usage = JournalUsage::read(&[temp_dir.path()], &ScanErrors::new());
+
+        assert_eq!(usage.total, 900);
+        assert_eq!(usage.vacuum_time_savings(Local::now() - Duration::days(30)), 400);
+        assert_eq!(usage.vacuum_size_savings(600), 400);
+        assert_eq!(usage.vacuum_size_savings(100), 700);
+    }
+}
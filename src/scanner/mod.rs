@@ -1,4 +1,17 @@
+pub mod browsers;
 pub mod cleanup;
+pub mod content;
+pub mod crashes;
 pub mod duplicates;
+pub mod errors;
+pub mod inspect;
 pub mod large_files;
+pub mod logs;
+pub mod ncdu;
+#[cfg(target_os = "linux")]
+pub mod packages;
+pub mod pattern;
+pub mod projects;
+pub mod thumbnails;
+pub mod toolchains;
 pub mod treemap;
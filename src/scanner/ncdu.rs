@@ -0,0 +1,222 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::models::TreeMapItem;
+
+/// Major/minor version of the ncdu JSON dump format we read and write
+const NCDU_MAJOR_VERSION: u64 = 1;
+const NCDU_MINOR_VERSION: u64 = 2;
+
+/// Export a scanned tree to an ncdu JSON dump (compatible with `ncdu -f`)
+pub fn export_to_file(root: &TreeMapItem, path: &Path) -> Result<()> {
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    export(root, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write a tree in ncdu JSON format to any writer
+pub fn export<W: Write>(root: &TreeMapItem, writer: W) -> Result<()> {
+    let header = json!({
+        "progname": "surge",
+        "progver": env!("CARGO_PKG_VERSION"),
+        "timestamp": chrono::Local::now().timestamp(),
+    });
+
+    // The root entry carries the full path so the dump can be browsed elsewhere
+    let mut root_info = Map::new();
+    root_info.insert(
+        "name".to_string(),
+        Value::String(root.path.to_string_lossy().to_string()),
+    );
+
+    let dump = json!([
+        NCDU_MAJOR_VERSION,
+        NCDU_MINOR_VERSION,
+        header,
+        directory_to_value(root, root_info),
+    ]);
+
+    serde_json::to_writer(writer, &dump)?;
+    Ok(())
+}
+
+fn item_to_value(item: &TreeMapItem) -> Value {
+    let mut info = Map::new();
+    info.insert("name".to_string(), Value::String(item.name.clone()));
+
+    if item.is_file {
+        info.insert("asize".to_string(), json!(item.size));
+        info.insert("dsize".to_string(), json!(item.size));
+        Value::Object(info)
+    } else {
+        directory_to_value(item, info)
+    }
+}
+
+fn directory_to_value(item: &TreeMapItem, mut info: Map<String, Value>) -> Value {
+    // Directories past the scan depth have a size but no children; store that
+    // size on the directory itself so ncdu's totals still add up
+    let children_size: u64 = item.children.iter().map(|c| c.size).sum();
+    let own_size = item.size.saturating_sub(children_size);
+    info.insert("asize".to_string(), json!(own_size));
+    info.insert("dsize".to_string(), json!(own_size));
+
+    let mut entries = Vec::with_capacity(item.children.len() + 1);
+    entries.push(Value::Object(info));
+    entries.extend(item.children.iter().map(item_to_value));
+    Value::Array(entries)
+}
+
+/// Import an ncdu JSON dump (as produced by `ncdu -o` or `surge --export`)
+pub fn import_from_file(path: &Path) -> Result<TreeMapItem> {
+    let file = File::open(path)?;
+    let dump: Value = serde_json::from_reader(BufReader::new(file))?;
+    import(&dump)
+}
+
+/// Convert a parsed ncdu dump into a TreeMap tree
+pub fn import(dump: &Value) -> Result<TreeMapItem> {
+    let parts = dump
+        .as_array()
+        .ok_or_else(|| anyhow!("Not an ncdu dump: expected a JSON array"))?;
+
+    let major = parts.first().and_then(Value::as_u64);
+    if major != Some(NCDU_MAJOR_VERSION) {
+        return Err(anyhow!(
+            "Unsupported ncdu dump version: {}",
+            parts.first().map(|v| v.to_string()).unwrap_or_default()
+        ));
+    }
+
+    let root = parts
+        .get(3)
+        .ok_or_else(|| anyhow!("ncdu dump has no directory tree"))?;
+
+    let root_info = root
+        .as_array()
+        .and_then(|entries| entries.first())
+        .and_then(Value::as_object)
+        .ok_or_else(|| anyhow!("ncdu dump root is not a directory"))?;
+    let root_name = entry_name(root_info)?;
+
+    value_to_item(root, &PathBuf::from(root_name))
+}
+
+fn value_to_item(value: &Value, path: &Path) -> Result<TreeMapItem> {
+    match value {
+        Value::Object(info) => Ok(TreeMapItem::new(path.to_path_buf(), entry_size(info), true)),
+        Value::Array(entries) => {
+            let info = entries
+                .first()
+                .and_then(Value::as_object)
+                .ok_or_else(|| anyhow!("Directory entry without info: {}", path.display()))?;
+
+            let mut item = TreeMapItem::new(path.to_path_buf(), 0, false);
+            let mut total_size = entry_size(info);
+
+            for child in entries.iter().skip(1) {
+                let child_info = match child {
+                    Value::Object(info) => info,
+                    Value::Array(child_entries) => match child_entries.first().and_then(Value::as_object) {
+                        Some(info) => info,
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+
+                // Excluded and non-regular entries don't take up space worth browsing
+                if child_info.contains_key("excluded") || child_info.get("notreg") == Some(&Value::Bool(true)) {
+                    continue;
+                }
+
+                let child_path = path.join(entry_name(child_info)?);
+                let child_item = value_to_item(child, &child_path)?;
+                total_size += child_item.size;
+                item.children.push(child_item);
+            }
+
+            // Match TreeMapScanner ordering (largest first)
+            item.children.sort_by_key(|c| std::cmp::Reverse(c.size));
+            item.size = total_size;
+
+            Ok(item)
+        }
+        _ => Err(anyhow!("Invalid ncdu entry at {}", path.display())),
+    }
+}
+
+fn entry_name(info: &Map<String, Value>) -> Result<&str> {
+    info.get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("ncdu entry without a name"))
+}
+
+/// Prefer apparent size (what TreeMapScanner reports), fall back to disk usage
+fn entry_size(info: &Map<String, Value>) -> u64 {
+    info.get("asize")
+        .and_then(Value::as_u64)
+        .or_else(|| info.get("dsize").and_then(Value::as_u64))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::treemap::TreeMapScanner;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+
+        fs::create_dir(temp_path.join("sub")).unwrap();
+        fs::write(temp_path.join("a.bin"), vec![0u8; 2048]).unwrap();
+        fs::write(temp_path.join("sub/b.bin"), vec![0u8; 1024]).unwrap();
+
+        let scanned = TreeMapScanner::new().scan(temp_path).unwrap();
+
+        let mut buffer = Vec::new();
+        export(&scanned, &mut buffer).unwrap();
+        let dump: Value = serde_json::from_slice(&buffer).unwrap();
+        let imported = import(&dump).unwrap();
+
+        assert_eq!(imported.path, scanned.path);
+        assert_eq!(imported.size, scanned.size);
+        assert_eq!(imported.children.len(), 2);
+        let sub = imported.children.iter().find(|c| c.name == "sub").unwrap();
+        assert!(!sub.is_file);
+        assert_eq!(sub.path, temp_path.join("sub"));
+        assert_eq!(sub.size, 1024);
+    }
+
+    #[test]
+    fn test_import_ncdu_dump() {
+        let dump = r#"[1,2,{"progname":"ncdu","progver":"1.19","timestamp":1700000000},
+            [{"name":"/srv","asize":4096,"dsize":4096},
+             {"name":"data.db","asize":1000,"dsize":4096},
+             [{"name":"logs","asize":4096,"dsize":4096},
+              {"name":"app.log","asize":500,"dsize":4096},
+              {"name":"skip","excluded":"pattern"}]]]"#;
+
+        let root = import(&serde_json::from_str(dump).unwrap()).unwrap();
+
+        assert_eq!(root.path, PathBuf::from("/srv"));
+        assert_eq!(root.size, 4096 + 1000 + 4096 + 500);
+        let logs = root.children.iter().find(|c| c.name == "logs").unwrap();
+        assert_eq!(logs.children.len(), 1);
+        assert_eq!(logs.children[0].path, PathBuf::from("/srv/logs/app.log"));
+    }
+
+    #[test]
+    fn test_rejects_unknown_version() {
+        let dump: Value = serde_json::from_str(r#"[2,0,{},[{"name":"/"}]]"#).unwrap();
+        assert!(import(&dump).is_err());
+    }
+}
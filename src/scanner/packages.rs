@@ -0,0 +1,503 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+use crate::models::{CleanableItem, CleanupCategory};
+use crate::scanner::errors::ScanErrors;
+use crate::system::privileges;
+
+/// Pacman keeps this many versions of each package, like `paccache -rk3`
+const PACMAN_KEEP_VERSIONS: usize = 3;
+
+const APT_ARCHIVES: &str = "/var/cache/apt/archives";
+const DNF_CACHE: &str = "/var/cache/dnf";
+const YUM_CACHE: &str = "/var/cache/yum";
+const PACMAN_CACHE: &str = "/var/cache/pacman/pkg";
+const ZYPPER_CACHE: &str = "/var/cache/zypp/packages";
+const SNAP_DIR: &str = "/var/lib/snapd/snaps";
+const FLATPAK_SYSTEM: &str = "/var/lib/flatpak";
+
+/// Finds package-manager caches that are safe to clear, and how to clear them
+pub struct PackageCacheScanner {
+    pacman_keep: usize,
+    errors: ScanErrors,
+}
+
+impl PackageCacheScanner {
+    pub fn new() -> Self {
+        Self {
+            pacman_keep: PACMAN_KEEP_VERSIONS,
+            errors: ScanErrors::new(),
+        }
+    }
+
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Cache directories this scanner understands; the generic
+    /// SystemCaches scan leaves them alone
+    pub fn managed_paths() -> Vec<PathBuf> {
+        [APT_ARCHIVES, DNF_CACHE, YUM_CACHE, PACMAN_CACHE, ZYPPER_CACHE]
+            .iter()
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    /// Every directory this scanner reports items under
+    pub fn roots() -> Vec<PathBuf> {
+        let mut roots = Self::managed_paths();
+        roots.extend([PathBuf::from(SNAP_DIR), PathBuf::from(FLATPAK_SYSTEM)]);
+        roots.extend(dirs::data_dir().map(|data| data.join("flatpak")));
+        roots
+    }
+
+    pub fn scan(&self) -> Result<Vec<CleanableItem>> {
+        let mut items = Vec::new();
+
+        items.extend(scan_apt(Path::new(APT_ARCHIVES), &self.errors));
+        items.extend(scan_command_cache(
+            &self.errors,
+            Path::new(DNF_CACHE),
+            &["dnf", "clean", "all"],
+            "dnf repository metadata and downloaded RPMs; metadata is re-downloaded on the next dnf run",
+        ));
+        items.extend(scan_command_cache(
+            &self.errors,
+            Path::new(YUM_CACHE),
+            &["yum", "clean", "all"],
+            "yum repository metadata and downloaded RPMs; metadata is re-downloaded on the next yum run",
+        ));
+        items.extend(scan_command_cache(
+            &self.errors,
+            Path::new(ZYPPER_CACHE),
+            &["zypper", "clean", "--all"],
+            "Downloaded zypper packages; only needed to reinstall the same versions offline",
+        ));
+        items.extend(scan_pacman(Path::new(PACMAN_CACHE), self.pacman_keep, &self.errors));
+
+        let mut flatpak_dirs = vec![PathBuf::from(FLATPAK_SYSTEM)];
+        if let Some(data) = dirs::data_dir() {
+            flatpak_dirs.push(data.join("flatpak"));
+        }
+        for dir in flatpak_dirs {
+            items.extend(scan_flatpak_installation(&dir, &self.errors));
+        }
+
+        if let Ok(output) = Command::new("snap").args(["list", "--all"]).output() {
+            if output.status.success() {
+                let listing = String::from_utf8_lossy(&output.stdout);
+                items.extend(disabled_snaps(&listing, Path::new(SNAP_DIR)));
+            }
+        }
+
+        Ok(items)
+    }
+}
+
+fn item(path: PathBuf, size: u64, modified: DateTime<Local>, note: String) -> CleanableItem {
+    CleanableItem {
+        path,
+        size,
+        category: CleanupCategory::PackageCaches,
+        modified,
+        selected: false,
+        note: Some(note),
+        remove_command: None,
+        depth: 0,
+        // Package managers act on system-wide state
+        needs_privileges: !privileges::is_root(),
+    }
+}
+
+fn modified_time(path: &Path) -> DateTime<Local> {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(DateTime::<Local>::from)
+        .unwrap_or_else(|_| Local::now())
+}
+
+fn read_dir_entries(dir: &Path, errors: &ScanErrors) -> Vec<fs::DirEntry> {
+    errors
+        .ok_io(dir, fs::read_dir(dir))
+        .into_iter()
+        .flatten()
+        .filter_map(|e| errors.ok_io(dir, e))
+        .collect()
+}
+
+fn files_with_extension(dir: &Path, ext: &str, errors: &ScanErrors) -> Vec<(PathBuf, fs::Metadata)> {
+    WalkDir::new(dir)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| errors.ok(e))
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| e.path().extension().is_some_and(|x| x == ext))
+        .filter_map(|e| Some((e.path().to_path_buf(), errors.ok(e.metadata())?)))
+        .collect()
+}
+
+/// Downloaded .deb files. `apt-get clean` also keeps the lock and partial/
+/// directory intact, so clear through apt rather than deleting the directory.
+fn scan_apt(archives: &Path, errors: &ScanErrors) -> Option<CleanableItem> {
+    let debs = files_with_extension(archives, "deb", errors);
+    if debs.is_empty() {
+        return None;
+    }
+
+    let size = debs.iter().map(|(_, m)| m.len()).sum();
+    let mut cache = item(
+        archives.to_path_buf(),
+        size,
+        modified_time(archives),
+        format!(
+            "{} downloaded .deb packages; installed software is unaffected, apt re-downloads them if needed",
+            debs.len()
+        ),
+    );
+    cache.remove_command = Some(vec!["apt-get".to_string(), "clean".to_string()]);
+    Some(cache)
+}
+
+/// A whole cache directory that its package manager knows how to clear
+fn scan_command_cache(errors: &ScanErrors, dir: &Path, command: &[&str], note: &str) -> Option<CleanableItem> {
+    if !dir.is_dir() {
+        return None;
+    }
+
+    let size: u64 = WalkDir::new(dir)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| errors.ok(e))
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| errors.ok(e.metadata()))
+        .map(|m| m.len())
+        .sum();
+    if size == 0 {
+        return None;
+    }
+
+    let mut cache = item(dir.to_path_buf(), size, modified_time(dir), note.to_string());
+    cache.remove_command = Some(command.iter().map(|s| s.to_string()).collect());
+    Some(cache)
+}
+
+/// Split `name-pkgver-pkgrel-arch.pkg.tar.zst` into the package name
+fn pacman_package_name(file_name: &str) -> Option<&str> {
+    let stem = &file_name[..file_name.find(".pkg.tar")?];
+    let mut parts = stem.rsplitn(4, '-');
+    let (_arch, _rel, _ver) = (parts.next()?, parts.next()?, parts.next()?);
+    parts.next()
+}
+
+/// Old package versions beyond the newest `keep` of each package.
+/// Newer versions are downloaded later, so modification time orders them.
+fn scan_pacman(pkg_dir: &Path, keep: usize, errors: &ScanErrors) -> Vec<CleanableItem> {
+    let mut by_name: HashMap<String, Vec<(PathBuf, u64, DateTime<Local>)>> = HashMap::new();
+
+    for entry in read_dir_entries(pkg_dir, errors) {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if file_name.ends_with(".sig") {
+            continue;
+        }
+        let (name, metadata) = match (pacman_package_name(&file_name), entry.metadata()) {
+            (Some(name), Ok(metadata)) => (name.to_string(), metadata),
+            _ => continue,
+        };
+        let modified = metadata
+            .modified()
+            .map(DateTime::<Local>::from)
+            .unwrap_or_else(|_| Local::now());
+        by_name
+            .entry(name)
+            .or_default()
+            .push((entry.path(), metadata.len(), modified));
+    }
+
+    let mut items = Vec::new();
+    for (name, mut versions) in by_name {
+        versions.sort_by_key(|(_, _, modified)| std::cmp::Reverse(*modified));
+        for (path, size, modified) in versions.into_iter().skip(keep) {
+            items.push(item(
+                path,
+                size,
+                modified,
+                format!(
+                    "Old {} package; the {} newest versions stay cached for downgrades",
+                    name, keep
+                ),
+            ));
+        }
+    }
+    items
+}
+
+/// Runtime ref `id/arch/branch` from a `runtime=` or `sdk=` metadata line
+fn metadata_ref(metadata: &str, key: &str) -> Option<String> {
+    metadata
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+        .map(|value| value.trim().to_string())
+}
+
+/// Extension point names from the `[Extension <name>]` groups of a metadata file
+fn extension_points(metadata: &str) -> Vec<String> {
+    metadata
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("[Extension ")?.strip_suffix(']'))
+        .map(|name| name.trim().to_string())
+        .collect()
+}
+
+/// Deployed refs of one kind (`app` or `runtime`): `id/arch/branch` and its directory
+fn flatpak_refs(installation: &Path, kind: &str, errors: &ScanErrors) -> Vec<(String, PathBuf)> {
+    let mut refs = Vec::new();
+    for id in read_dir_entries(&installation.join(kind), errors) {
+        for arch in read_dir_entries(&id.path(), errors) {
+            for branch in read_dir_entries(&arch.path(), errors) {
+                let flatpak_ref = format!(
+                    "{}/{}/{}",
+                    id.file_name().to_string_lossy(),
+                    arch.file_name().to_string_lossy(),
+                    branch.file_name().to_string_lossy()
+                );
+                refs.push((flatpak_ref, branch.path()));
+            }
+        }
+    }
+    refs
+}
+
+/// Runtimes in a flatpak installation that no installed app needs, directly
+/// or through another needed runtime. A ref needs the `runtime=` and `sdk=`
+/// it names and every installed runtime filling one of its extension points
+/// (GL drivers, codecs, locales), whatever the branch; ids extending a
+/// needed id (`<id>.Locale`, `<id>.Debug`) count as needed too.
+fn scan_flatpak_installation(installation: &Path, errors: &ScanErrors) -> Vec<CleanableItem> {
+    let apps = flatpak_refs(installation, "app", errors);
+    let runtimes = flatpak_refs(installation, "runtime", errors);
+    let id_of = |flatpak_ref: &str| flatpak_ref.split('/').next().unwrap_or_default().to_string();
+
+    // app/<id>/<arch>/<branch>/active is a symlink to the deployed commit
+    let mut used_refs: HashSet<String> = apps.iter().map(|(r, _)| r.clone()).collect();
+    let mut pending: Vec<PathBuf> = apps.iter().map(|(_, dir)| dir.clone()).collect();
+    while let Some(dir) = pending.pop() {
+        let metadata = fs::read_to_string(dir.join("active/metadata")).unwrap_or_default();
+        let points = extension_points(&metadata);
+        let mut needed: HashSet<String> = ["runtime", "sdk"]
+            .iter()
+            .filter_map(|key| metadata_ref(&metadata, key))
+            .collect();
+        let used_ids: Vec<String> = used_refs.iter().map(|r| id_of(r)).collect();
+        for (runtime_ref, _) in &runtimes {
+            let id = id_of(runtime_ref);
+            let fills_point = points
+                .iter()
+                .any(|point| id == *point || id.starts_with(&format!("{}.", point)));
+            let extends_used = used_ids.iter().any(|used| id.starts_with(&format!("{}.", used)));
+            if fills_point || extends_used {
+                needed.insert(runtime_ref.clone());
+            }
+        }
+
+        for runtime_ref in needed {
+            if used_refs.insert(runtime_ref.clone()) {
+                if let Some((_, dir)) = runtimes.iter().find(|(r, _)| *r == runtime_ref) {
+                    pending.push(dir.clone());
+                }
+            }
+        }
+    }
+
+    // Also catches extensions of a needed ref that isn't installed itself
+    let used_ids: Vec<String> = used_refs.iter().map(|r| id_of(r)).collect();
+    let mut items = Vec::new();
+    for (runtime_ref, path) in runtimes {
+        let id = id_of(&runtime_ref);
+        if used_refs.contains(&runtime_ref) || used_ids.iter().any(|used| id.starts_with(&format!("{}.", used))) {
+            continue;
+        }
+
+        let size = WalkDir::new(&path)
+            .follow_links(false)
+            .into_iter()
+            .filter_map(|e| errors.ok(e))
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| errors.ok(e.metadata()))
+            .map(|m| m.len())
+            .sum();
+
+        let mut unused = item(
+            path.clone(),
+            size,
+            modified_time(&path),
+            format!(
+                "Flatpak runtime {} is not used by any installed app; flatpak reinstalls it if an app needs it",
+                runtime_ref
+            ),
+        );
+        let mut command = vec!["flatpak".to_string(), "uninstall".to_string(), "--noninteractive".to_string()];
+        if !installation.starts_with(FLATPAK_SYSTEM) {
+            command.push("--user".to_string());
+            unused.needs_privileges = false;
+        }
+        command.push(format!("runtime/{}", runtime_ref));
+        unused.remove_command = Some(command);
+        items.push(unused);
+    }
+    items
+}
+
+/// Disabled revisions from `snap list --all`; snapd keeps them for rollback
+fn disabled_snaps(listing: &str, snap_dir: &Path) -> Vec<CleanableItem> {
+    listing
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let (name, revision) = (*columns.first()?, *columns.get(2)?);
+            if !columns.last()?.split(',').any(|note| note == "disabled") {
+                return None;
+            }
+
+            let path = snap_dir.join(format!("{}_{}.snap", name, revision));
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            let mut revision_item = item(
+                path.clone(),
+                size,
+                modified_time(&path),
+                format!(
+                    "Disabled {} revision {}, kept only to roll back a refresh",
+                    name, revision
+                ),
+            );
+            revision_item.remove_command = Some(vec![
+                "snap".to_string(),
+                "remove".to_string(),
+                name.to_string(),
+                "--revision".to_string(),
+                revision.to_string(),
+            ]);
+            Some(revision_item)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pacman_keeps_newest_versions() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let now = SystemTime::now();
+
+        for (i, version) in ["1.0-1", "1.1-1", "1.2-1", "1.3-1"].iter().enumerate() {
+            let path = dir.join(format!("foo-bar-{}-x86_64.pkg.tar.zst", version));
+            let file = File::create(&path).unwrap();
+            file.set_modified(now - Duration::from_secs(3600 * (4 - i as u64))).unwrap();
+            File::create(dir.join(format!("foo-bar-{}-x86_64.pkg.tar.zst.sig", version))).unwrap();
+        }
+        File::create(dir.join("other-2.0-1-any.pkg.tar.zst")).unwrap();
+
+        let items = scan_pacman(dir, 3, &ScanErrors::new());
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, dir.join("foo-bar-1.0-1-x86_64.pkg.tar.zst"));
+        assert_eq!(pacman_package_name("foo-bar-1.0-1-x86_64.pkg.tar.zst"), Some("foo-bar"));
+    }
+
+    #[test]
+    fn test_disabled_snaps() {
+        let listing = "\
+Name    Version  Rev    Tracking       Publisher   Notes
+core22  20240111 1122   latest/stable  canonical✓  base,disabled
+core22  20240408 1380   latest/stable  canonical✓  base
+firefox 125.0    4173   latest/stable  mozilla✓    disabled
+";
+        let items = disabled_snaps(listing, Path::new("/var/lib/snapd/snaps"));
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].path, PathBuf::from("/var/lib/snapd/snaps/core22_1122.snap"));
+        assert_eq!(
+            items[1].remove_command.as_deref().unwrap(),
+            ["snap", "remove", "firefox", "--revision", "4173"]
+        );
+    }
+
+    #[test]
+    fn test_flatpak_unused_runtimes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        let active = root.join("app/org.example.App/x86_64/stable/active");
+        fs::create_dir_all(&active).unwrap();
+        fs::write(
+            active.join("metadata"),
+            "[Application]\nname=org.example.App\nruntime=org.gnome.Platform/x86_64/45\nsdk=org.gnome.Sdk/x86_64/45\n",
+        )
+        .unwrap();
+
+        for runtime in [
+            "org.gnome.Platform/x86_64/45",
+            "org.gnome.Platform.Locale/x86_64/45",
+            "org.gnome.Platform/x86_64/44",
+            "org.gnome.Sdk/x86_64/45",
+            "org.freedesktop.Platform.GL.default/x86_64/23.08",
+            "org.freedesktop.Platform.VAAPI.Intel/x86_64/23.08",
+            "org.freedesktop.Platform.GL.default/x86_64/22.08",
+            "org.freedesktop.Sdk/x86_64/23.08",
+        ] {
+            let dir = root.join("runtime").join(runtime);
+            fs::create_dir_all(dir.join("active")).unwrap();
+            fs::write(dir.join("files"), "x").unwrap();
+        }
+        // The GNOME runtime is built on the freedesktop one and takes its
+        // GL drivers and codecs through extension points
+        fs::write(
+            root.join("runtime/org.gnome.Platform/x86_64/45/active/metadata"),
+            "[Runtime]\nname=org.gnome.Platform\nruntime=org.gnome.Platform/x86_64/45\nsdk=org.gnome.Sdk/x86_64/45\n\n\
+             [Extension org.freedesktop.Platform.GL]\nversions=23.08;1.4\nsubdirectories=true\n\n\
+             [Extension org.freedesktop.Platform.VAAPI.Intel]\nversion=23.08\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("runtime/org.gnome.Sdk/x86_64/45/active/metadata"),
+            "[Runtime]\nname=org.gnome.Sdk\nruntime=org.gnome.Platform/x86_64/45\nsdk=org.gnome.Sdk/x86_64/45\n",
+        )
+        .unwrap();
+
+        let mut unused: Vec<PathBuf> = scan_flatpak_installation(root, &ScanErrors::new()).into_iter().map(|i| i.path).collect();
+        unused.sort();
+
+        assert_eq!(
+            unused,
+            vec![
+                root.join("runtime/org.freedesktop.Sdk/x86_64/23.08"),
+                root.join("runtime/org.gnome.Platform/x86_64/44"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apt_archives() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::create_dir(dir.join("partial")).unwrap();
+        fs::write(dir.join("curl_8.5.0_amd64.deb"), vec![0u8; 300]).unwrap();
+        fs::write(dir.join("lock"), "").unwrap();
+
+        let cache = scan_apt(dir, &ScanErrors::new()).unwrap();
+        assert_eq!(cache.size, 300);
+        assert_eq!(cache.remove_command.unwrap(), ["apt-get", "clean"]);
+    }
+}
@@ -0,0 +1,166 @@
+use anyhow::{anyhow, Result};
+use regex::{Regex, RegexBuilder};
+use std::path::Path;
+
+/// A user-supplied search pattern: plain substring, shell glob, or regex.
+///
+/// Queries are interpreted as:
+/// - `re:<regex>` - regular expression
+/// - anything containing `*`, `?` or `[` - glob
+/// - everything else - case-insensitive substring
+///
+/// Patterns containing a `/` are matched against the full path, all others
+/// against the file name only.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    kind: PatternKind,
+    match_full_path: bool,
+}
+
+#[derive(Debug, Clone)]
+enum PatternKind {
+    Substring(String),
+    Glob(Regex),
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn parse(query: &str) -> Result<Self> {
+        if query.is_empty() {
+            return Err(anyhow!("Empty search pattern"));
+        }
+
+        let match_full_path = query.contains('/');
+
+        let kind = if let Some(expr) = query.strip_prefix("re:") {
+            PatternKind::Regex(
+                RegexBuilder::new(expr)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| anyhow!("Invalid regex: {}", e))?,
+            )
+        } else if query.contains(['*', '?', '[']) {
+            PatternKind::Glob(glob_to_regex(query)?)
+        } else {
+            PatternKind::Substring(query.to_lowercase())
+        };
+
+        Ok(Self {
+            kind,
+            match_full_path,
+        })
+    }
+
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            PatternKind::Substring(_) => "text",
+            PatternKind::Glob(_) => "glob",
+            PatternKind::Regex(_) => "regex",
+        }
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match &self.kind {
+            PatternKind::Substring(needle) => text.to_lowercase().contains(needle),
+            PatternKind::Glob(re) | PatternKind::Regex(re) => re.is_match(text),
+        }
+    }
+
+    /// Match a path against the name or the full path, depending on the pattern
+    pub fn matches_path(&self, path: &Path) -> bool {
+        if self.match_full_path {
+            self.is_match(&path.to_string_lossy())
+        } else {
+            path.file_name()
+                .map(|n| self.is_match(&n.to_string_lossy()))
+                .unwrap_or(false)
+        }
+    }
+}
+
+/// Translate a shell glob into an anchored, case-insensitive regex.
+///
+/// Like `fnmatch` without `FNM_PATHNAME`: `*` matches any run of characters
+/// (including `/`), `?` matches one character and `[...]` is a character class.
+pub fn glob_to_regex(glob: &str) -> Result<Regex> {
+    let mut expr = String::from("^");
+    let mut chars = glob.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => expr.push_str(".*"),
+            '?' => expr.push('.'),
+            '[' => {
+                let mut class = String::new();
+                let mut closed = false;
+                for next in chars.by_ref() {
+                    if next == ']' {
+                        closed = true;
+                        break;
+                    }
+                    class.push(next);
+                }
+                if !closed {
+                    return Err(anyhow!("Unclosed '[' in glob: {}", glob));
+                }
+                let class = match class.strip_prefix('!') {
+                    Some(rest) => format!("^{}", rest),
+                    None => class,
+                };
+                expr.push('[');
+                expr.push_str(&class.replace('\\', "\\\\"));
+                expr.push(']');
+            }
+            _ => expr.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    expr.push('$');
+
+    RegexBuilder::new(&expr)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| anyhow!("Invalid glob '{}': {}", glob, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substring_is_case_insensitive() {
+        let pattern = Pattern::parse("Cache").unwrap();
+        assert!(pattern.matches_path(Path::new("/home/u/.cache")));
+        assert!(!pattern.matches_path(Path::new("/home/cache/file.txt")));
+    }
+
+    #[test]
+    fn test_glob() {
+        let pattern = Pattern::parse("*.log").unwrap();
+        assert_eq!(pattern.kind_name(), "glob");
+        assert!(pattern.matches_path(Path::new("/var/log/syslog.log")));
+        assert!(!pattern.matches_path(Path::new("/var/log/syslog.log.1")));
+
+        let full = Pattern::parse("*/node_modules").unwrap();
+        assert!(full.matches_path(Path::new("/p/node_modules")));
+        assert!(!full.matches_path(Path::new("/p/node_modules/react")));
+    }
+
+    #[test]
+    fn test_regex() {
+        let pattern = Pattern::parse(r"re:\.(mkv|mp4)$").unwrap();
+        assert!(pattern.matches_path(Path::new("/videos/movie.MKV")));
+        assert!(!pattern.matches_path(Path::new("/videos/movie.avi")));
+        assert!(Pattern::parse("re:(").is_err());
+    }
+
+    #[test]
+    fn test_glob_to_regex() {
+        let re = glob_to_regex("*/Library/LaunchAgents/com.*.plist").unwrap();
+        assert!(re.is_match("/Users/me/Library/LaunchAgents/com.evil.plist"));
+        assert!(!re.is_match("/Users/me/Library/LaunchAgents/org.evil.plist"));
+
+        let re = glob_to_regex("file[!0-9].txt").unwrap();
+        assert!(re.is_match("filea.txt"));
+        assert!(!re.is_match("file1.txt"));
+    }
+}
@@ -0,0 +1,299 @@
+use anyhow::Result;
+use chrono::{DateTime, Local, TimeZone};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+use crate::models::{CleanableItem, CleanupCategory};
+use crate::scanner::errors::ScanErrors;
+
+/// A project type, recognised by a marker file, and the directories it regenerates
+struct ArtifactRule {
+    kind: &'static str,
+    markers: &'static [&'static str],
+    artifacts: &'static [&'static str],
+}
+
+const ARTIFACT_RULES: &[ArtifactRule] = &[
+    ArtifactRule {
+        kind: "Rust",
+        markers: &["Cargo.toml"],
+        artifacts: &["target"],
+    },
+    ArtifactRule {
+        kind: "Node",
+        markers: &["package.json"],
+        artifacts: &["node_modules"],
+    },
+    ArtifactRule {
+        kind: "Python",
+        markers: &["pyproject.toml", "setup.py", "requirements.txt"],
+        artifacts: &[".venv", "venv", "__pycache__", ".pytest_cache", ".mypy_cache", ".tox"],
+    },
+    ArtifactRule {
+        kind: "Gradle",
+        markers: &["build.gradle", "build.gradle.kts", "settings.gradle", "settings.gradle.kts"],
+        artifacts: &["build", ".gradle"],
+    },
+];
+
+/// Directories never worth descending into while looking for projects
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "venv",
+    "__pycache__",
+    "Library",
+    "Applications",
+];
+
+/// Finds per-project build artifacts (target/, node_modules/, .venv, build/)
+/// under a set of roots, tagged with the project's last activity
+pub struct ProjectScanner {
+    roots: Vec<PathBuf>,
+    max_depth: usize,
+    min_size: u64,
+    errors: ScanErrors,
+}
+
+impl ProjectScanner {
+    pub fn new() -> Self {
+        Self {
+            roots: dirs::home_dir().into_iter().collect(),
+            max_depth: 6,
+            min_size: 1024 * 1024, // 1 MB
+            errors: ScanErrors::new(),
+        }
+    }
+
+    pub fn with_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.roots = roots;
+        self
+    }
+
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn scan(&self) -> Result<Vec<CleanableItem>> {
+        let mut items = Vec::new();
+
+        for root in &self.roots {
+            let walker = WalkDir::new(root)
+                .max_depth(self.max_depth)
+                .follow_links(false)
+                .into_iter()
+                .filter_entry(|e| e.depth() == 0 || !is_skipped_dir(e));
+
+            for entry in walker.filter_map(|e| self.errors.ok(e)) {
+                if entry.file_type().is_dir() {
+                    items.extend(self.scan_project(entry.path()));
+                }
+            }
+        }
+
+        items.sort_by_key(|i| std::cmp::Reverse(i.size));
+        Ok(items)
+    }
+
+    /// Artifacts of the project rooted at `dir`, if it is one
+    fn scan_project(&self, dir: &Path) -> Vec<CleanableItem> {
+        let mut artifacts: Vec<(&str, PathBuf)> = Vec::new();
+
+        for rule in ARTIFACT_RULES {
+            if !rule.markers.iter().any(|m| dir.join(m).is_file()) {
+                continue;
+            }
+            for name in rule.artifacts {
+                let path = dir.join(name);
+                if path.is_dir() && !artifacts.iter().any(|(_, p)| *p == path) {
+                    artifacts.push((rule.kind, path));
+                }
+            }
+        }
+
+        // CMake builds can live in any directory name; CMakeCache.txt identifies them
+        if dir.join("CMakeLists.txt").is_file() {
+            if let Ok(entries) = std::fs::read_dir(dir) {
+                for entry in entries.filter_map(|e| e.ok()) {
+                    let path = entry.path();
+                    if path.join("CMakeCache.txt").is_file() && !artifacts.iter().any(|(_, p)| *p == path) {
+                        artifacts.push(("CMake", path));
+                    }
+                }
+            }
+        }
+
+        if artifacts.is_empty() {
+            return Vec::new();
+        }
+
+        let project_name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let artifact_paths: Vec<&Path> = artifacts.iter().map(|(_, p)| p.as_path()).collect();
+        let (last_activity, source) = match last_commit_time(dir) {
+            Some(time) => (time, "last commit"),
+            None => (last_modified_time(dir, &artifact_paths), "last modified"),
+        };
+
+        artifacts
+            .into_iter()
+            .filter_map(|(kind, path)| {
+                let size = dir_size(&path, &self.errors);
+                (size >= self.min_size).then(|| CleanableItem {
+                    path,
+                    size,
+                    category: CleanupCategory::ProjectArtifacts,
+                    modified: last_activity,
+                    selected: false,
+                    remove_command: None,
+                    note: Some(format!(
+                        "{} project '{}' • {} {}",
+                        kind,
+                        project_name,
+                        source,
+                        format_age(&last_activity)
+                    )),
+                    depth: 0,
+                    needs_privileges: false,
+                })
+            })
+            .collect()
+    }
+}
+
+fn is_skipped_dir(entry: &walkdir::DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+    entry.file_type().is_dir() && (name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()))
+}
+
+/// Time of the last commit touching `dir`, if it's inside a git work tree
+fn last_commit_time(dir: &Path) -> Option<DateTime<Local>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["log", "-1", "--format=%ct", "--", "."])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let timestamp: i64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Local.timestamp_opt(timestamp, 0).single()
+}
+
+/// Newest modification time among the project's own (non-artifact) entries
+fn last_modified_time(dir: &Path, artifacts: &[&Path]) -> DateTime<Local> {
+    let newest = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter(|e| !artifacts.contains(&e.path().as_path()))
+        .filter_map(|e| e.metadata().ok()?.modified().ok())
+        .max();
+
+    newest
+        .or_else(|| std::fs::metadata(dir).ok()?.modified().ok())
+        .map(DateTime::<Local>::from)
+        .unwrap_or_else(Local::now)
+}
+
+fn dir_size(path: &Path, errors: &ScanErrors) -> u64 {
+    WalkDir::new(path)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| errors.ok(e))
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| errors.ok(e.metadata()))
+        .map(|m| m.len())
+        .sum()
+}
+
+fn format_age(time: &DateTime<Local>) -> String {
+    let days = Local::now().signed_duration_since(*time).num_days();
+    if days > 365 {
+        format!("{} years ago", days / 365)
+    } else if days > 30 {
+        format!("{} months ago", days / 30)
+    } else if days > 0 {
+        format!("{} days ago", days)
+    } else {
+        "today".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn scanner(root: &Path) -> ProjectScanner {
+        ProjectScanner {
+            roots: vec![root.to_path_buf()],
+            max_depth: 6,
+            min_size: 0,
+            errors: ScanErrors::new(),
+        }
+    }
+
+    #[test]
+    fn test_finds_artifacts_by_marker() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        fs::create_dir_all(root.join("app/target/debug")).unwrap();
+        fs::write(root.join("app/Cargo.toml"), "[package]").unwrap();
+        fs::write(root.join("app/target/debug/app"), vec![0u8; 4096]).unwrap();
+
+        fs::create_dir_all(root.join("web/node_modules/left-pad")).unwrap();
+        fs::write(root.join("web/package.json"), "{}").unwrap();
+        fs::write(root.join("web/node_modules/left-pad/index.js"), vec![0u8; 1024]).unwrap();
+
+        // No marker file: not a project, so build/ is left alone
+        fs::create_dir_all(root.join("notes/build")).unwrap();
+        fs::write(root.join("notes/build/out.txt"), "x").unwrap();
+
+        let items = scanner(root).scan().unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].path, root.join("app/target"));
+        assert_eq!(items[0].size, 4096);
+        assert_eq!(items[0].category, CleanupCategory::ProjectArtifacts);
+        assert!(items[0].note.as_ref().unwrap().starts_with("Rust project 'app'"));
+        assert_eq!(items[1].path, root.join("web/node_modules"));
+    }
+
+    #[test]
+    fn test_cmake_build_dirs_need_cache_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        fs::write(root.join("CMakeLists.txt"), "project(x)").unwrap();
+        fs::create_dir_all(root.join("cmake-build-debug")).unwrap();
+        fs::write(root.join("cmake-build-debug/CMakeCache.txt"), "cache").unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/index.md"), "docs").unwrap();
+
+        let items = scanner(root).scan().unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, root.join("cmake-build-debug"));
+    }
+
+    #[test]
+    fn test_skips_hidden_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        fs::create_dir_all(root.join(".cache/pkg/node_modules")).unwrap();
+        fs::write(root.join(".cache/pkg/package.json"), "{}").unwrap();
+        fs::write(root.join(".cache/pkg/node_modules/a.js"), "a").unwrap();
+
+        assert!(scanner(root).scan().unwrap().is_empty());
+    }
+}
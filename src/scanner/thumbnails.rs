@@ -0,0 +1,243 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::models::{CleanableItem, CleanupCategory};
+use crate::scanner::errors::ScanErrors;
+
+/// Thumbnails of existing files are only offered once unused for this long
+const DEFAULT_MAX_AGE_DAYS: i64 = 90;
+
+/// Size directories of the freedesktop thumbnail spec, plus failed attempts
+const THUMBNAIL_DIRS: &[&str] = &["normal", "large", "x-large", "xx-large", "fail"];
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Finds freedesktop thumbnails (`~/.cache/thumbnails`) that are orphaned
+/// (their source file is gone) or simply old. Recent thumbnails of existing
+/// files are left alone, since clearing them forces regeneration.
+pub struct ThumbnailScanner {
+    max_age_days: i64,
+    errors: ScanErrors,
+}
+
+impl ThumbnailScanner {
+    pub fn new() -> Self {
+        Self {
+            max_age_days: DEFAULT_MAX_AGE_DAYS,
+            errors: ScanErrors::new(),
+        }
+    }
+
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn scan(&self, roots: &[PathBuf]) -> Result<Vec<CleanableItem>> {
+        let cutoff = Local::now() - Duration::days(self.max_age_days);
+        let mut orphaned = Vec::new();
+        let mut old = Vec::new();
+
+        for root in roots {
+            for dir in THUMBNAIL_DIRS {
+                for entry in WalkDir::new(root.join(dir))
+                    .follow_links(false)
+                    .into_iter()
+                    .filter_map(|e| self.errors.ok(e))
+                {
+                    if !entry.file_type().is_file() {
+                        continue;
+                    }
+                    let metadata = match self.errors.ok(entry.metadata()) {
+                        Some(metadata) => metadata,
+                        None => continue,
+                    };
+                    let modified = metadata
+                        .modified()
+                        .map(DateTime::<Local>::from)
+                        .unwrap_or_else(|_| Local::now());
+                    // Viewers don't rewrite thumbnails they reuse, so the last
+                    // access is the better measure of "unused"
+                    let used = metadata
+                        .accessed()
+                        .map(DateTime::<Local>::from)
+                        .map_or(modified, |accessed| accessed.max(modified));
+
+                    let source = read_thumb_uri(entry.path()).and_then(|uri| uri_to_path(&uri));
+
+                    let mut item = CleanableItem {
+                        path: entry.path().to_path_buf(),
+                        size: metadata.len(),
+                        category: CleanupCategory::Thumbnails,
+                        modified: used,
+                        selected: false,
+                        note: None,
+                        remove_command: None,
+                        depth: 0,
+                        needs_privileges: false,
+                    };
+
+                    match source {
+                        Some(source) if !source.exists() => {
+                            item.note = Some(format!("Orphaned: {} no longer exists", source.display()));
+                            orphaned.push(item);
+                        }
+                        // Existing source, or one we can't check (smb://, trash://)
+                        _ if used < cutoff => {
+                            item.note = Some(format!(
+                                "Old: unused for {} days, regenerated when the file is viewed again",
+                                (Local::now() - used).num_days()
+                            ));
+                            old.push(item);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        // Orphans first: removing them costs nothing
+        orphaned.sort_by_key(|i| std::cmp::Reverse(i.size));
+        old.sort_by_key(|i| std::cmp::Reverse(i.size));
+        orphaned.extend(old);
+        Ok(orphaned)
+    }
+}
+
+/// The `Thumb::URI` tEXt chunk of a thumbnail PNG
+pub fn read_thumb_uri(path: &Path) -> Option<String> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+
+    let mut signature = [0u8; 8];
+    reader.read_exact(&mut signature).ok()?;
+    if signature != PNG_SIGNATURE {
+        return None;
+    }
+
+    loop {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header).ok()?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = &header[4..8];
+
+        if kind == b"IEND" {
+            return None;
+        }
+        if kind != b"tEXt" {
+            // Skip the data and CRC
+            std::io::copy(&mut (&mut reader).take(length as u64 + 4), &mut std::io::sink()).ok()?;
+            continue;
+        }
+
+        let mut data = vec![0u8; length];
+        reader.read_exact(&mut data).ok()?;
+        reader.read_exact(&mut [0u8; 4]).ok()?;
+
+        if let Some(nul) = data.iter().position(|&b| b == 0) {
+            if &data[..nul] == b"Thumb::URI" {
+                // tEXt is Latin-1, but thumbnailers store URIs percent-encoded
+                return Some(String::from_utf8_lossy(&data[nul + 1..]).into_owned());
+            }
+        }
+    }
+}
+
+/// Local path of a `file://` URI; None for other schemes
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    // Skip an optional host (file://localhost/...)
+    let path = &rest[rest.find('/')?..];
+
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut input = path.bytes();
+    while let Some(b) = input.next() {
+        if b == b'%' {
+            let hex = [input.next()?, input.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        Some(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+    }
+    #[cfg(not(unix))]
+    {
+        Some(PathBuf::from(String::from_utf8(bytes).ok()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{Duration as StdDuration, SystemTime};
+    use tempfile::TempDir;
+
+    fn chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut out = (data.len() as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(data);
+        out.extend_from_slice(&[0, 0, 0, 0]); // CRC isn't checked
+        out
+    }
+
+    fn write_thumbnail(path: &Path, uri: &str, days: u64) {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend(chunk(b"IHDR", &[0u8; 13]));
+        png.extend(chunk(b"tEXt", b"Software\0GNOME::ThumbnailFactory"));
+        png.extend(chunk(b"tEXt", format!("Thumb::URI\0{}", uri).as_bytes()));
+        png.extend(chunk(b"IDAT", &[0u8; 64]));
+        png.extend(chunk(b"IEND", &[]));
+        fs::write(path, png).unwrap();
+
+        let time = SystemTime::now() - StdDuration::from_secs(days * 86400);
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_times(fs::FileTimes::new().set_accessed(time).set_modified(time)).unwrap();
+    }
+
+    #[test]
+    fn test_uri_to_path() {
+        assert_eq!(
+            uri_to_path("file:///home/me/My%20Photos/a%C3%A9.jpg"),
+            Some(PathBuf::from("/home/me/My Photos/aé.jpg"))
+        );
+        assert_eq!(uri_to_path("file://localhost/tmp/x.png"), Some(PathBuf::from("/tmp/x.png")));
+        assert_eq!(uri_to_path("smb://server/share/x.png"), None);
+    }
+
+    #[test]
+    fn test_orphaned_and_old_thumbnails() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("thumbnails");
+        fs::create_dir_all(root.join("normal")).unwrap();
+        fs::create_dir_all(root.join("large")).unwrap();
+
+        let source = temp_dir.path().join("photo one.jpg");
+        fs::write(&source, "jpeg").unwrap();
+        let source_uri = format!("file://{}", source.display()).replace(' ', "%20");
+        let missing_uri = format!("file://{}/gone.jpg", temp_dir.path().display());
+
+        write_thumbnail(&root.join("normal/recent.png"), &source_uri, 1);
+        write_thumbnail(&root.join("large/old.png"), &source_uri, 200);
+        write_thumbnail(&root.join("normal/orphan.png"), &missing_uri, 1);
+
+        assert_eq!(read_thumb_uri(&root.join("normal/orphan.png")), Some(missing_uri));
+
+        let items = ThumbnailScanner::new().scan(std::slice::from_ref(&root)).unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].path, root.join("normal/orphan.png"));
+        assert!(items[0].note.as_ref().unwrap().starts_with("Orphaned"));
+        assert_eq!(items[1].path, root.join("large/old.png"));
+        assert!(items[1].note.as_ref().unwrap().starts_with("Old"));
+    }
+}
@@ -0,0 +1,427 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::models::{CleanableItem, CleanupCategory};
+use crate::scanner::errors::ScanErrors;
+
+/// Rustup toolchains not used for this long (and not the default or an
+/// override) are offered for uninstall
+const UNUSED_TOOLCHAIN_DAYS: i64 = 90;
+
+/// How a registry entry is turned into cleanable items
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheKind {
+    /// The whole directory is one item
+    Directory,
+    /// Each installed rustup toolchain is an item, if unused
+    RustupToolchains,
+}
+
+/// A developer tool's cache: where it lives, what clearing it costs, and
+/// the tool's own command for clearing it when deleting files isn't enough
+pub struct DeveloperCache {
+    pub tool: &'static str,
+    /// `~`-relative paths, as in the security tables
+    pub paths: &'static [&'static str],
+    pub kind: CacheKind,
+    pub remove_command: Option<&'static [&'static str]>,
+    pub note: &'static str,
+}
+
+/// The single list of developer caches, shared by the cleanup scanner and
+/// the security whitelist
+pub static DEVELOPER_CACHES: &[DeveloperCache] = &[
+    DeveloperCache {
+        tool: "npm",
+        paths: &["~/.npm"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Package tarballs; re-downloaded on the next install",
+    },
+    DeveloperCache {
+        tool: "Yarn",
+        paths: &["~/.yarn/berry/cache", "~/.cache/yarn", "~/Library/Caches/Yarn"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Package archives; zero-install projects keep their own copy in .yarn/cache",
+    },
+    DeveloperCache {
+        tool: "Cargo",
+        paths: &["~/.cargo/registry"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Crate index and sources; the next build re-downloads them, offline builds fail until then",
+    },
+    DeveloperCache {
+        tool: "Cargo git",
+        paths: &["~/.cargo/git"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Checkouts of git dependencies; re-fetched on the next build",
+    },
+    DeveloperCache {
+        tool: "Gradle",
+        paths: &["~/.gradle/caches"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Dependency and build caches; stop running daemons first (gradle --stop)",
+    },
+    DeveloperCache {
+        tool: "Maven",
+        paths: &["~/.m2/repository"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Local repository; artifacts you only `mvn install`ed and never published are lost",
+    },
+    DeveloperCache {
+        tool: "pub",
+        paths: &["~/.pub-cache/hosted", "~/.pub-cache/git"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Dart/Flutter packages; run `dart pub get` in each project afterwards",
+    },
+    DeveloperCache {
+        tool: "Go modules",
+        paths: &["~/go/pkg/mod"],
+        kind: CacheKind::Directory,
+        // Module files are read-only, so plain deletion fails
+        remove_command: Some(&["go", "clean", "-modcache"]),
+        note: "Downloaded modules (read-only files); cleared with `go clean -modcache`",
+    },
+    DeveloperCache {
+        tool: "Go build",
+        paths: &["~/.cache/go-build", "~/Library/Caches/go-build"],
+        kind: CacheKind::Directory,
+        remove_command: Some(&["go", "clean", "-cache"]),
+        note: "Compiled packages; the next build is a full rebuild",
+    },
+    DeveloperCache {
+        tool: "pip",
+        paths: &["~/.cache/pip", "~/Library/Caches/pip"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Wheels and HTTP cache; packages re-download on the next install",
+    },
+    DeveloperCache {
+        tool: "uv",
+        paths: &["~/.cache/uv", "~/Library/Caches/uv"],
+        kind: CacheKind::Directory,
+        // uv holds a lock on the cache while running
+        remove_command: Some(&["uv", "cache", "clean"]),
+        note: "Environments hardlink from here, so little space may come back while they exist",
+    },
+    DeveloperCache {
+        tool: "Poetry",
+        // Not the whole pypoetry dir: it also holds virtualenvs
+        paths: &[
+            "~/.cache/pypoetry/cache",
+            "~/.cache/pypoetry/artifacts",
+            "~/Library/Caches/pypoetry/cache",
+            "~/Library/Caches/pypoetry/artifacts",
+        ],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Package metadata and artifacts; virtualenvs are left alone",
+    },
+    DeveloperCache {
+        tool: "conda",
+        paths: &["~/miniconda3/pkgs", "~/anaconda3/pkgs", "~/miniforge3/pkgs", "~/.conda/pkgs"],
+        kind: CacheKind::Directory,
+        // Environments hardlink into pkgs; conda knows which are still needed
+        remove_command: Some(&["conda", "clean", "--all", "--yes"]),
+        note: "Package cache; `conda clean --all` keeps what environments still link to",
+    },
+    DeveloperCache {
+        tool: "ccache",
+        paths: &["~/.cache/ccache", "~/.ccache", "~/Library/Caches/ccache"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "C/C++ compiler cache; the next builds are full rebuilds",
+    },
+    DeveloperCache {
+        tool: "sccache",
+        paths: &["~/.cache/sccache", "~/Library/Caches/Mozilla.sccache"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Compiler cache; stop the server first (sccache --stop-server)",
+    },
+    DeveloperCache {
+        tool: "Bazel",
+        paths: &["~/.cache/bazel"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Output bases of every workspace; shut down servers first (bazel shutdown)",
+    },
+    DeveloperCache {
+        tool: "Docker BuildKit",
+        paths: &["~/.local/share/docker/buildkit"],
+        kind: CacheKind::Directory,
+        // The daemon tracks its cache records; deleting files behind its back corrupts them
+        remove_command: Some(&["docker", "builder", "prune", "--all", "--force"]),
+        note: "Rootless build cache; cleared with `docker builder prune` so the daemon stays consistent",
+    },
+    DeveloperCache {
+        tool: "Xcode",
+        paths: &["~/Library/Developer/Xcode/DerivedData"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Build products and indexes; close Xcode first",
+    },
+    DeveloperCache {
+        tool: "Simulator",
+        paths: &["~/Library/Developer/CoreSimulator/Caches"],
+        kind: CacheKind::Directory,
+        remove_command: None,
+        note: "Simulator dyld caches; rebuilt when a simulator boots",
+    },
+    DeveloperCache {
+        tool: "rustup",
+        paths: &["~/.rustup/toolchains"],
+        kind: CacheKind::RustupToolchains,
+        remove_command: None,
+        note: "Toolchain not used for 90+ days, not the default or a directory override",
+    },
+];
+
+/// Finds developer caches from the registry, plus unused rustup toolchains
+pub struct ToolchainScanner {
+    home: Option<PathBuf>,
+    errors: ScanErrors,
+}
+
+impl ToolchainScanner {
+    pub fn new() -> Self {
+        Self {
+            home: dirs::home_dir(),
+            errors: ScanErrors::new(),
+        }
+    }
+
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn scan(&self) -> Result<Vec<CleanableItem>> {
+        let home = match &self.home {
+            Some(home) => home,
+            None => return Ok(Vec::new()),
+        };
+        let mut items = Vec::new();
+
+        for cache in DEVELOPER_CACHES {
+            for path in cache.paths.iter().map(|p| expand_home(p, home)) {
+                if !path.is_dir() {
+                    continue;
+                }
+                match cache.kind {
+                    CacheKind::Directory => {
+                        let size = dir_size(&path, &self.errors);
+                        if size == 0 {
+                            continue;
+                        }
+                        items.push(CleanableItem {
+                            path: path.clone(),
+                            size,
+                            category: CleanupCategory::DeveloperCaches,
+                            modified: modified_time(&path),
+                            selected: false,
+                            note: Some(format!("{}: {}", cache.tool, cache.note)),
+                            remove_command: cache
+                                .remove_command
+                                .map(|cmd| cmd.iter().map(|s| s.to_string()).collect()),
+                            depth: 0,
+                            needs_privileges: false,
+                        });
+                    }
+                    CacheKind::RustupToolchains => {
+                        let rustup_home = path.parent().unwrap_or(&path);
+                        items.extend(unused_toolchains(rustup_home, cache, &self.errors));
+                    }
+                }
+            }
+        }
+
+        items.sort_by_key(|i| std::cmp::Reverse(i.size));
+        Ok(items)
+    }
+}
+
+/// Toolchains that are neither the default nor a directory override, and
+/// whose rustc hasn't run in a while
+fn unused_toolchains(rustup_home: &Path, cache: &DeveloperCache, errors: &ScanErrors) -> Vec<CleanableItem> {
+    let settings = fs::read_to_string(rustup_home.join("settings.toml")).unwrap_or_default();
+    let in_use = toolchains_in_settings(&settings);
+    let cutoff = Local::now() - Duration::days(UNUSED_TOOLCHAIN_DAYS);
+
+    fs::read_dir(rustup_home.join("toolchains"))
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if in_use.iter().any(|t| toolchain_matches(&name, t)) {
+                return None;
+            }
+            let last_used = last_used_time(&entry.path());
+            if last_used >= cutoff {
+                return None;
+            }
+
+            Some(CleanableItem {
+                path: entry.path(),
+                size: dir_size(&entry.path(), errors),
+                category: CleanupCategory::DeveloperCaches,
+                modified: last_used,
+                selected: false,
+                note: Some(format!("{} {}: {}", cache.tool, name, cache.note)),
+                remove_command: Some(vec![
+                    "rustup".to_string(),
+                    "toolchain".to_string(),
+                    "uninstall".to_string(),
+                    name,
+                ]),
+                depth: 0,
+                needs_privileges: false,
+            })
+        })
+        .collect()
+}
+
+/// `default_toolchain` and the `[overrides]` values of rustup's settings.toml
+pub fn toolchains_in_settings(settings: &str) -> HashSet<String> {
+    let mut toolchains = HashSet::new();
+    let mut section = String::new();
+
+    for line in settings.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line.trim_matches(|c| c == '[' || c == ']').to_string();
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim().trim_matches('"')),
+            None => continue,
+        };
+        if (section.is_empty() && key == "default_toolchain") || section == "overrides" {
+            toolchains.insert(value.to_string());
+        }
+    }
+
+    toolchains
+}
+
+/// `stable` in settings names the installed `stable-x86_64-unknown-linux-gnu`
+fn toolchain_matches(installed: &str, configured: &str) -> bool {
+    installed == configured
+        || installed
+            .strip_prefix(configured)
+            .is_some_and(|rest| rest.starts_with('-'))
+}
+
+/// Last access to the toolchain's rustc or cargo, falling back to its mtime
+fn last_used_time(toolchain: &Path) -> DateTime<Local> {
+    ["bin/rustc", "bin/cargo"]
+        .iter()
+        .filter_map(|bin| fs::metadata(toolchain.join(bin)).ok()?.accessed().ok())
+        .max()
+        .map(DateTime::<Local>::from)
+        .unwrap_or_else(|| modified_time(toolchain))
+}
+
+fn expand_home(path: &str, home: &Path) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+fn modified_time(path: &Path) -> DateTime<Local> {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(DateTime::<Local>::from)
+        .unwrap_or_else(|_| Local::now())
+}
+
+fn dir_size(path: &Path, errors: &ScanErrors) -> u64 {
+    WalkDir::new(path)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| errors.ok(e))
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| errors.ok(e.metadata()))
+        .map(|m| m.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::{Duration as StdDuration, SystemTime};
+    use tempfile::TempDir;
+
+    fn write_aged(path: &Path, days: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0u8; 1024]).unwrap();
+        let time = SystemTime::now() - StdDuration::from_secs(days * 86400);
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_times(fs::FileTimes::new().set_accessed(time).set_modified(time)).unwrap();
+    }
+
+    #[test]
+    fn test_registry_paths_are_home_relative() {
+        for cache in DEVELOPER_CACHES {
+            assert!(!cache.paths.is_empty(), "{}", cache.tool);
+            for path in cache.paths {
+                assert!(path.starts_with("~/"), "{}: {}", cache.tool, path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_toolchains_in_settings() {
+        let settings = "default_host_triple = \"x86_64-unknown-linux-gnu\"\n\
+                        default_toolchain = \"stable\"\n\
+                        profile = \"default\"\n\
+                        version = \"12\"\n\n\
+                        [overrides]\n\
+                        \"/home/me/legacy\" = \"1.70.0-x86_64-unknown-linux-gnu\"\n";
+        let toolchains = toolchains_in_settings(settings);
+
+        assert_eq!(toolchains.len(), 2);
+        assert!(toolchains.contains("stable"));
+        assert!(toolchains.contains("1.70.0-x86_64-unknown-linux-gnu"));
+        assert!(toolchain_matches("stable-x86_64-unknown-linux-gnu", "stable"));
+        assert!(!toolchain_matches("stable-x86_64-unknown-linux-gnu", "stab"));
+    }
+
+    #[test]
+    fn test_unused_toolchains() {
+        let temp_dir = TempDir::new().unwrap();
+        let rustup = temp_dir.path().join(".rustup");
+        fs::create_dir_all(&rustup).unwrap();
+        fs::write(rustup.join("settings.toml"), "default_toolchain = \"stable\"\n").unwrap();
+
+        write_aged(&rustup.join("toolchains/stable-x86_64-unknown-linux-gnu/bin/rustc"), 200);
+        write_aged(&rustup.join("toolchains/nightly-2023-01-01-x86_64-unknown-linux-gnu/bin/rustc"), 200);
+        write_aged(&rustup.join("toolchains/beta-x86_64-unknown-linux-gnu/bin/rustc"), 2);
+
+        let scanner = ToolchainScanner {
+            home: Some(temp_dir.path().to_path_buf()),
+            errors: ScanErrors::new(),
+        };
+        let items = scanner.scan().unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, rustup.join("toolchains/nightly-2023-01-01-x86_64-unknown-linux-gnu"));
+        assert_eq!(
+            items[0].remove_command.as_deref().unwrap().join(" "),
+            "rustup toolchain uninstall nightly-2023-01-01-x86_64-unknown-linux-gnu"
+        );
+    }
+}
@@ -3,17 +3,35 @@ use std::path::{Path, PathBuf};
 use std::fs;
 
 use crate::models::TreeMapItem;
+use crate::scanner::errors::ScanErrors;
 
-pub struct TreeMapScanner;
+pub struct TreeMapScanner {
+    max_depth: usize,
+    errors: ScanErrors,
+}
 
 impl TreeMapScanner {
     pub fn new() -> Self {
-        Self
+        Self {
+            max_depth: 3, // Max depth of 3 levels
+            errors: ScanErrors::new(),
+        }
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Where paths the scan had to skip are recorded
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
     }
 
     /// Scan a directory and build a tree map
     pub fn scan(&self, root_path: &Path) -> Result<TreeMapItem> {
-        self.scan_directory(root_path, 0, 3) // Max depth of 3 levels
+        self.scan_directory(root_path, 0, self.max_depth)
     }
 
     fn scan_directory(&self, path: &Path, current_depth: usize, max_depth: usize) -> Result<TreeMapItem> {
@@ -31,10 +49,10 @@ impl TreeMapScanner {
         let mut total_size = 0u64;
 
         // Read directory entries
-        if let Ok(entries) = fs::read_dir(path) {
-            for entry in entries.flatten() {
-                if let Ok(child_metadata) = entry.metadata() {
-                    let child_path = entry.path();
+        if let Some(entries) = self.errors.ok_io(path, fs::read_dir(path)) {
+            for entry in entries.filter_map(|e| self.errors.ok_io(path, e)) {
+                let child_path = entry.path();
+                if let Some(child_metadata) = self.errors.ok_io(&child_path, entry.metadata()) {
 
                     // Skip hidden files and system files
                     if let Some(name) = child_path.file_name() {
@@ -43,12 +61,24 @@ impl TreeMapScanner {
                         }
                     }
 
+                    if child_metadata.is_symlink() {
+                        if let Err(err) = fs::metadata(&child_path) {
+                            self.errors.record_io(&child_path, &err);
+                        }
+                    }
+
                     let child = if child_metadata.is_dir() {
                         if current_depth < max_depth {
                             // Recursively scan subdirectory
                             match self.scan_directory(&child_path, current_depth + 1, max_depth) {
                                 Ok(child_item) => child_item,
-                                Err(_) => continue, // Skip directories we can't read
+                                Err(err) => {
+                                    // Skip directories we can't read
+                                    if let Some(io) = err.downcast_ref::<std::io::Error>() {
+                                        self.errors.record_io(&child_path, io);
+                                    }
+                                    continue;
+                                }
                             }
                         } else {
                             // At max depth, just create a leaf node with the directory size
@@ -79,9 +109,9 @@ impl TreeMapScanner {
     /// Quickly calculate directory size without deep recursion
     fn quick_dir_size(&self, path: &Path) -> u64 {
         let mut total = 0u64;
-        if let Ok(entries) = fs::read_dir(path) {
-            for entry in entries.flatten() {
-                if let Ok(metadata) = entry.metadata() {
+        if let Some(entries) = self.errors.ok_io(path, fs::read_dir(path)) {
+            for entry in entries.filter_map(|e| self.errors.ok_io(path, e)) {
+                if let Some(metadata) = self.errors.ok_io(&entry.path(), entry.metadata()) {
                     total += metadata.len();
                 }
             }
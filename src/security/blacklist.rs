@@ -1,3 +1,5 @@
+use crate::scanner::toolchains::DEVELOPER_CACHES;
+
 /// System directories that must NEVER be deleted
 /// This is a security-critical list - any modification must be carefully reviewed
 #[cfg(target_os = "macos")]
@@ -80,6 +82,53 @@ pub static BLACKLISTED_PATHS: &[&str] = &[
     "~/Downloads",
 ];
 
+/// Directories that must never be deleted themselves, although the files
+/// inside them can be (e.g. a project folder under `/home/<user>`)
+#[cfg(target_os = "macos")]
+pub static PROTECTED_ROOTS: &[&str] = &[
+    "/",
+    "/Users",
+    "/Volumes",
+    "/Applications",
+    "/Library",
+    "~",
+    "~/Library",
+];
+
+#[cfg(target_os = "linux")]
+pub static PROTECTED_ROOTS: &[&str] = &[
+    "/",
+    "/home",
+    "/media",
+    "/mnt",
+    "/opt",
+    "/srv",
+    "/usr",
+    "/var",
+    "~",
+    "~/.config",
+    "~/.local",
+    "~/.local/share",
+];
+
+/// Credentials and keys that stay blocked even below a root the user chose
+/// to act on (see `PathSanitizer::with_allowed_root`)
+pub static SENSITIVE_HOME_PATHS: &[&str] = &[
+    "~/.ssh",
+    "~/.gnupg",
+    "~/.password-store",
+    "~/.pki",
+    "~/.local/share/keyrings",
+    "~/Library/Keychains",
+];
+
+/// Where the home directories of all users live
+#[cfg(target_os = "macos")]
+pub static HOME_BASE: &str = "/Users";
+
+#[cfg(target_os = "linux")]
+pub static HOME_BASE: &str = "/home";
+
 /// Whitelisted paths that are safe to clean (with additional validation)
 #[cfg(target_os = "macos")]
 pub static WHITELISTED_PATHS: &[&str] = &[
@@ -105,19 +154,8 @@ pub static WHITELISTED_PATHS: &[&str] = &[
     "/var/log",
 ];
 
-/// Developer-specific cache directories (cross-platform)
-pub static DEVELOPER_CACHE_PATHS: &[&str] = &[
-    "~/.npm",
-    "~/.yarn",
-    "~/.cargo/registry",
-    "~/.cargo/git",
-    "~/.gradle/caches",
-    "~/.m2/repository",
-    "~/.pub-cache",
-    "~/Library/Developer/Xcode/DerivedData",
-    "~/Library/Developer/CoreSimulator/Caches",
-    "~/.rustup/toolchains",
-    "~/.cache/pip",
-    "~/.cache/yarn",
-    "~/.cache/go-build",
-];
+/// Developer-specific cache directories (cross-platform), from the
+/// toolchain registry shared with the cleanup scanner
+pub fn developer_cache_paths() -> impl Iterator<Item = &'static str> {
+    DEVELOPER_CACHES.iter().flat_map(|cache| cache.paths.iter().copied())
+}
@@ -0,0 +1,403 @@
+use anyhow::Result;
+use chrono::Local;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::models::{SecurityThreat, ThreatSeverity, ThreatType};
+use crate::scanner::browsers::{known_browsers, Browser, Engine};
+use crate::scanner::errors::ScanErrors;
+use crate::security::signatures::SignatureDatabase;
+
+/// Permissions that let an extension read or change what the user does, and
+/// how much each adds to its risk score
+const RISKY_PERMISSIONS: &[(&str, u32)] = &[
+    ("<all_urls>", 3),
+    ("nativeMessaging", 3),
+    ("debugger", 3),
+    ("webRequest", 2),
+    ("webRequestBlocking", 2),
+    ("proxy", 2),
+    ("management", 2),
+    ("cookies", 1),
+    ("history", 1),
+    ("tabs", 1),
+    ("webNavigation", 1),
+    ("clipboardRead", 1),
+    ("downloads", 1),
+    ("privacy", 1),
+    ("scripting", 1),
+    ("declarativeNetRequest", 1),
+];
+
+/// Host patterns equivalent to `<all_urls>`
+const ALL_HOSTS: &[&str] = &["*://*/*", "http://*/*", "https://*/*", "<all_urls>"];
+
+/// Firefox add-ons shipped with the browser itself
+const FIREFOX_BUILTIN_LOCATIONS: &[&str] = &["app-builtin", "app-system-defaults", "app-system-addons"];
+
+/// An installed browser extension
+#[derive(Debug, Clone, Serialize)]
+pub struct Extension {
+    pub browser: &'static str,
+    pub profile: String,
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    /// API permissions and host patterns it asked for
+    pub permissions: Vec<String>,
+    pub path: PathBuf,
+    /// Sum of the weights of its risky permissions
+    pub risk_score: u32,
+    pub risk: Option<ThreatSeverity>,
+    /// Id of the signature listing this extension
+    pub signature_id: Option<String>,
+}
+
+impl Extension {
+    /// The permissions that count towards the risk score
+    pub fn risky_permissions(&self) -> Vec<&str> {
+        let mut risky: Vec<&str> = Vec::new();
+        for permission in &self.permissions {
+            let name = if ALL_HOSTS.contains(&permission.as_str()) {
+                "<all_urls>"
+            } else {
+                permission.as_str()
+            };
+            if RISKY_PERMISSIONS.iter().any(|(p, _)| *p == name) && !risky.contains(&name) {
+                risky.push(name);
+            }
+        }
+        risky
+    }
+
+    fn score(&mut self) {
+        self.risk_score = self
+            .risky_permissions()
+            .iter()
+            .filter_map(|name| RISKY_PERMISSIONS.iter().find(|(p, _)| p == name))
+            .map(|(_, weight)| weight)
+            .sum();
+        self.risk = match self.risk_score {
+            0 => None,
+            1..=2 => Some(ThreatSeverity::Low),
+            3..=5 => Some(ThreatSeverity::Medium),
+            _ => Some(ThreatSeverity::High),
+        };
+    }
+
+    /// A threat if a signature lists the extension or it's high risk.
+    /// Permissions alone are no proof (password managers ask for most of
+    /// them), so those are reported as Medium for review.
+    fn threat(&self, database: &SignatureDatabase) -> Option<SecurityThreat> {
+        let signature = self.signature_id.as_deref().and_then(|id| database.signature(id));
+        let (severity, signature_id, description) = match signature {
+            Some(signature) => (
+                signature.severity,
+                signature.id.clone(),
+                format!("{}: {}", signature.name, signature.description),
+            ),
+            None if self.risk == Some(ThreatSeverity::High) => (
+                ThreatSeverity::Medium,
+                "extension-permissions".to_string(),
+                format!("Broad access: {}", self.risky_permissions().join(", ")),
+            ),
+            None => return None,
+        };
+        Some(SecurityThreat {
+            path: self.path.clone(),
+            threat_type: ThreatType::BrowserExtension,
+            severity,
+            description: format!(
+                "{} {} ({} profile '{}'). {}",
+                self.name, self.version, self.browser, self.profile, description
+            ),
+            detected_at: Local::now(),
+            signature_id,
+            remediation: None,
+            fix_mode: None,
+        })
+    }
+}
+
+/// Lists the extensions of every profile of every known browser
+pub struct ExtensionScanner {
+    browsers: Vec<Browser>,
+    database: Arc<SignatureDatabase>,
+    errors: ScanErrors,
+}
+
+impl ExtensionScanner {
+    pub fn new(database: Arc<SignatureDatabase>) -> Self {
+        Self {
+            browsers: dirs::home_dir().map(|home| known_browsers(&home)).unwrap_or_default(),
+            database,
+            errors: ScanErrors::new(),
+        }
+    }
+
+    /// Where paths the scan had to skip are recorded
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Every extension found, scored and matched against the signatures
+    pub fn extensions(&self) -> Vec<Extension> {
+        let mut extensions = Vec::new();
+        for browser in &self.browsers {
+            for profile in browser.profiles() {
+                let profile_dir = browser.data_dir.join(&profile.dir);
+                let found = match browser.engine {
+                    Engine::Chromium => self.chromium_extensions(&profile_dir),
+                    Engine::Firefox => self.firefox_extensions(&profile_dir),
+                };
+                for mut extension in found {
+                    extension.browser = browser.name;
+                    extension.profile = profile.name.clone();
+                    extension.score();
+                    extension.signature_id = self.database.match_extension(&extension.id).map(|s| s.id.clone());
+                    extensions.push(extension);
+                }
+            }
+        }
+        extensions
+    }
+
+    /// Extensions listed by a signature or with high-risk permissions
+    pub fn scan(&self) -> Result<Vec<SecurityThreat>> {
+        Ok(self
+            .extensions()
+            .iter()
+            .filter_map(|e| e.threat(&self.database))
+            .collect())
+    }
+
+    /// `Extensions/<id>/<version>/manifest.json`, newest version of each
+    fn chromium_extensions(&self, profile_dir: &Path) -> Vec<Extension> {
+        let extensions_dir = profile_dir.join("Extensions");
+        let mut extensions = Vec::new();
+
+        for id_dir in self.subdirs(&extensions_dir) {
+            let Some(version_dir) = self.subdirs(&id_dir).into_iter().max_by(|a, b| compare_versions(a, b)) else {
+                continue;
+            };
+            let manifest_path = version_dir.join("manifest.json");
+            let Some(manifest) = self.read_json(&manifest_path) else { continue };
+
+            let mut permissions = strings(&manifest["permissions"]);
+            permissions.extend(strings(&manifest["host_permissions"]));
+            if let Some(scripts) = manifest["content_scripts"].as_array() {
+                for script in scripts {
+                    permissions.extend(strings(&script["matches"]));
+                }
+            }
+            dedup(&mut permissions);
+
+            let id = file_name(&id_dir);
+            let name = manifest["name"].as_str().unwrap_or(&id).to_string();
+            extensions.push(Extension {
+                browser: "",
+                profile: String::new(),
+                name: localized(&version_dir, &manifest, &name).unwrap_or(name),
+                version: manifest["version"].as_str().unwrap_or_default().to_string(),
+                permissions,
+                path: id_dir.clone(),
+                id,
+                risk_score: 0,
+                risk: None,
+                signature_id: None,
+            });
+        }
+        extensions
+    }
+
+    /// Add-ons of type `extension` in `extensions.json`, without the built-in ones
+    fn firefox_extensions(&self, profile_dir: &Path) -> Vec<Extension> {
+        let Some(json) = self.read_json(&profile_dir.join("extensions.json")) else {
+            return Vec::new();
+        };
+        let Some(addons) = json["addons"].as_array() else {
+            return Vec::new();
+        };
+
+        addons
+            .iter()
+            .filter(|addon| addon["type"].as_str() == Some("extension"))
+            .filter(|addon| {
+                !FIREFOX_BUILTIN_LOCATIONS.contains(&addon["location"].as_str().unwrap_or_default())
+            })
+            .filter_map(|addon| {
+                let id = addon["id"].as_str()?.to_string();
+                let mut permissions = strings(&addon["userPermissions"]["permissions"]);
+                permissions.extend(strings(&addon["userPermissions"]["origins"]));
+                dedup(&mut permissions);
+                let path = addon["path"]
+                    .as_str()
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| profile_dir.join("extensions").join(format!("{}.xpi", id)));
+                Some(Extension {
+                    browser: "",
+                    profile: String::new(),
+                    name: addon["defaultLocale"]["name"].as_str().unwrap_or(&id).to_string(),
+                    version: addon["version"].as_str().unwrap_or_default().to_string(),
+                    permissions,
+                    path,
+                    id,
+                    risk_score: 0,
+                    risk: None,
+                    signature_id: None,
+                })
+            })
+            .collect()
+    }
+
+    fn subdirs(&self, dir: &Path) -> Vec<PathBuf> {
+        if !dir.is_dir() {
+            return Vec::new();
+        }
+        let Some(entries) = self.errors.ok_io(dir, fs::read_dir(dir)) else {
+            return Vec::new();
+        };
+        let mut dirs: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_dir() && !file_name(p).starts_with('.') && file_name(p) != "Temp")
+            .collect();
+        dirs.sort();
+        dirs
+    }
+
+    fn read_json(&self, path: &Path) -> Option<Value> {
+        let contents = self.errors.ok_io(path, fs::read_to_string(path))?;
+        serde_json::from_str(&contents).ok()
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Drop repeated entries, keeping the first
+fn dedup(items: &mut Vec<String>) {
+    let mut seen = HashSet::new();
+    items.retain(|item| seen.insert(item.clone()));
+}
+
+/// String entries of a JSON array; object permissions (MV2 `fileSystem`) are skipped
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| items.iter().filter_map(|i| i.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+/// Chromium version directories are named `<version>_<n>`; compare numerically
+fn compare_versions(a: &Path, b: &Path) -> std::cmp::Ordering {
+    let parts = |p: &Path| -> Vec<u64> {
+        file_name(p)
+            .split(['.', '_'])
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parts(a).cmp(&parts(b))
+}
+
+/// Resolve a `__MSG_key__` name from the extension's default locale
+fn localized(version_dir: &Path, manifest: &Value, name: &str) -> Option<String> {
+    let key = name.strip_prefix("__MSG_")?.strip_suffix("__")?.to_lowercase();
+    let locale = manifest["default_locale"].as_str()?;
+    let messages = fs::read_to_string(version_dir.join("_locales").join(locale).join("messages.json")).ok()?;
+    let messages: Value = serde_json::from_str(&messages).ok()?;
+    messages
+        .as_object()?
+        .iter()
+        .find(|(k, _)| k.to_lowercase() == key)
+        .and_then(|(_, v)| v["message"].as_str())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn database() -> Arc<SignatureDatabase> {
+        let json = r#"{"version": "1.0.0", "extensions": [{
+            "id": "coupon-injector", "name": "Coupon Injector", "severity": "high",
+            "extensionIDs": ["bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"]
+        }]}"#;
+        Arc::new(SignatureDatabase::from_json(json).unwrap())
+    }
+
+    #[test]
+    fn test_inventory_and_scoring() {
+        let temp_dir = TempDir::new().unwrap();
+        let chrome = temp_dir.path().join("chrome");
+        let firefox = temp_dir.path().join("firefox");
+
+        write(&chrome.join("Local State"), r#"{"profile": {"info_cache": {"Default": {"name": "Work"}}}}"#);
+        let ext = chrome.join("Default/Extensions/aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa");
+        write(&ext.join("1.2.0_0/manifest.json"), r#"{"name": "Old", "version": "1.2.0"}"#);
+        write(
+            &ext.join("1.10.0_0/manifest.json"),
+            r#"{"name": "__MSG_appName__", "version": "1.10.0", "default_locale": "en",
+                "permissions": ["storage", "webRequest", "nativeMessaging"],
+                "content_scripts": [{"matches": ["https://*/*"], "js": ["c.js"]}]}"#,
+        );
+        write(&ext.join("1.10.0_0/_locales/en/messages.json"), r#"{"appName": {"message": "Helper"}}"#);
+        write(
+            &chrome.join("Default/Extensions/bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb/2.0_0/manifest.json"),
+            r#"{"name": "Coupons", "version": "2.0", "permissions": ["storage"]}"#,
+        );
+
+        write(&firefox.join("profiles.ini"), "[Profile0]\nName=default\nPath=abcd.default\n");
+        write(
+            &firefox.join("abcd.default/extensions.json"),
+            r#"{"addons": [
+                {"id": "ublock@example.org", "type": "extension", "location": "app-profile", "version": "1.0",
+                 "defaultLocale": {"name": "Blocker"},
+                 "userPermissions": {"permissions": ["tabs"], "origins": ["<all_urls>"]}},
+                {"id": "screenshots@mozilla.org", "type": "extension", "location": "app-system-defaults"},
+                {"id": "en-US@dictionaries", "type": "dictionary", "location": "app-profile"}
+            ]}"#,
+        );
+
+        let scanner = ExtensionScanner {
+            browsers: vec![
+                Browser { name: "Chrome", engine: Engine::Chromium, data_dir: chrome, cache_dir: None, processes: &[] },
+                Browser { name: "Firefox", engine: Engine::Firefox, data_dir: firefox, cache_dir: None, processes: &[] },
+            ],
+            database: database(),
+            errors: ScanErrors::new(),
+        };
+        let extensions = scanner.extensions();
+        let names: Vec<&str> = extensions.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Helper", "Coupons", "Blocker"]);
+
+        let helper = &extensions[0];
+        assert_eq!(helper.version, "1.10.0");
+        assert_eq!(helper.profile, "Work");
+        assert_eq!(helper.risky_permissions(), vec!["webRequest", "nativeMessaging", "<all_urls>"]);
+        assert_eq!(helper.risk, Some(ThreatSeverity::High));
+
+        assert_eq!(extensions[1].signature_id.as_deref(), Some("coupon-injector"));
+        assert_eq!(extensions[2].risk, Some(ThreatSeverity::Medium));
+
+        let threats = scanner.scan().unwrap();
+        let found: Vec<(&str, ThreatSeverity)> =
+            threats.iter().map(|t| (t.signature_id.as_str(), t.severity)).collect();
+        assert_eq!(
+            found,
+            vec![("extension-permissions", ThreatSeverity::Medium), ("coupon-injector", ThreatSeverity::High)]
+        );
+    }
+}
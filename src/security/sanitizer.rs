@@ -2,7 +2,8 @@ use anyhow::{anyhow, Result};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
-use super::blacklist::BLACKLISTED_PATHS;
+use super::blacklist::{BLACKLISTED_PATHS, HOME_BASE, PROTECTED_ROOTS, SENSITIVE_HOME_PATHS};
+use crate::system::privileges;
 
 #[derive(Debug, Clone)]
 pub enum ValidationError {
@@ -34,6 +35,11 @@ pub struct PathSanitizer {
     min_age_days: u64,
     /// Whether to enforce age protection
     enforce_age_protection: bool,
+    /// Directory the user chose to act on; paths strictly inside it and in
+    /// the user's own home pass the home base prefix (`/home`, `/Users`)
+    allowed_root: Option<PathBuf>,
+    /// The invoking user's home, the only one the allowed root opens up
+    own_home: Option<PathBuf>,
 }
 
 impl Default for PathSanitizer {
@@ -41,6 +47,8 @@ impl Default for PathSanitizer {
         Self {
             min_age_days: 7,
             enforce_age_protection: true,
+            allowed_root: None,
+            own_home: None,
         }
     }
 }
@@ -50,6 +58,8 @@ impl PathSanitizer {
         Self {
             min_age_days,
             enforce_age_protection,
+            allowed_root: None,
+            own_home: None,
         }
     }
 
@@ -135,6 +145,14 @@ impl PathSanitizer {
     /// Check if path is in the blacklist
     fn check_blacklist(&self, path: &Path) -> Result<()> {
         let canonical_str = path.to_str().ok_or_else(|| anyhow!("Invalid UTF-8 in path"))?;
+        let blacklisted_error = || anyhow!(ValidationError::Blacklisted(canonical_str.to_string()));
+
+        // Never lifted, whatever root was allowed
+        for sensitive in SENSITIVE_HOME_PATHS.iter() {
+            if path.starts_with(self.expand_home(Path::new(sensitive))?) {
+                return Err(blacklisted_error());
+            }
+        }
 
         for blacklisted in BLACKLISTED_PATHS.iter() {
             let blacklisted_expanded = self.expand_home(Path::new(blacklisted))?;
@@ -144,15 +162,38 @@ impl PathSanitizer {
 
             // Check if path starts with blacklisted directory
             if canonical_str == blacklisted_str || canonical_str.starts_with(&format!("{}/", blacklisted_str)) {
-                return Err(anyhow!(ValidationError::Blacklisted(
-                    canonical_str.to_string()
-                )));
+                if self.is_allowed_below_root(path, &blacklisted_expanded) {
+                    continue;
+                }
+                return Err(blacklisted_error());
+            }
+        }
+
+        // Protected roots only guard the directory itself, not its contents
+        for protected in PROTECTED_ROOTS.iter() {
+            if path == self.expand_home(Path::new(protected))? {
+                return Err(blacklisted_error());
             }
         }
 
         Ok(())
     }
 
+    /// Whether the allowed root lifts `blacklisted` for `path`. Only the home
+    /// base is ever lifted, for paths strictly inside both the root and the
+    /// invoking user's own home; system entries always stay blocked.
+    fn is_allowed_below_root(&self, path: &Path, blacklisted: &Path) -> bool {
+        let Some(root) = &self.allowed_root else {
+            return false;
+        };
+        if blacklisted != Path::new(HOME_BASE) || path == root || !path.starts_with(root) {
+            return false;
+        }
+        self.own_home
+            .as_ref()
+            .is_some_and(|home| path != home && path.starts_with(home))
+    }
+
     /// Check if file was modified recently (age protection)
     fn check_age_protection(&self, path: &Path) -> Result<()> {
         if !path.exists() {
@@ -188,6 +229,25 @@ impl PathSanitizer {
         self
     }
 
+    /// Allow acting on what's inside `root` (a scanned tree, or the user's
+    /// home for quarantine) where the home base prefix (`/home`, `/Users`)
+    /// covers it. Only the user's own home opens up; system directories,
+    /// sensitive dot-directories, other users' homes and the root itself
+    /// stay blocked.
+    pub fn with_allowed_root(mut self, root: &Path) -> Self {
+        let root = self.expand_home(root).unwrap_or_else(|_| root.to_path_buf());
+        self.allowed_root = Some(root.canonicalize().unwrap_or(root));
+        let home = privileges::invoking_user_home().or_else(dirs::home_dir);
+        self.own_home = home.map(|home| home.canonicalize().unwrap_or(home));
+        self
+    }
+
+    #[cfg(test)]
+    fn with_own_home(mut self, home: &Path) -> Self {
+        self.own_home = Some(home.to_path_buf());
+        self
+    }
+
     /// Set minimum age in days
     pub fn with_min_age(mut self, days: u64) -> Self {
         self.min_age_days = days;
@@ -211,6 +271,45 @@ mod tests {
         assert!(sanitizer.sanitize_path(Path::new("/usr/bin")).is_err());
     }
 
+    #[test]
+    fn test_protected_roots() {
+        let sanitizer = PathSanitizer::default().without_age_protection();
+
+        // The home directory itself is protected, files inside it are not
+        let home = dirs::home_dir().unwrap();
+        assert!(sanitizer.sanitize_path(&home).is_err());
+        assert!(sanitizer.sanitize_path(Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn test_allowed_root() {
+        let home = dirs::home_dir().unwrap().canonicalize().unwrap();
+        let allowed = PathSanitizer::default().without_age_protection().with_allowed_root(&home);
+        assert!(allowed.sanitize_path(&home).is_err());
+        let err = allowed.sanitize_path(&home.join(".ssh")).unwrap_err();
+        assert!(err.to_string().contains("blacklisted"), "{}", err);
+
+        // System directories stay blocked whatever root was scanned
+        for (root, path) in [("/etc", "/etc/passwd"), ("/usr", "/usr/bin/ls"), ("/", "/usr/bin/ls")] {
+            let scanned = PathSanitizer::default().without_age_protection().with_allowed_root(Path::new(root));
+            let err = scanned.sanitize_path(Path::new(path)).unwrap_err();
+            assert!(err.to_string().contains("blacklisted"), "{}", err);
+        }
+
+        // Only the home base opens up, and only inside the user's own home
+        let base = Path::new(HOME_BASE);
+        let alice = base.join("alice");
+        let project = PathSanitizer::default().with_allowed_root(&alice.join("proj")).with_own_home(&alice);
+        assert!(project.is_allowed_below_root(&alice.join("proj/build.log"), base));
+        assert!(!project.is_allowed_below_root(&alice.join("proj"), base));
+        assert!(!project.is_allowed_below_root(&alice.join("notes.txt"), base));
+        assert!(!project.is_allowed_below_root(&alice.join("proj/build.log"), Path::new("/etc")));
+
+        let all_homes = PathSanitizer::default().with_allowed_root(base).with_own_home(&alice);
+        assert!(all_homes.is_allowed_below_root(&alice.join("notes.txt"), base));
+        assert!(!all_homes.is_allowed_below_root(&base.join("bob/.bashrc"), base));
+    }
+
     #[test]
     fn test_age_protection() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,674 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::Local;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use walkdir::WalkDir;
+
+use crate::models::{SecurityThreat, ThreatSeverity, ThreatType};
+use crate::scanner::errors::ScanErrors;
+use crate::scanner::pattern::{glob_to_regex, Pattern};
+use crate::security::ioc::Ioc;
+use crate::security::rules::{self, Rule};
+
+/// The database shipped with surge
+const BUNDLED_SIGNATURES: &str = include_str!("../../Resources/signatures.json");
+
+/// Content rules shipped next to it
+const BUNDLED_RULES: &[(&str, &str)] = &[(
+    "shell-droppers.rules",
+    include_str!("../../Resources/shell-droppers.rules"),
+)];
+
+/// Major schema version this build understands
+const SCHEMA_MAJOR: &str = "1";
+
+/// Directories never worth descending into while looking for threats
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", ".git", ".cache", "__pycache__"];
+
+// Schema of Resources/signatures.json
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignatureFile {
+    version: String,
+    last_updated: Option<String>,
+    #[serde(default)]
+    malware: Vec<MalwareEntry>,
+    #[serde(default)]
+    extensions: Vec<ExtensionEntry>,
+    #[serde(default)]
+    hashes: Vec<HashEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MalwareEntry {
+    id: String,
+    name: String,
+    #[serde(rename = "type")]
+    kind: MalwareKind,
+    severity: ThreatSeverity,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    path_patterns: Vec<String>,
+    #[serde(default)]
+    name_patterns: Vec<String>,
+    #[serde(default)]
+    bundle_identifiers: Vec<String>,
+    #[serde(default)]
+    references: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExtensionEntry {
+    id: String,
+    name: String,
+    severity: ThreatSeverity,
+    #[serde(default)]
+    description: String,
+    #[serde(rename = "extensionIDs", default)]
+    extension_ids: Vec<String>,
+    #[serde(default)]
+    references: Vec<String>,
+}
+
+/// A known-bad file by its MD5, SHA-1 or SHA-256 digest
+#[derive(Debug, Deserialize)]
+struct HashEntry {
+    hash: String,
+    name: String,
+    #[serde(default = "default_hash_severity")]
+    severity: ThreatSeverity,
+}
+
+fn default_hash_severity() -> ThreatSeverity {
+    ThreatSeverity::Critical
+}
+
+/// The `type` of a malware entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MalwareKind {
+    Adware,
+    Malware,
+    Spyware,
+    Ransomware,
+    /// Types added by newer databases
+    #[serde(other)]
+    Other,
+}
+
+impl MalwareKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Adware => "Adware",
+            Self::Malware => "Malware",
+            Self::Spyware => "Spyware",
+            Self::Ransomware => "Ransomware",
+            Self::Other => "Unwanted software",
+        }
+    }
+}
+
+/// A database entry with its patterns compiled.
+///
+/// Name patterns, bundle identifiers and extension IDs identify a threat;
+/// path patterns only narrow where it's looked for, since on their own they
+/// match plenty of legitimate files (`*/LaunchAgents/com.*.plist`).
+#[derive(Debug)]
+pub struct Signature {
+    pub id: String,
+    pub name: String,
+    pub kind: MalwareKind,
+    pub severity: ThreatSeverity,
+    pub description: String,
+    pub references: Vec<String>,
+    paths: Vec<Regex>,
+    names: Vec<NamePattern>,
+    bundle_ids: Vec<Regex>,
+    extension_ids: HashSet<String>,
+}
+
+impl Signature {
+    fn from_malware(entry: MalwareEntry) -> Result<Self> {
+        let compile = |globs: &[String]| -> Result<Vec<Regex>> {
+            globs.iter().map(|g| glob_to_regex(g)).collect()
+        };
+        let names = entry
+            .name_patterns
+            .iter()
+            .map(|p| NamePattern::parse(p))
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Signature '{}'", entry.id))?;
+
+        Ok(Self {
+            paths: compile(&entry.path_patterns).with_context(|| format!("Signature '{}'", entry.id))?,
+            bundle_ids: compile(&entry.bundle_identifiers).with_context(|| format!("Signature '{}'", entry.id))?,
+            names,
+            extension_ids: HashSet::new(),
+            id: entry.id,
+            name: entry.name,
+            kind: entry.kind,
+            severity: entry.severity,
+            description: entry.description,
+            references: entry.references,
+        })
+    }
+
+    fn from_extension(entry: ExtensionEntry) -> Self {
+        Self {
+            id: entry.id,
+            name: entry.name,
+            kind: MalwareKind::Adware,
+            severity: entry.severity,
+            description: entry.description,
+            references: entry.references,
+            paths: Vec::new(),
+            names: Vec::new(),
+            bundle_ids: Vec::new(),
+            extension_ids: entry.extension_ids.into_iter().map(|id| id.to_lowercase()).collect(),
+        }
+    }
+
+    /// Whether the entry can match anything (placeholder entries can't)
+    pub fn is_active(&self) -> bool {
+        !self.names.is_empty() || !self.bundle_ids.is_empty() || !self.extension_ids.is_empty()
+    }
+
+    /// Whether `path` is an instance of this threat
+    pub fn matches(&self, path: &Path) -> bool {
+        let full = path.to_string_lossy();
+        if !self.paths.is_empty() && !self.paths.iter().any(|re| re.is_match(&full)) {
+            return false;
+        }
+
+        self.names.iter().any(|p| p.matches(path))
+            || bundle_identifier(path).is_some_and(|id| self.bundle_ids.iter().any(|re| re.is_match(&id)))
+            || extension_id(path).is_some_and(|id| self.extension_ids.contains(&id))
+    }
+}
+
+/// A `namePatterns` entry. Plain names match a whole word of the file name,
+/// so `proton` flags `proton-agent` but not `ProtonVPN`; globs and `re:`
+/// patterns work as in search.
+#[derive(Debug)]
+enum NamePattern {
+    Word(String),
+    Pattern(Pattern),
+}
+
+impl NamePattern {
+    fn parse(query: &str) -> Result<Self> {
+        if query.starts_with("re:") || query.contains(['*', '?', '[', '/']) {
+            Ok(Self::Pattern(Pattern::parse(query)?))
+        } else {
+            Ok(Self::Word(query.to_lowercase()))
+        }
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        match self {
+            Self::Word(word) => path.file_name().is_some_and(|name| {
+                name.to_string_lossy()
+                    .to_lowercase()
+                    .split(|c: char| !c.is_alphanumeric())
+                    .any(|part| part == word)
+            }),
+            Self::Pattern(pattern) => pattern.matches_path(path),
+        }
+    }
+}
+
+/// A validated, compiled signature database
+#[derive(Debug)]
+pub struct SignatureDatabase {
+    pub version: String,
+    pub last_updated: Option<String>,
+    pub signatures: Vec<Signature>,
+    /// IOC hashes from the `hashes` section
+    pub hashes: Vec<Ioc>,
+    /// Content rules from the `*.rules` files next to the database
+    pub rules: Vec<Rule>,
+}
+
+impl SignatureDatabase {
+    /// The database shipped with surge
+    pub fn bundled() -> Result<Self> {
+        let mut database = Self::from_json(BUNDLED_SIGNATURES).context("Bundled signature database")?;
+        for (source, contents) in BUNDLED_RULES {
+            database.rules.extend(rules::parse_rules(contents, source)?);
+        }
+        rules::check_unique(&database.rules)?;
+        Ok(database)
+    }
+
+    /// The database in `path`, with the rule files in its directory
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut database = Self::from_json(&json)
+            .with_context(|| format!("Invalid signature database {}", path.display()))?;
+        if let Some(dir) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            database.rules = rules::load_dir(dir)?;
+        } else {
+            database.rules = rules::load_dir(Path::new("."))?;
+        }
+        rules::check_unique(&database.rules)?;
+        Ok(database)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let file: SignatureFile = serde_json::from_str(json)?;
+
+        if file.version.split('.').next() != Some(SCHEMA_MAJOR) {
+            return Err(anyhow!(
+                "Unsupported signature schema version {} (expected {}.x)",
+                file.version,
+                SCHEMA_MAJOR
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        let ids = file.malware.iter().map(|m| &m.id).chain(file.extensions.iter().map(|e| &e.id));
+        for id in ids {
+            if id.trim().is_empty() {
+                return Err(anyhow!("Signature with an empty id"));
+            }
+            if !seen.insert(id.clone()) {
+                return Err(anyhow!("Duplicate signature id '{}'", id));
+            }
+        }
+
+        let mut signatures = file
+            .malware
+            .into_iter()
+            .map(Signature::from_malware)
+            .collect::<Result<Vec<_>>>()?;
+        signatures.extend(file.extensions.into_iter().map(Signature::from_extension));
+
+        let hashes = file
+            .hashes
+            .iter()
+            .map(|entry| {
+                Ioc::new(&entry.hash, &entry.name, entry.severity, "signature database")
+                    .with_context(|| format!("Hash entry '{}'", entry.name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            version: file.version,
+            last_updated: file.last_updated,
+            signatures,
+            hashes,
+            rules: Vec::new(),
+        })
+    }
+
+    /// Entries that can match something
+    pub fn active(&self) -> impl Iterator<Item = &Signature> {
+        self.signatures.iter().filter(|s| s.is_active())
+    }
+
+    pub fn signature(&self, id: &str) -> Option<&Signature> {
+        self.signatures.iter().find(|s| s.id == id)
+    }
+
+    /// The most severe signature listing browser extension `id`
+    pub fn match_extension(&self, id: &str) -> Option<&Signature> {
+        let id = id.to_lowercase();
+        self.active()
+            .filter(|s| s.extension_ids.contains(&id))
+            .max_by_key(|s| s.severity)
+    }
+
+    /// The most severe signature matching `path`
+    pub fn match_path(&self, path: &Path) -> Option<&Signature> {
+        self.active()
+            .filter(|s| s.matches(path))
+            .max_by_key(|s| s.severity)
+    }
+
+    /// The threat `path` represents, if it matches a signature
+    pub fn threat(&self, path: &Path) -> Option<SecurityThreat> {
+        let signature = self.match_path(path)?;
+        Some(SecurityThreat {
+            path: path.to_path_buf(),
+            threat_type: threat_type(path),
+            severity: signature.severity,
+            description: format!("{} ({}): {}", signature.name, signature.kind.name(), signature.description),
+            detected_at: Local::now(),
+            signature_id: signature.id.clone(),
+            remediation: None,
+            fix_mode: None,
+        })
+    }
+}
+
+/// Walks the places malware installs itself and matches them against a database
+pub struct SignatureScanner {
+    database: Arc<SignatureDatabase>,
+    roots: Vec<PathBuf>,
+    max_depth: usize,
+    errors: ScanErrors,
+}
+
+impl SignatureScanner {
+    pub fn new(database: Arc<SignatureDatabase>) -> Self {
+        Self {
+            database,
+            roots: default_roots(),
+            max_depth: 6,
+            errors: ScanErrors::new(),
+        }
+    }
+
+    pub fn with_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.roots = roots;
+        self
+    }
+
+    /// Where paths the scan had to skip are recorded
+    pub fn with_errors(mut self, errors: ScanErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn scan(&self) -> Result<Vec<SecurityThreat>> {
+        let mut threats = Vec::new();
+
+        for root in &self.roots {
+            let mut walker = WalkDir::new(root)
+                .max_depth(self.max_depth)
+                .follow_links(false)
+                .into_iter();
+
+            while let Some(entry) = walker.next() {
+                let entry = match self.errors.ok(entry) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+                if entry.depth() > 0 && entry.file_type().is_dir() && is_skipped_dir(&entry) {
+                    walker.skip_current_dir();
+                    continue;
+                }
+                if let Some(threat) = self.database.threat(entry.path()) {
+                    // A matching bundle or folder is reported once, not per file inside
+                    if entry.file_type().is_dir() {
+                        walker.skip_current_dir();
+                    }
+                    if !threats.iter().any(|t: &SecurityThreat| t.path == threat.path) {
+                        threats.push(threat);
+                    }
+                }
+            }
+        }
+
+        threats.sort_by_key(|t| std::cmp::Reverse(t.severity));
+        Ok(threats)
+    }
+}
+
+pub fn is_skipped_dir(entry: &walkdir::DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+    SKIPPED_DIRS.contains(&name.as_ref())
+}
+
+/// Scripts are worth reading even without an execute bit, since they're
+/// usually run through an interpreter
+const SCRIPT_EXTENSIONS: &[&str] = &[
+    "sh", "bash", "zsh", "command", "py", "pl", "rb", "js", "php", "ps1", "applescript", "scpt", "jar",
+];
+
+/// Files the content checks (IOC hashes and rules) look at
+pub fn is_executable_or_script(path: &Path, metadata: &fs::Metadata) -> bool {
+    let executable = metadata.permissions().mode() & 0o111 != 0;
+    executable
+        || path.extension().is_some_and(|ext| {
+            let ext = ext.to_string_lossy().to_lowercase();
+            SCRIPT_EXTENSIONS.contains(&ext.as_str())
+        })
+}
+
+/// Where a threat sits decides what kind it is
+pub fn threat_type(path: &Path) -> ThreatType {
+    let full = path.to_string_lossy();
+    let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+
+    if full.contains("/LaunchAgents/") {
+        ThreatType::LaunchAgent
+    } else if full.contains("/LaunchDaemons/") {
+        ThreatType::LaunchDaemon
+    } else if full.contains("/systemd/user/") || full.contains("/systemd/system/") {
+        ThreatType::SystemdUnit
+    } else if full.contains("/autostart/") || full.contains("/Login Items/") {
+        ThreatType::LoginItem
+    } else if name.ends_with(".kext") {
+        ThreatType::KernelExtension
+    } else if extension_id(path).is_some() {
+        ThreatType::BrowserExtension
+    } else {
+        ThreatType::SuspiciousFile
+    }
+}
+
+/// Bundle identifier of an app bundle (from an XML Info.plist), or the label
+/// a launchd plist is named after
+fn bundle_identifier(path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "app" => {
+            let plist = fs::read_to_string(path.join("Contents/Info.plist")).ok()?;
+            let after_key = &plist[plist.find("<key>CFBundleIdentifier</key>")?..];
+            let start = after_key.find("<string>")? + "<string>".len();
+            let end = after_key[start..].find("</string>")?;
+            Some(after_key[start..start + end].trim().to_string())
+        }
+        "plist" => Some(path.file_stem()?.to_string_lossy().to_string()),
+        _ => None,
+    }
+}
+
+/// Extension ID of a browser extension directory (Chromium `Extensions/<id>`)
+/// or package (Firefox `extensions/<id>.xpi`)
+fn extension_id(path: &Path) -> Option<String> {
+    let parent = path.parent()?.file_name()?.to_string_lossy();
+    if !parent.eq_ignore_ascii_case("extensions") {
+        return None;
+    }
+    let name = path.file_name()?.to_string_lossy();
+    let id = name.strip_suffix(".xpi").unwrap_or(&name);
+    Some(id.to_lowercase())
+}
+
+/// Where adware and malware install themselves, relative to home
+#[cfg(target_os = "macos")]
+const HOME_ROOTS: &[&str] = &[
+    "Library/LaunchAgents",
+    "Library/Application Support",
+    "Library/Safari/Extensions",
+    "Library/Application Support/Google/Chrome",
+    "Applications",
+    "Downloads",
+];
+
+#[cfg(target_os = "macos")]
+const SYSTEM_ROOTS: &[&str] = &[
+    "/Library/LaunchAgents",
+    "/Library/LaunchDaemons",
+    "/Library/Application Support",
+    "/Library/Extensions",
+    "/Applications",
+];
+
+#[cfg(target_os = "linux")]
+const HOME_ROOTS: &[&str] = &[
+    ".config/autostart",
+    ".config/systemd/user",
+    ".config/google-chrome",
+    ".config/chromium",
+    ".config/BraveSoftware",
+    ".mozilla/firefox",
+    ".local/share/applications",
+    ".local/bin",
+    "Downloads",
+];
+
+#[cfg(target_os = "linux")]
+const SYSTEM_ROOTS: &[&str] = &["/etc/systemd/system", "/etc/xdg/autostart", "/opt", "/tmp", "/var/tmp"];
+
+/// The usual install locations that exist on this machine
+pub fn default_roots() -> Vec<PathBuf> {
+    let home = dirs::home_dir();
+    let mut roots: Vec<PathBuf> = SYSTEM_ROOTS.iter().map(PathBuf::from).collect();
+    roots.extend(home.iter().flat_map(|home| HOME_ROOTS.iter().map(move |dir| home.join(dir))));
+    roots.retain(|root| root.exists());
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SAMPLE: &str = r#"{
+        "version": "1.2.0",
+        "lastUpdated": "2026-01-26",
+        "malware": [
+            {
+                "id": "adload",
+                "name": "Adload Adware",
+                "type": "adware",
+                "severity": "medium",
+                "description": "Injects ads",
+                "pathPatterns": ["*/Library/LaunchAgents/com.*.plist"],
+                "namePatterns": ["adload", "searchbaron"]
+            },
+            {
+                "id": "genio",
+                "name": "Genio",
+                "type": "trojan-horse",
+                "severity": "critical",
+                "namePatterns": ["genio"],
+                "bundleIdentifiers": ["com.genieo.*"]
+            }
+        ],
+        "extensions": [
+            {
+                "id": "bad-ext",
+                "name": "Bad Extension",
+                "severity": "high",
+                "extensionIDs": ["abcdefghijklmnopabcdefghijklmnop"],
+                "browsers": ["chrome"]
+            },
+            {
+                "id": "placeholder",
+                "name": "No IDs yet",
+                "severity": "low",
+                "extensionIDs": []
+            }
+        ],
+        "hashes": [
+            {
+                "hash": "D41D8CD98F00B204E9800998ECF8427E",
+                "name": "Empty dropper",
+                "severity": "high"
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_bundled_database_loads() {
+        let database = SignatureDatabase::bundled().unwrap();
+        assert!(database.active().count() > 0);
+    }
+
+    #[test]
+    fn test_validation() {
+        let database = SignatureDatabase::from_json(SAMPLE).unwrap();
+        assert_eq!(database.signatures.len(), 4);
+        assert_eq!(database.active().count(), 3);
+        assert_eq!(database.signatures[1].kind, MalwareKind::Other);
+
+        let future = SAMPLE.replace("\"1.2.0\"", "\"2.0.0\"");
+        assert!(SignatureDatabase::from_json(&future).is_err());
+
+        let duplicate = SAMPLE.replace("\"genio\",\n", "\"adload\",\n");
+        assert!(SignatureDatabase::from_json(&duplicate).is_err());
+
+        let bad_glob = SAMPLE.replace("com.genieo.*", "com.[genieo");
+        assert!(SignatureDatabase::from_json(&bad_glob).is_err());
+
+        let bad_severity = SAMPLE.replace("\"critical\"", "\"extreme\"");
+        assert!(SignatureDatabase::from_json(&bad_severity).is_err());
+
+        assert_eq!(database.hashes[0].hash, "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(database.hashes[0].severity, ThreatSeverity::High);
+        let bad_hash = SAMPLE.replace("ECF8427E", "ECF8427");
+        assert!(SignatureDatabase::from_json(&bad_hash).is_err());
+    }
+
+    #[test]
+    fn test_path_patterns_only_narrow() {
+        let database = SignatureDatabase::from_json(SAMPLE).unwrap();
+
+        let agent = Path::new("/Users/me/Library/LaunchAgents/com.adload.helper.plist");
+        let threat = database.threat(agent).unwrap();
+        assert_eq!(threat.signature_id, "adload");
+        assert_eq!(threat.severity, ThreatSeverity::Medium);
+        assert_eq!(threat.threat_type, ThreatType::LaunchAgent);
+
+        // Right place, innocent name
+        assert!(database.threat(Path::new("/Users/me/Library/LaunchAgents/com.google.keystone.plist")).is_none());
+        // Suspicious name outside the signature's paths
+        assert!(database.threat(Path::new("/Users/me/Downloads/adload-notes.txt")).is_none());
+
+        // Plain names are whole words
+        assert!(database.threat(Path::new("/opt/genio-installer")).is_some());
+        assert!(database.threat(Path::new("/opt/genious")).is_none());
+    }
+
+    #[test]
+    fn test_bundle_and_extension_ids() {
+        let database = SignatureDatabase::from_json(SAMPLE).unwrap();
+
+        let label = Path::new("/Library/LaunchDaemons/com.genieo.updater.plist");
+        let threat = database.threat(label).unwrap();
+        assert_eq!(threat.signature_id, "genio");
+        assert_eq!(threat.threat_type, ThreatType::LaunchDaemon);
+
+        let extension = Path::new("/home/me/.config/google-chrome/Default/Extensions/abcdefghijklmnopabcdefghijklmnop");
+        let threat = database.threat(extension).unwrap();
+        assert_eq!(threat.signature_id, "bad-ext");
+        assert_eq!(threat.threat_type, ThreatType::BrowserExtension);
+    }
+
+    #[test]
+    fn test_scan_reports_bundles_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let app = root.join("Applications/Cleaner.app");
+        fs::create_dir_all(app.join("Contents/MacOS")).unwrap();
+        fs::write(
+            app.join("Contents/Info.plist"),
+            "<dict><key>CFBundleIdentifier</key>\n<string>com.genieo.cleaner</string></dict>",
+        )
+        .unwrap();
+        fs::write(app.join("Contents/MacOS/genio-helper"), "").unwrap();
+        fs::write(root.join("searchbaron.sh"), "").unwrap();
+
+        let scanner = SignatureScanner::new(Arc::new(SignatureDatabase::from_json(SAMPLE).unwrap()))
+            .with_roots(vec![root.to_path_buf()]);
+        let threats = scanner.scan().unwrap();
+
+        let paths: Vec<&Path> = threats.iter().map(|t| t.path.as_path()).collect();
+        assert_eq!(paths, vec![app.as_path()]);
+        assert_eq!(threats[0].severity, ThreatSeverity::Critical);
+    }
+}
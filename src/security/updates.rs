@@ -0,0 +1,496 @@
+// Signature updates from files, for hosts that can't download them: a bundle
+// is a directory (or a signatures JSON file and the directory it's in) holding
+// the database, any `*.rules` files and a SHA-256 manifest covering all of
+// them. The manifest travels with the bundle, so it catches a corrupted or
+// truncated copy but not a tampered one: an integrity check only, trust comes
+// from the mirror the bundle was fetched from. Installed databases live under
+// the data directory; the one they replaced is kept for `surge signatures
+// rollback`.
+
+use anyhow::{anyhow, Context, Result};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::scanner::duplicates;
+use crate::security::signatures::SignatureDatabase;
+
+/// File name of an installed database
+const DATABASE_FILE: &str = "signatures.json";
+
+/// Manifest names looked for in a bundle, besides `<database>.sha256`
+const MANIFEST_NAMES: &[&str] = &["SHA256SUMS", "SHA256SUMS.txt", "signatures.sha256"];
+
+/// An update bundle whose files all matched its manifest
+#[derive(Debug)]
+pub struct Bundle {
+    database: PathBuf,
+    rules: Vec<PathBuf>,
+    manifest: PathBuf,
+    /// Digests the files matched, by file name
+    digests: HashMap<String, String>,
+}
+
+impl Bundle {
+    /// Find the database, rules and manifest in `from` and check every file
+    /// against the manifest. This detects corruption, not tampering.
+    pub fn open(from: &Path) -> Result<Self> {
+        let (dir, database) = if from.is_dir() {
+            (from.to_path_buf(), from.join(DATABASE_FILE))
+        } else {
+            let dir = from
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+            (dir.to_path_buf(), from.to_path_buf())
+        };
+        if !database.is_file() {
+            return Err(anyhow!("No signature database at {}", database.display()));
+        }
+
+        let own_manifest = PathBuf::from(format!("{}.sha256", database.display()));
+        let manifest = std::iter::once(own_manifest)
+            .chain(MANIFEST_NAMES.iter().map(|name| dir.join(name)))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                anyhow!(
+                    "No SHA-256 manifest next to {} (expected {} or {}.sha256)",
+                    database.display(),
+                    MANIFEST_NAMES.join(", "),
+                    database.file_name().unwrap_or_default().to_string_lossy()
+                )
+            })?;
+
+        let mut rules: Vec<PathBuf> = fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "rules"))
+            .collect();
+        rules.sort();
+
+        let mut bundle = Self {
+            database,
+            rules,
+            manifest,
+            digests: HashMap::new(),
+        };
+        bundle.digests = bundle.check_integrity()?;
+        Ok(bundle)
+    }
+
+    fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.database).chain(&self.rules)
+    }
+
+    fn check_integrity(&self) -> Result<HashMap<String, String>> {
+        let contents = fs::read_to_string(&self.manifest)
+            .with_context(|| format!("Failed to read {}", self.manifest.display()))?;
+        let digests = parse_manifest(&contents);
+
+        for file in self.files() {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            let expected = digests
+                .get(name.as_ref())
+                .ok_or_else(|| anyhow!("{} is not listed in {}", name, self.manifest.display()))?;
+            let actual = sha256_file(file)?;
+            if *expected != actual {
+                return Err(anyhow!(
+                    "SHA-256 mismatch for {}: manifest says {}, file is {}",
+                    name,
+                    expected,
+                    actual
+                ));
+            }
+        }
+        Ok(digests)
+    }
+}
+
+/// `sha256sum` lines (`<hex>  <name>`, `*<name>` in binary mode) or BSD
+/// `SHA256 (<name>) = <hex>` lines, by file name
+fn parse_manifest(contents: &str) -> HashMap<String, String> {
+    let mut digests = HashMap::new();
+    for line in contents.lines().map(str::trim) {
+        let entry = if let Some(bsd) = line.strip_prefix("SHA256 (") {
+            bsd.rsplit_once(") = ")
+        } else {
+            line.split_once(char::is_whitespace)
+                .map(|(hex, name)| (name.trim().trim_start_matches('*'), hex))
+        };
+        let Some((name, hex)) = entry else { continue };
+        let hex = hex.trim().to_lowercase();
+        if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            // Manifests may list paths; bundles are flat
+            let name = Path::new(name)
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            digests.insert(name, hex);
+        }
+    }
+    digests
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    duplicates::sha256_file(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// Orders dotted versions numerically, so 1.10.0 is newer than 1.9.2
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<u64> {
+        v.split('.')
+            .map(|p| p.trim().parse().unwrap_or(0))
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// What an update changed
+#[derive(Debug, Default)]
+pub struct UpdateReport {
+    pub from_version: String,
+    pub to_version: String,
+    pub to_date: Option<String>,
+    /// Signature ids with their names
+    pub added: Vec<(String, String)>,
+    pub removed: Vec<(String, String)>,
+    pub added_rules: Vec<String>,
+    pub removed_rules: Vec<String>,
+    pub added_hashes: usize,
+    pub removed_hashes: usize,
+}
+
+impl UpdateReport {
+    fn between(old: &SignatureDatabase, new: &SignatureDatabase) -> Self {
+        let names = |db: &SignatureDatabase| -> HashMap<String, String> {
+            db.signatures
+                .iter()
+                .map(|s| (s.id.clone(), s.name.clone()))
+                .collect()
+        };
+        let (old_names, new_names) = (names(old), names(new));
+        let mut added: Vec<(String, String)> = new_names
+            .iter()
+            .filter(|(id, _)| !old_names.contains_key(*id))
+            .map(|(id, name)| (id.clone(), name.clone()))
+            .collect();
+        let mut removed: Vec<(String, String)> = old_names
+            .iter()
+            .filter(|(id, _)| !new_names.contains_key(*id))
+            .map(|(id, name)| (id.clone(), name.clone()))
+            .collect();
+        added.sort();
+        removed.sort();
+
+        let rule_ids = |db: &SignatureDatabase| -> BTreeSet<String> {
+            db.rules.iter().map(|r| r.id.clone()).collect()
+        };
+        let (old_rules, new_rules) = (rule_ids(old), rule_ids(new));
+        let hashes = |db: &SignatureDatabase| -> BTreeSet<String> {
+            db.hashes.iter().map(|h| h.hash.clone()).collect()
+        };
+        let (old_hashes, new_hashes) = (hashes(old), hashes(new));
+
+        Self {
+            from_version: old.version.clone(),
+            to_version: new.version.clone(),
+            to_date: new.last_updated.clone(),
+            added,
+            removed,
+            added_rules: new_rules.difference(&old_rules).cloned().collect(),
+            removed_rules: old_rules.difference(&new_rules).cloned().collect(),
+            added_hashes: new_hashes.difference(&old_hashes).count(),
+            removed_hashes: old_hashes.difference(&new_hashes).count(),
+        }
+    }
+}
+
+/// Installed signature databases: `current/` in use, `previous/` for rollback
+pub struct SignatureStore {
+    dir: PathBuf,
+}
+
+impl SignatureStore {
+    /// The store under the user's data directory
+    pub fn open() -> Result<Self> {
+        let data_dir =
+            dirs::data_dir().ok_or_else(|| anyhow!("Could not determine data directory"))?;
+        Ok(Self::new(data_dir.join("surge/signatures")))
+    }
+
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn current(&self) -> PathBuf {
+        self.dir.join("current")
+    }
+
+    fn previous(&self) -> PathBuf {
+        self.dir.join("previous")
+    }
+
+    fn installed(&self) -> Result<Option<SignatureDatabase>> {
+        let path = self.current().join(DATABASE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        SignatureDatabase::load(&path)
+            .map(Some)
+            .context("Installed signature database is broken; `surge signatures rollback` restores the previous one")
+    }
+
+    /// The database scans use: the installed one, unless surge itself ships a
+    /// newer one
+    pub fn database(&self) -> Result<SignatureDatabase> {
+        let bundled = SignatureDatabase::bundled()?;
+        match self.installed()? {
+            Some(installed)
+                if compare_versions(&installed.version, &bundled.version) != Ordering::Less =>
+            {
+                Ok(installed)
+            }
+            _ => Ok(bundled),
+        }
+    }
+
+    /// Check the integrity of the bundle in `from` and install it. Unless `force` is set, only a
+    /// newer version than the one in use is accepted.
+    pub fn update(&self, from: &Path, force: bool) -> Result<UpdateReport> {
+        let bundle = Bundle::open(from)?;
+        let new = SignatureDatabase::load(&bundle.database)
+            .with_context(|| format!("Bundle {}", from.display()))?;
+        let old = self.database()?;
+
+        if !force && compare_versions(&new.version, &old.version) != Ordering::Greater {
+            return Err(anyhow!(
+                "Bundle has signatures v{}, not newer than v{} in use (--force installs it anyway)",
+                new.version,
+                old.version
+            ));
+        }
+
+        // Stage the bundle, then swap it in so a failed copy leaves the
+        // current database alone
+        let staging = self.dir.join("staging");
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        fs::create_dir_all(&staging)?;
+        fs::copy(&bundle.database, staging.join(DATABASE_FILE))?;
+        for rule in &bundle.rules {
+            fs::copy(rule, staging.join(rule.file_name().unwrap_or_default()))?;
+        }
+        // The manifest is rewritten for the installed names, so the database
+        // can still be checked once `<name>.json` becomes `signatures.json`
+        let mut manifest_name = bundle
+            .manifest
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        if !MANIFEST_NAMES.contains(&manifest_name.as_str()) {
+            manifest_name = format!("{}.sha256", DATABASE_FILE);
+        }
+        let mut manifest = String::new();
+        for file in bundle.files() {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            let installed = if *file == bundle.database {
+                DATABASE_FILE
+            } else {
+                name.as_ref()
+            };
+            manifest.push_str(&format!("{}  {}\n", bundle.digests[name.as_ref()], installed));
+        }
+        fs::write(staging.join(manifest_name), manifest)?;
+        SignatureDatabase::load(&staging.join(DATABASE_FILE))
+            .context("Staged signature database")?;
+
+        self.swap_in(&staging)?;
+
+        Ok(UpdateReport::between(&old, &new))
+    }
+
+    /// Make `staging` the current database and the current one the previous.
+    /// If the staged one can't be moved in, the current one is put back.
+    fn swap_in(&self, staging: &Path) -> Result<()> {
+        let had_current = self.current().exists();
+        if had_current {
+            if self.previous().exists() {
+                fs::remove_dir_all(self.previous())?;
+            }
+            fs::rename(self.current(), self.previous())?;
+        }
+        if let Err(err) = fs::rename(staging, self.current()) {
+            if had_current {
+                fs::rename(self.previous(), self.current()).with_context(|| {
+                    format!(
+                        "Failed to install {} ({}), and to restore {}",
+                        staging.display(),
+                        err,
+                        self.current().display()
+                    )
+                })?;
+            }
+            return Err(err).with_context(|| format!("Failed to install {}", staging.display()));
+        }
+        Ok(())
+    }
+
+    /// Go back to the database the last update replaced, or to the bundled
+    /// one if there's none
+    pub fn rollback(&self) -> Result<UpdateReport> {
+        let old = self.database()?;
+        if !self.current().exists() {
+            return Err(anyhow!("No installed signature update to roll back"));
+        }
+        fs::remove_dir_all(self.current())?;
+        if self.previous().exists() {
+            fs::rename(self.previous(), self.current())?;
+        }
+        let new = self.database()?;
+        Ok(UpdateReport::between(&old, &new))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_bundle(dir: &Path, version: &str, ids: &[&str], rule: Option<&str>) {
+        fs::create_dir_all(dir).unwrap();
+        let malware: Vec<String> = ids
+            .iter()
+            .map(|id| {
+                format!(
+                    r#"{{"id": "{}", "name": "{} name", "type": "adware", "severity": "low", "namePatterns": ["{}"]}}"#,
+                    id, id, id
+                )
+            })
+            .collect();
+        let json = format!(
+            r#"{{"version": "{}", "lastUpdated": "2026-10-01", "malware": [{}]}}"#,
+            version,
+            malware.join(",")
+        );
+        fs::write(dir.join("signatures.json"), &json).unwrap();
+        let mut manifest = format!(
+            "{}  signatures.json\n",
+            sha256_file(&dir.join("signatures.json")).unwrap()
+        );
+        if let Some(rule) = rule {
+            let file = dir.join("extra.rules");
+            fs::write(&file, format!("rule {} {{ condition: false }}", rule)).unwrap();
+            manifest.push_str(&format!(
+                "SHA256 (extra.rules) = {}\n",
+                sha256_file(&file).unwrap()
+            ));
+        }
+        fs::write(dir.join("SHA256SUMS"), manifest).unwrap();
+    }
+
+    #[test]
+    fn test_versions() {
+        assert_eq!(compare_versions("1.10.0", "1.9.2"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.0", "1.0.1"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_update_verify_and_rollback() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SignatureStore::new(temp_dir.path().join("store"));
+        let bundled = SignatureDatabase::bundled().unwrap();
+
+        let first = temp_dir.path().join("first");
+        write_bundle(
+            &first,
+            "99.0.0",
+            &["adload", "newthing"],
+            Some("extra_rule"),
+        );
+        // The schema major version is checked before anything is installed
+        assert!(store.update(&first, false).is_err());
+
+        write_bundle(
+            &first,
+            "1.50.0",
+            &["adload", "newthing"],
+            Some("extra_rule"),
+        );
+        let report = store.update(&first, false).unwrap();
+        assert_eq!(report.from_version, bundled.version);
+        assert_eq!(report.to_version, "1.50.0");
+        assert_eq!(
+            report.added,
+            vec![("newthing".to_string(), "newthing name".to_string())]
+        );
+        assert!(report.removed.iter().any(|(id, _)| id == "genio"));
+        assert_eq!(report.added_rules, vec!["extra_rule".to_string()]);
+        assert!(!report.removed_rules.is_empty());
+        assert_eq!(store.database().unwrap().version, "1.50.0");
+        assert_eq!(store.database().unwrap().rules.len(), 1);
+
+        // Not newer, or tampered with
+        assert!(store.update(&first, false).is_err());
+        let second = temp_dir.path().join("second");
+        write_bundle(&second, "1.51.0", &["adload"], None);
+        fs::write(
+            second.join("signatures.json"),
+            r#"{"version": "1.51.0", "malware": []}"#,
+        )
+        .unwrap();
+        let err = store.update(&second, false).unwrap_err();
+        assert!(err.to_string().contains("mismatch"), "{}", err);
+
+        // A single file with its own manifest, installed as signatures.json
+        // with a manifest that still matches
+        write_bundle(&second, "1.51.0", &["adload"], None);
+        let file = second.join("signatures-1.51.0.json");
+        fs::rename(second.join("signatures.json"), &file).unwrap();
+        fs::write(
+            second.join("signatures-1.51.0.json.sha256"),
+            format!("{}  signatures-1.51.0.json\n", sha256_file(&file).unwrap()),
+        )
+        .unwrap();
+        let report = store.update(&file, false).unwrap();
+        assert!(Bundle::open(&store.current()).is_ok());
+        assert_eq!(
+            report.removed,
+            vec![("newthing".to_string(), "newthing name".to_string())]
+        );
+
+        let report = store.rollback().unwrap();
+        assert_eq!(
+            (report.from_version.as_str(), report.to_version.as_str()),
+            ("1.51.0", "1.50.0")
+        );
+        let report = store.rollback().unwrap();
+        assert_eq!(report.to_version, bundled.version);
+        assert!(store.rollback().is_err());
+    }
+
+    #[test]
+    fn test_failed_install_keeps_current() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SignatureStore::new(temp_dir.path().join("store"));
+        let bundle = temp_dir.path().join("bundle");
+        write_bundle(&bundle, "1.50.0", &["adload"], None);
+        store.update(&bundle, false).unwrap();
+
+        // The staged database can't be moved in: the current one stays
+        assert!(store.swap_in(&temp_dir.path().join("missing")).is_err());
+        assert_eq!(store.database().unwrap().version, "1.50.0");
+    }
+}
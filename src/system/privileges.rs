@@ -0,0 +1,133 @@
+use std::env;
+use std::ffi::{CStr, CString, OsStr};
+use std::fs;
+use std::io::{ErrorKind, IsTerminal};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Whether surge is running as root
+pub fn is_root() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// Whether the current user can remove `path`.
+///
+/// Removal needs a writable parent, which in a sticky directory like
+/// `/var/crash` only helps for our own files; emptying a directory also
+/// needs write access to it.
+pub fn can_remove(path: &Path) -> bool {
+    let (Some(parent), Ok(metadata)) = (path.parent(), fs::symlink_metadata(path)) else {
+        return false;
+    };
+    if is_root() {
+        return true;
+    }
+    let sticky = fs::metadata(parent).is_ok_and(|m| m.mode() & 0o1000 != 0);
+    if sticky && metadata.uid() != unsafe { libc::geteuid() } {
+        return false;
+    }
+    access(parent, libc::W_OK | libc::X_OK)
+        && (!metadata.is_dir() || access(path, libc::R_OK | libc::W_OK | libc::X_OK))
+}
+
+/// The path a walker couldn't read because of missing permissions
+pub fn permission_denied(err: &walkdir::Error) -> Option<PathBuf> {
+    let io = err.io_error()?;
+    if io.kind() != ErrorKind::PermissionDenied {
+        return None;
+    }
+    err.path().map(Path::to_path_buf)
+}
+
+/// Whether an operation failed only for lack of permissions
+pub fn is_permission_denied(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == ErrorKind::PermissionDenied)
+}
+
+/// Home directory of the user who ran sudo, doas or pkexec. The elevated
+/// process may have root's `$HOME` instead.
+pub fn invoking_user_home() -> Option<PathBuf> {
+    let entry = match env::var_os("SUDO_USER").or_else(|| env::var_os("DOAS_USER")) {
+        Some(name) => {
+            let name = CString::new(name.as_bytes()).ok()?;
+            unsafe { libc::getpwnam(name.as_ptr()) }
+        }
+        None => {
+            let uid: libc::uid_t = env::var("PKEXEC_UID").ok()?.parse().ok()?;
+            unsafe { libc::getpwuid(uid) }
+        }
+    };
+    if entry.is_null() {
+        return None;
+    }
+    let dir = unsafe { CStr::from_ptr((*entry).pw_dir) };
+    Some(PathBuf::from(OsStr::from_bytes(dir.to_bytes())))
+}
+
+/// The tool used to re-run surge as root: sudo or doas in a terminal, pkexec otherwise
+pub fn elevation_tool() -> Option<PathBuf> {
+    let candidates: &[&str] = if std::io::stdin().is_terminal() {
+        &["sudo", "doas", "pkexec"]
+    } else {
+        &["pkexec", "sudo"]
+    };
+    candidates.iter().find_map(|name| find_in_path(name))
+}
+
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|candidate| access(candidate, libc::X_OK))
+}
+
+fn access(path: &Path, mode: libc::c_int) -> bool {
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    unsafe { libc::access(path.as_ptr(), mode) == 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+    use walkdir::WalkDir;
+
+    #[test]
+    fn test_can_remove_follows_parent_permissions() {
+        let tmp = TempDir::new().unwrap();
+        let locked = tmp.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("file"), b"x").unwrap();
+        assert!(can_remove(&locked.join("file")));
+
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+        // root bypasses permission bits
+        assert_eq!(can_remove(&locked.join("file")), is_root());
+        assert_eq!(can_remove(&locked), is_root());
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_permission_denied_reports_unreadable_dir() {
+        if is_root() {
+            return;
+        }
+        let tmp = TempDir::new().unwrap();
+        let hidden = tmp.path().join("hidden");
+        fs::create_dir(&hidden).unwrap();
+        fs::set_permissions(&hidden, fs::Permissions::from_mode(0o000)).unwrap();
+
+        let denied: Vec<_> = WalkDir::new(tmp.path())
+            .into_iter()
+            .filter_map(|e| e.err())
+            .filter_map(|e| permission_denied(&e))
+            .collect();
+        fs::set_permissions(&hidden, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(denied, vec![hidden]);
+    }
+}
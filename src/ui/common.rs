@@ -1,11 +1,14 @@
 use ratatui::{
-    layout::{Alignment, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, BorderType, Paragraph},
+    widgets::{Block, Borders, BorderType, Clear, List, ListItem, ListState, Paragraph},
     Frame,
 };
 
+use crate::app::App;
+use crate::scanner::errors::ScanErrorKind;
+
 // ASCII art banner using line characters (like npkill)
 const SURGE_BANNER: &str = r#"
    -----       ____    _   _    ____     ____    ____
@@ -56,3 +59,130 @@ pub fn render_banner(frame: &mut Frame, area: Rect) {
 
     frame.render_widget(banner, area);
 }
+
+/// Rect of `height` rows and `percent_x`% of the width, centered in `area` (for popups)
+pub fn centered_rect(percent_x: u16, height: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(height.min(area.height)),
+            Constraint::Min(0),
+        ])
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+/// Status line for the `/` search prompt or the active filter, if any
+pub fn search_line(app: &App) -> Option<Line<'static>> {
+    if let Some(input) = &app.search_input {
+        return Some(Line::from(vec![
+            Span::styled("/", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Span::styled(input.clone(), Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+            Span::styled("█", Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!("  {} matches  (Enter=Apply, Esc=Cancel)", app.search_match_count()),
+                Style::default().fg(Color::Gray),
+            ),
+        ]));
+    }
+
+    let pattern = app.search_pattern.as_ref()?;
+    Some(Line::from(vec![
+        Span::styled("Filter: ", Style::default().fg(Color::Cyan)),
+        Span::styled(
+            app.search_query.clone(),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(format!(" [{}]", pattern.kind_name()), Style::default().fg(Color::DarkGray)),
+        Span::styled(
+            format!("  {} matches  ", app.search_match_count()),
+            Style::default().fg(Color::Green),
+        ),
+        Span::styled("[n/N] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Next/Prev  "),
+        Span::styled("[Esc] ", Style::default().fg(Color::Red)),
+        Span::raw("Clear"),
+    ]))
+}
+
+/// Status bar note for paths the current screen's scan skipped, if any
+pub fn scan_errors_spans(app: &App) -> Vec<Span<'static>> {
+    let errors = app.current_scan_errors();
+    if errors.is_empty() {
+        return Vec::new();
+    }
+
+    let kinds = [
+        ScanErrorKind::PermissionDenied,
+        ScanErrorKind::BrokenSymlink,
+        ScanErrorKind::SymlinkLoop,
+        ScanErrorKind::Io,
+    ];
+    let breakdown: Vec<String> = kinds
+        .iter()
+        .filter_map(|kind| {
+            let count = errors.iter().filter(|e| e.kind == *kind).count();
+            (count > 0).then(|| format!("{} {}", count, kind.name().to_lowercase()))
+        })
+        .collect();
+
+    vec![
+        Span::styled(
+            format!("⚠ {} paths skipped", errors.len()),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(format!(" ({}) ", breakdown.join(", ")), Style::default().fg(Color::Gray)),
+        Span::styled("[e] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Details"),
+    ]
+}
+
+/// Popup listing every path the current screen's scan skipped, and why
+pub fn render_scan_errors(frame: &mut Frame, app: &App, area: Rect) {
+    let errors = app.current_scan_errors();
+    let items: Vec<ListItem> = errors
+        .iter()
+        .map(|error| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:<18}", error.kind.name()),
+                    Style::default().fg(Color::Red),
+                ),
+                Span::styled(error.path.display().to_string(), Style::default().fg(Color::White)),
+                Span::styled(format!("  {}", error.message), Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+
+    let height = (errors.len() as u16 + 2).clamp(5, area.height.saturating_sub(4));
+    let popup_area = centered_rect(90, height, area);
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Red))
+                .title(format!(
+                    " {} paths skipped, results are incomplete  [↑↓] Scroll  [Esc] Close ",
+                    errors.len()
+                ))
+                .title_style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        )
+        .highlight_style(Style::default().bg(Color::Rgb(30, 30, 30)).add_modifier(Modifier::BOLD));
+
+    let mut state = ListState::default();
+    state.select(Some(app.scan_errors_index));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_stateful_widget(list, popup_area, &mut state);
+}
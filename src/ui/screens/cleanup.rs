@@ -9,6 +9,7 @@ use ratatui::{
 
 use crate::app::App;
 use crate::app::state::SortOrder;
+use crate::models;
 use crate::ui::common;
 
 pub fn render(frame: &mut Frame, app: &App, area: Rect) {
@@ -18,7 +19,7 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
             Constraint::Length(12), // Banner
             Constraint::Length(3),  // Title
             Constraint::Min(5),     // Items list
-            Constraint::Length(4),  // Status/actions
+            Constraint::Length(5),  // Status/actions
         ])
         .split(area);
 
@@ -56,10 +57,10 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
             .block(Block::default().borders(Borders::ALL).title("Items"));
         frame.render_widget(empty, chunks[2]);
     } else {
-        let items: Vec<ListItem> = app
-            .cleanable_items
+        let visible = app.visible_cleanup_indices();
+        let items: Vec<ListItem> = visible
             .iter()
-            .enumerate()
+            .map(|&i| (i, &app.cleanable_items[i]))
             .map(|(i, item)| {
                 let checkbox = if item.selected { "[✓]" } else { "[ ]" };
                 let highlight = if i == app.selected_index {
@@ -68,29 +69,62 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
                     Style::default()
                 };
 
-                let line = Line::from(vec![
+                // Nested entries are indented under their parent and show just their name
+                let marker = if !models::has_children(&app.cleanable_items, i) {
+                    "  "
+                } else if app.cleanup_expanded.contains(&item.path) {
+                    "▾ "
+                } else {
+                    "▸ "
+                };
+                let label = match item.path.file_name() {
+                    Some(name) if item.depth > 0 => name.to_string_lossy().to_string(),
+                    _ => item.path.display().to_string(),
+                };
+
+                let mut spans = vec![
+                    Span::raw("  ".repeat(item.depth)),
                     Span::styled(
                         format!("{} ", checkbox),
                         Style::default().fg(if item.selected { Color::Green } else { Color::Gray }),
                     ),
-                    Span::styled(
+                    Span::raw(marker),
+                ];
+                if item.depth == 0 {
+                    spans.push(Span::styled(
                         item.category.name(),
                         Style::default().fg(Color::Cyan),
-                    ),
-                    Span::raw(" - "),
+                    ));
+                    spans.push(Span::raw(" - "));
+                }
+                spans.extend([
                     Span::styled(
-                        item.path.display().to_string(),
+                        label,
                         highlight,
                     ),
                     Span::raw(" ("),
                     Span::styled(
-                        format_size(item.size, BINARY),
+                        // Unreadable folders have no size until scanned as root
+                        if item.size == 0 && item.needs_privileges {
+                            "?".to_string()
+                        } else {
+                            format_size(item.size, BINARY)
+                        },
                         Style::default().fg(Color::Yellow),
                     ),
                     Span::raw(")"),
                 ]);
-
-                ListItem::new(line)
+                if item.needs_privileges {
+                    spans.push(Span::styled(" [root]", Style::default().fg(Color::Red)));
+                }
+                if let Some(note) = &item.note {
+                    spans.push(Span::styled(
+                        format!("  {}", note),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+
+                ListItem::new(Line::from(spans))
             })
             .collect();
 
@@ -116,24 +150,34 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
 
         // Create list state with current selection
         let mut list_state = ListState::default();
-        list_state.select(Some(app.selected_index));
+        list_state.select(visible.iter().position(|&i| i == app.selected_index));
 
         frame.render_stateful_widget(list, chunks[2], &mut list_state);
     }
 
     // Status and actions
     let selected_size = format_size(app.get_selected_size(), BINARY);
-    let selected_count = app.cleanable_items.iter().filter(|i| i.selected).count();
-
-    let mut status_lines = vec![
-        Line::from(vec![
-            Span::styled("Selected: ", Style::default().fg(Color::White)),
-            Span::styled(
-                format!("{} items ({})", selected_count, selected_size),
-                Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
-            ),
-        ]),
+    let selected_count = app.cleanup_selected_count();
+
+    let mut summary = vec![
+        Span::styled("Selected: ", Style::default().fg(Color::White)),
+        Span::styled(
+            format!("{} items ({})", selected_count, selected_size),
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        ),
     ];
+    // Paths the scan skipped, so the results above are incomplete
+    let skipped = common::scan_errors_spans(app);
+    if !skipped.is_empty() {
+        summary.push(Span::raw("  │  "));
+        summary.extend(skipped);
+    }
+    let mut status_lines = vec![Line::from(summary)];
+
+    // Search prompt / active filter
+    if let Some(line) = common::search_line(app) {
+        status_lines.insert(0, line);
+    }
 
     // Show number buffer if user is typing
     if !app.number_buffer.is_empty() {
@@ -179,6 +223,14 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
             Span::raw("Jump  "),
             Span::styled("[s] ", Style::default().fg(Color::Cyan)),
             Span::raw("Sort  "),
+            Span::styled("[/] ", Style::default().fg(Color::Cyan)),
+            Span::raw("Search  "),
+            Span::styled("[←/→] ", Style::default().fg(Color::Cyan)),
+            Span::raw("Collapse/Expand  "),
+            Span::styled("[o] ", Style::default().fg(Color::Cyan)),
+            Span::raw("Stale projects  "),
+            Span::styled("[c] ", Style::default().fg(Color::Cyan)),
+            Span::raw("Compress logs  "),
             Span::styled("[Enter] ", Style::default().fg(Color::Green)),
             Span::raw("Clean"),
         ]));
@@ -189,4 +241,8 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
         .block(Block::default().borders(Borders::ALL));
 
     frame.render_widget(status, chunks[3]);
+
+    if app.scan_errors_open {
+        common::render_scan_errors(frame, app, area);
+    }
 }
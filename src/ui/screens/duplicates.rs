@@ -97,8 +97,9 @@ fn render_file_list(frame: &mut Frame, app: &App, area: Rect) {
     let mut flat_index = 0;
     let mut selected_flat_index = 0;
 
-    // Build flat list of all files across all groups
-    for (group_idx, group) in app.duplicate_groups.iter().enumerate() {
+    // Build flat list of all files across all (matching) groups
+    for group_idx in app.visible_duplicate_groups() {
+        let group = &app.duplicate_groups[group_idx];
         // Group header - cleaner design
         let group_header = format!(
             "━━ {} copies • {} each • {} total ━━",
@@ -290,7 +291,9 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         "Select duplicates to delete • 'a' keeps newest • 'n' clears selection".to_string()
     };
 
-    let widget = Paragraph::new(status_text)
+    let status_line = common::search_line(app).unwrap_or_else(|| Line::from(status_text));
+
+    let widget = Paragraph::new(status_line)
         .style(Style::default().fg(Color::Cyan))
         .block(Block::default().borders(Borders::ALL));
 
@@ -36,9 +36,12 @@ pub fn render(frame: &mut Frame, _app: &App, area: Rect) {
         ])),
         ListItem::new("  ↑↓ or j/k     - Move up/down in lists"),
         ListItem::new("  ←→ or h/l     - Move left/right (tabs)"),
-        ListItem::new("  1-2           - Jump to feature (Storage/TreeMap)"),
+        ListItem::new("  ← / →         - Collapse / expand a folder (Cleanup)"),
+        ListItem::new("  1-3           - Jump to feature (Storage/TreeMap/Security)"),
         ListItem::new("  g             - Go home"),
         ListItem::new("  PageUp/Down   - Fast scroll"),
+        ListItem::new("  /             - Search (text, *.glob, or re:regex)"),
+        ListItem::new("  n / N         - Next / previous match"),
         ListItem::new(""),
         ListItem::new(Line::from(vec![
             Span::styled("Selection Keys", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
@@ -54,7 +57,18 @@ pub fn render(frame: &mut Frame, _app: &App, area: Rect) {
         ListItem::new("  d             - Delete selected"),
         ListItem::new("  s             - Sort items"),
         ListItem::new("  p             - Toggle preview (TreeMap)"),
-        ListItem::new("  o             - Open file (TreeMap)"),
+        ListItem::new("  o             - Open file (TreeMap, Security) / Select stale projects (Cleanup)"),
+        ListItem::new("  b             - Content breakdown (TreeMap)"),
+        ListItem::new("  c             - Gzip selected rotated logs (Cleanup)"),
+        ListItem::new("                  [root] items: run `surge clean --system`"),
+        ListItem::new("  e             - Paths the last scan skipped, and why"),
+        ListItem::new("  t             - Move to trash (TreeMap)"),
+        ListItem::new("  m             - Move to another directory (TreeMap)"),
+        ListItem::new("  x             - Quarantine threat (Security)"),
+        ListItem::new("  f             - Fix permissions of a finding (Security)"),
+        ListItem::new("  i / I         - Ignore threat until rescan / suppress with reason, expiry (Security)"),
+        ListItem::new("  a             - Saved suppressions; d removes one (Security)"),
+        ListItem::new("  r             - Rescan (Security)"),
         ListItem::new(""),
         ListItem::new(Line::from(vec![
             Span::styled("Global Keys", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
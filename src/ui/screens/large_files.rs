@@ -101,10 +101,10 @@ fn render_large_files(frame: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_file_list(frame: &mut Frame, app: &App, area: Rect) {
-    let items: Vec<ListItem> = app
-        .large_files
+    let visible = app.visible_large_file_indices();
+    let items: Vec<ListItem> = visible
         .iter()
-        .enumerate()
+        .map(|&idx| (idx, &app.large_files[idx]))
         .map(|(idx, file)| {
             let is_selected = idx == app.large_files_selected_index;
             let checkbox = if file.selected { "[×]" } else { "[ ]" };
@@ -153,8 +153,12 @@ fn render_file_list(frame: &mut Frame, app: &App, area: Rect) {
 
     // Calculate scroll offset to keep selected item visible
     let visible_height = area.height.saturating_sub(2) as usize; // Minus borders
-    let scroll_offset = if app.large_files_selected_index >= visible_height {
-        app.large_files_selected_index.saturating_sub(visible_height / 2)
+    let selected_position = visible
+        .iter()
+        .position(|&i| i == app.large_files_selected_index)
+        .unwrap_or(0);
+    let scroll_offset = if selected_position >= visible_height {
+        selected_position.saturating_sub(visible_height / 2)
     } else {
         0
     };
@@ -251,7 +255,9 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         help_text.to_string()
     };
 
-    let widget = Paragraph::new(status_text)
+    let status_line = common::search_line(app).unwrap_or_else(|| Line::from(status_text));
+
+    let widget = Paragraph::new(status_line)
         .style(Style::default().fg(Color::Cyan))
         .block(Block::default().borders(Borders::ALL));
 
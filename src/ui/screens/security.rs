@@ -1,29 +1,442 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Paragraph},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
 
+use crate::app::state::{SuppressDraft, SUPPRESS_EXPIRIES};
 use crate::app::App;
+use crate::models::ThreatSeverity;
+use crate::security::allowlist::AllowlistEntry;
 use crate::ui::common;
 
-pub fn render(frame: &mut Frame, _app: &App, area: Rect) {
+pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(12), // Banner
-            Constraint::Min(5),     // Content
+            Constraint::Length(3),  // Title
+            Constraint::Min(5),     // Threats + details
+            Constraint::Length(5),  // Status/actions
         ])
         .split(area);
 
     // Render banner
     common::render_banner(frame, chunks[0]);
 
-    // Content
-    let widget = Paragraph::new("Security Scan - Coming Soon\n\nPress Esc to go back")
-        .style(Style::default().fg(Color::White).add_modifier(Modifier::BOLD))
+    // Title, with the signature database in use
+    let title = match &app.security_database {
+        Some(database) => format!("Security Scan - signatures v{}", database.version),
+        None => "Security Scan".to_string(),
+    };
+    let title_widget = Paragraph::new(title)
+        .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
         .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL).title("Security Scan"));
-    frame.render_widget(widget, chunks[1]);
+        .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(title_widget, chunks[1]);
+
+    let content = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(chunks[2]);
+
+    if app.security_threats.is_empty() {
+        let (message, style) = if app.security_scanning {
+            (
+                format!("{} Scanning for known adware and malware...", app.get_spinner()),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            )
+        } else {
+            (
+                "No known threats found.".to_string(),
+                Style::default().fg(Color::Green),
+            )
+        };
+        let empty = Paragraph::new(message)
+            .style(style)
+            .alignment(Alignment::Center)
+            .block(Block::default().borders(Borders::ALL).title("Threats"));
+        frame.render_widget(empty, chunks[2]);
+    } else {
+        render_threats(frame, app, content[0]);
+        render_details(frame, app, content[1]);
+    }
+
+    render_status(frame, app, chunks[3]);
+
+    if app.security_confirm_quarantine {
+        render_quarantine_confirmation(frame, app, area);
+    } else if let Some(draft) = &app.security_suppress {
+        render_suppress_prompt(frame, app, draft, area);
+    } else if let Some(entries) = &app.security_allowlist {
+        render_allowlist(frame, app, entries, area);
+    } else if app.scan_errors_open {
+        common::render_scan_errors(frame, app, area);
+    }
+}
+
+fn severity_color(severity: ThreatSeverity) -> Color {
+    match severity {
+        ThreatSeverity::Critical => Color::Magenta,
+        ThreatSeverity::High => Color::Red,
+        ThreatSeverity::Medium => Color::Yellow,
+        ThreatSeverity::Low => Color::Blue,
+    }
+}
+
+/// Threats under a header per severity; they arrive most severe first
+fn render_threats(frame: &mut Frame, app: &App, area: Rect) {
+    let mut items = Vec::new();
+    let mut selected_row = None;
+    let mut current_severity = None;
+
+    for (index, threat) in app.security_threats.iter().enumerate() {
+        if current_severity != Some(threat.severity) {
+            current_severity = Some(threat.severity);
+            let count = app
+                .security_threats
+                .iter()
+                .filter(|t| t.severity == threat.severity)
+                .count();
+            items.push(ListItem::new(Line::from(Span::styled(
+                format!("● {} ({})", threat.severity.name(), count),
+                Style::default()
+                    .fg(severity_color(threat.severity))
+                    .add_modifier(Modifier::BOLD),
+            ))));
+        }
+
+        if index == app.security_selected_index {
+            selected_row = Some(items.len());
+        }
+        let highlight = if index == app.security_selected_index {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        items.push(ListItem::new(Line::from(vec![
+            Span::raw("  "),
+            Span::styled(
+                format!("{:<18}", threat.threat_type.name()),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::styled(threat.path.display().to_string(), highlight),
+        ])));
+    }
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Threats ({})", app.security_threats.len())),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::Rgb(30, 30, 30))
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let mut list_state = ListState::default();
+    list_state.select(selected_row);
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+/// Description, matched signature and references of the highlighted threat
+fn render_details(frame: &mut Frame, app: &App, area: Rect) {
+    let threat = match app.security_selected_threat() {
+        Some(threat) => threat,
+        None => return,
+    };
+    let signature = app
+        .security_database
+        .as_ref()
+        .and_then(|database| database.signature(&threat.signature_id));
+
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Gray));
+    let color = severity_color(threat.severity);
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            signature.map_or_else(|| threat.signature_id.clone(), |s| s.name.clone()),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            label("Severity:  "),
+            Span::styled(threat.severity.name(), Style::default().fg(color)),
+        ]),
+        Line::from(vec![label("Type:      "), Span::raw(threat.threat_type.name())]),
+        Line::from(vec![label("Path:      "), Span::raw(threat.path.display().to_string())]),
+        Line::from(vec![
+            label("Signature: "),
+            Span::styled(threat.signature_id.clone(), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                signature.map(|s| format!(" ({})", s.kind.name())).unwrap_or_default(),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]),
+        Line::from(vec![
+            label("Detected:  "),
+            Span::raw(threat.detected_at.format("%Y-%m-%d %H:%M").to_string()),
+        ]),
+        Line::from(""),
+    ];
+
+    // The signature's own text; the threat's description repeats its name
+    let description = signature
+        .map(|s| s.description.clone())
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| threat.description.clone());
+    lines.push(Line::from(Span::styled(description, Style::default().fg(Color::White))));
+
+    if let Some(remediation) = &threat.remediation {
+        lines.push(Line::from(""));
+        lines.push(Line::from(label("Remediation:")));
+        lines.push(Line::from(Span::styled(
+            format!("  {}", remediation),
+            Style::default().fg(Color::Green),
+        )));
+        if let Some(mode) = threat.fix_mode {
+            lines.push(Line::from(Span::styled(
+                format!("  [f] chmod {:04o}", mode),
+                Style::default().fg(Color::Cyan),
+            )));
+        }
+    }
+
+    if let Some(signature) = signature.filter(|s| !s.references.is_empty()) {
+        lines.push(Line::from(""));
+        lines.push(Line::from(label("References:")));
+        for reference in &signature.references {
+            lines.push(Line::from(Span::styled(
+                format!("  {}", reference),
+                Style::default().fg(Color::Blue),
+            )));
+        }
+    }
+
+    let details = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Details"));
+    frame.render_widget(details, area);
+}
+
+fn render_status(frame: &mut Frame, app: &App, area: Rect) {
+    let mut summary = Vec::new();
+    for severity in [
+        ThreatSeverity::Critical,
+        ThreatSeverity::High,
+        ThreatSeverity::Medium,
+        ThreatSeverity::Low,
+    ] {
+        let count = app.security_threats.iter().filter(|t| t.severity == severity).count();
+        if count > 0 {
+            summary.push(Span::styled(
+                format!("{} {}  ", count, severity.name()),
+                Style::default().fg(severity_color(severity)).add_modifier(Modifier::BOLD),
+            ));
+        }
+    }
+    // Paths the scan skipped, so the results above are incomplete
+    let skipped = common::scan_errors_spans(app);
+    if !skipped.is_empty() {
+        summary.push(Span::raw("│  "));
+        summary.extend(skipped);
+    }
+
+    let mut status_lines = Vec::new();
+    if !summary.is_empty() {
+        status_lines.push(Line::from(summary));
+    }
+    if let Some(msg) = &app.status_message {
+        if !app.security_scanning {
+            status_lines.push(Line::from(Span::styled(
+                msg.clone(),
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+    }
+    if let Some(err) = &app.error_message {
+        status_lines.push(Line::from(Span::styled(
+            err.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    status_lines.push(Line::from(vec![
+        Span::styled("[x] ", Style::default().fg(Color::Red)),
+        Span::raw("Quarantine  "),
+        Span::styled("[f] ", Style::default().fg(Color::Green)),
+        Span::raw("Fix permissions  "),
+        Span::styled("[o] ", Style::default().fg(Color::Cyan)),
+        Span::raw("Open  "),
+        Span::styled("[i] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Ignore  "),
+        Span::styled("[I] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Suppress  "),
+        Span::styled("[a] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Suppressions  "),
+        Span::styled("[r] ", Style::default().fg(Color::Cyan)),
+        Span::raw("Rescan"),
+    ]));
+
+    let status = Paragraph::new(status_lines)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(status, area);
+}
+
+fn render_quarantine_confirmation(frame: &mut Frame, app: &App, area: Rect) {
+    let threat = match app.security_selected_threat() {
+        Some(threat) => threat,
+        None => return,
+    };
+
+    let lines = vec![
+        Line::from(Span::styled(
+            "Move this item into the quarantine?",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("  {}", threat.path.display()),
+            Style::default().fg(Color::Gray),
+        )),
+        Line::from(Span::styled(
+            "  Its files lose their execute bits; the original path is recorded.",
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("[y/Enter] ", Style::default().fg(Color::Green)),
+            Span::raw("Confirm  "),
+            Span::styled("[Esc] ", Style::default().fg(Color::Red)),
+            Span::raw("Cancel"),
+        ]),
+    ];
+
+    let height = lines.len() as u16 + 2;
+    let popup_area = common::centered_rect(70, height, area);
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Red))
+            .title(" Quarantine ")
+            .title_style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+    );
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_suppress_prompt(frame: &mut Frame, app: &App, draft: &SuppressDraft, area: Rect) {
+    let threat = match app.security_selected_threat() {
+        Some(threat) => threat,
+        None => return,
+    };
+    let key = if draft.by_content {
+        "this file's contents (any signature; lapses when the file changes)".to_string()
+    } else {
+        format!("signature {}", threat.signature_id)
+    };
+
+    let lines = vec![
+        Line::from(Span::styled(
+            "Stop reporting this finding?",
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("  {}", threat.path.display()),
+            Style::default().fg(Color::Gray),
+        )),
+        Line::from(vec![
+            Span::styled("  Keyed by: ", Style::default().fg(Color::DarkGray)),
+            Span::raw(key),
+        ]),
+        Line::from(vec![
+            Span::styled("  Expires:  ", Style::default().fg(Color::DarkGray)),
+            Span::raw(SUPPRESS_EXPIRIES[draft.expiry].0),
+        ]),
+        Line::from(vec![
+            Span::styled("  Reason:   ", Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("{}_", draft.reason), Style::default().fg(Color::White)),
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("[Enter] ", Style::default().fg(Color::Green)),
+            Span::raw("Save  "),
+            Span::styled("[Tab] ", Style::default().fg(Color::Cyan)),
+            Span::raw("Expiry  "),
+            Span::styled("[↑↓] ", Style::default().fg(Color::Cyan)),
+            Span::raw("Signature/contents  "),
+            Span::styled("[Esc] ", Style::default().fg(Color::Red)),
+            Span::raw("Cancel"),
+        ]),
+    ];
+
+    let height = lines.len() as u16 + 2;
+    let popup_area = common::centered_rect(70, height, area);
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(" Suppress ")
+            .title_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+    );
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+/// Saved suppressions with what they're keyed on, why and until when
+fn render_allowlist(frame: &mut Frame, app: &App, entries: &[AllowlistEntry], area: Rect) {
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|entry| {
+            let (expiry, expiry_style) = match entry.expires {
+                _ if entry.is_expired() => ("expired".to_string(), Style::default().fg(Color::Red)),
+                Some(expires) => (
+                    format!("until {}", expires.format("%Y-%m-%d")),
+                    Style::default().fg(Color::Yellow),
+                ),
+                None => ("permanent".to_string(), Style::default().fg(Color::DarkGray)),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<17}", expiry), expiry_style),
+                Span::styled(entry.path.display().to_string(), Style::default().fg(Color::White)),
+                Span::styled(format!("  [{}]", entry.key()), Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    entry.reason.as_deref().map(|r| format!("  {}", r)).unwrap_or_default(),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let height = (entries.len() as u16 + 2).clamp(5, area.height.saturating_sub(4));
+    let popup_area = common::centered_rect(90, height, area);
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title(format!(
+                    " {} suppressions  [↑↓] Scroll  [d] Remove  [Esc] Close ",
+                    entries.len()
+                ))
+                .title_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        )
+        .highlight_style(Style::default().bg(Color::Rgb(30, 30, 30)).add_modifier(Modifier::BOLD));
+
+    let mut state = ListState::default();
+    state.select(Some(app.security_allowlist_index));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_stateful_widget(list, popup_area, &mut state);
 }
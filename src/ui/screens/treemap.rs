@@ -3,14 +3,19 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, BorderType, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, BorderType, Clear, List, ListItem, ListState, Paragraph},
     Frame,
 };
 
+use crate::app::state::TreeMapAction;
 use crate::app::App;
+use crate::scanner::content::{self, ContentBreakdown};
+use crate::scanner::inspect::ArchiveListing;
 use crate::ui::common;
+use crate::ui::widgets::image::HalfBlockImage;
 
-pub fn render(frame: &mut Frame, app: &App, area: Rect) {
+/// Returns the preview area an overlay image should be drawn into, if any
+pub fn render(frame: &mut Frame, app: &App, area: Rect) -> Option<Rect> {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -61,6 +66,8 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
         .block(Block::default().borders(Borders::ALL));
     frame.render_widget(title_widget, chunks[1]);
 
+    let mut image_area = None;
+
     // Items list
     if app.treemap_scanning {
         let message = format!("{} Scanning directory tree...", app.get_spinner());
@@ -72,7 +79,12 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
         let items_data = app.get_current_treemap_items();
 
         if items_data.is_empty() {
-            let empty = Paragraph::new("No items found in this directory")
+            let message = if app.search_is_active() {
+                "No matches in this directory (n/N jumps to matches elsewhere)"
+            } else {
+                "No items found in this directory"
+            };
+            let empty = Paragraph::new(message)
                 .alignment(Alignment::Center)
                 .block(Block::default().borders(Borders::ALL).title("Directories"));
             frame.render_widget(empty, content_chunks[0]);
@@ -92,9 +104,14 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
                     };
 
                     let icon = if item.is_file { "📄" } else { "📁" };
+                    let checkbox = if item.selected { "[✓]" } else { "[ ]" };
                     let percentage = item.percentage_of(total_size);
 
                     let line = Line::from(vec![
+                        Span::styled(
+                            format!("{} ", checkbox),
+                            Style::default().fg(if item.selected { Color::Green } else { Color::Gray }),
+                        ),
                         Span::styled(
                             format!("{} ", icon),
                             Style::default().fg(Color::Cyan),
@@ -138,7 +155,7 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
 
             // Render preview panel if enabled
             if app.treemap_show_preview && content_chunks.len() > 1 {
-                render_preview(frame, app, content_chunks[1]);
+                image_area = render_preview(frame, app, content_chunks[1]);
             }
         }
     }
@@ -150,29 +167,60 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
         "OFF"
     };
 
+    let mut summary = vec![
+        Span::styled("Total items: ", Style::default().fg(Color::White)),
+        Span::styled(
+            format!("{}", app.get_current_treemap_items().len()),
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("  │  "),
+        Span::styled("Preview: ", Style::default().fg(Color::White)),
+        Span::styled(
+            preview_status,
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ),
+    ];
+
+    // Paths the scan skipped, so the sizes above are lower bounds
+    let skipped = common::scan_errors_spans(app);
+    if !skipped.is_empty() {
+        summary.push(Span::raw("  │  "));
+        summary.extend(skipped);
+    }
+
+    // Deletion results and failures from the last action
+    if let Some(err) = &app.error_message {
+        summary.push(Span::raw("  │  "));
+        summary.push(Span::styled(err.clone(), Style::default().fg(Color::Red)));
+    } else if let Some(msg) = &app.status_message {
+        summary.push(Span::raw("  │  "));
+        summary.push(Span::styled(msg.clone(), Style::default().fg(Color::Yellow)));
+    }
+
+    // The search prompt takes over the summary line while active
+    let first_line = common::search_line(app).unwrap_or_else(|| Line::from(summary));
+
     let status_lines = vec![
+        first_line,
         Line::from(vec![
-            Span::styled("Total items: ", Style::default().fg(Color::White)),
-            Span::styled(
-                format!("{}", app.get_current_treemap_items().len()),
-                Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
-            ),
-            Span::raw("  │  "),
-            Span::styled("Preview: ", Style::default().fg(Color::White)),
-            Span::styled(
-                preview_status,
-                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
-            ),
-        ]),
-        Line::from(vec![
-            Span::styled("[PgUp/PgDn] ", Style::default().fg(Color::Yellow)),
-            Span::raw("Fast  "),
             Span::styled("[Enter] ", Style::default().fg(Color::Green)),
             Span::raw("Open  "),
             Span::styled("[o] ", Style::default().fg(Color::Green)),
             Span::raw("File  "),
             Span::styled("[p] ", Style::default().fg(Color::Cyan)),
             Span::raw("Preview  "),
+            Span::styled("[b] ", Style::default().fg(Color::Cyan)),
+            Span::raw("Breakdown  "),
+            Span::styled("[/] ", Style::default().fg(Color::Cyan)),
+            Span::raw("Search  "),
+            Span::styled("[Space] ", Style::default().fg(Color::Cyan)),
+            Span::raw("Select  "),
+            Span::styled("[d] ", Style::default().fg(Color::Red)),
+            Span::raw("Delete  "),
+            Span::styled("[t] ", Style::default().fg(Color::Red)),
+            Span::raw("Trash  "),
+            Span::styled("[m] ", Style::default().fg(Color::Yellow)),
+            Span::raw("Move  "),
             Span::styled("[g] ", Style::default().fg(Color::Yellow)),
             Span::raw("Home"),
         ]),
@@ -183,91 +231,222 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
         .block(Block::default().borders(Borders::ALL));
 
     frame.render_widget(status, chunks[3]);
+
+    if let Some(action) = app.treemap_pending_action {
+        render_action_confirmation(frame, app, action, area);
+        // The popup covers the preview, so don't draw an image over it
+        return None;
+    }
+    if app.scan_errors_open {
+        common::render_scan_errors(frame, app, area);
+        return None;
+    }
+
+    image_area
 }
 
-fn render_preview(frame: &mut Frame, app: &App, area: Rect) {
+fn render_action_confirmation(frame: &mut Frame, app: &App, action: TreeMapAction, area: Rect) {
+    const MAX_LISTED: usize = 5;
+
+    let targets = &app.treemap_action_targets;
+    let total = format_size(app.get_treemap_selected_size(), BINARY);
+    let (question, color) = match action {
+        TreeMapAction::Delete => (
+            format!("Permanently delete {} items ({})?", targets.len(), total),
+            Color::Red,
+        ),
+        TreeMapAction::Trash => (
+            format!("Move {} items ({}) to the trash?", targets.len(), total),
+            Color::Yellow,
+        ),
+        TreeMapAction::Move => (
+            format!("Move {} items ({}) to:", targets.len(), total),
+            Color::Cyan,
+        ),
+    };
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            question,
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        )),
+    ];
+
+    if action == TreeMapAction::Move {
+        lines.push(Line::from(vec![
+            Span::styled(
+                app.treemap_move_destination.clone(),
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("█", Style::default().fg(Color::Cyan)),
+        ]));
+    }
+
+    lines.push(Line::from(""));
+    for path in targets.iter().take(MAX_LISTED) {
+        lines.push(Line::from(Span::styled(
+            format!("  {}", path.display()),
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    if targets.len() > MAX_LISTED {
+        lines.push(Line::from(Span::styled(
+            format!("  ... and {} more", targets.len() - MAX_LISTED),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    lines.push(Line::from(""));
+    let confirm_keys = if action == TreeMapAction::Move { "[Enter] " } else { "[y/Enter] " };
+    lines.push(Line::from(vec![
+        Span::styled(confirm_keys, Style::default().fg(Color::Green)),
+        Span::raw("Confirm  "),
+        Span::styled("[Esc] ", Style::default().fg(Color::Red)),
+        Span::raw("Cancel"),
+    ]));
+
+    let height = lines.len() as u16 + 2;
+    let popup_area = common::centered_rect(70, height, area);
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(color))
+            .title(format!(" {} ", action.name()))
+            .title_style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
+    );
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+/// Draws the preview panel; returns the area reserved for an overlay image
+fn render_preview(frame: &mut Frame, app: &App, area: Rect) -> Option<Rect> {
+    if let Some((path, breakdown)) = app.treemap_visible_breakdown() {
+        render_breakdown(frame, path, breakdown, area);
+        return None;
+    }
+    if app.treemap_breakdown_computing() {
+        let computing = Paragraph::new(format!("{} Computing content breakdown...", app.get_spinner()))
+            .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
+        frame.render_widget(computing, area);
+        return None;
+    }
+
     let selected = app.get_selected_treemap_item();
 
     if let Some(item) = selected {
         if item.is_file {
             let path = &item.path;
-            let extension = path
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("")
-                .to_lowercase();
-
-            // Detect file type
-            let preview_content = if is_text_file(&extension) {
-                // Read text file (including .srt subtitle files)
-                read_text_preview(path, 50)
-            } else if is_image_file(&extension) {
-                // Try to display image inline
-                if let Some(img_preview) = render_image_preview(path, area) {
-                    let preview = Paragraph::new(img_preview)
-                        .block(
-                            Block::default()
-                                .borders(Borders::ALL)
-                                .border_type(BorderType::Rounded)
-                                .title(" Image Preview ")
-                                .title_style(
-                                    Style::default()
-                                        .fg(Color::Cyan)
-                                        .add_modifier(Modifier::BOLD),
-                                ),
-                        );
-                    frame.render_widget(preview, area);
-                    return;
-                } else {
-                    format!(
-                        "Image File\n\nName: {}\nSize: {}\nType: {}\n\nPress 'o' to open with system viewer",
-                        item.name,
-                        format_size(item.size, BINARY),
-                        extension.to_uppercase()
+
+            let block = |title: &'static str| {
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .title(title)
+                    .title_style(
+                        Style::default()
+                            .fg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD),
                     )
+            };
+
+            let mut lines = vec![
+                info_line("Name", &item.name),
+                info_line("Size", &format_size(item.size, BINARY)),
+            ];
+
+            // An imported tree's files may not exist here; go by name only
+            if app.treemap_imported {
+                let root = app.treemap_root.as_ref().map_or(path, |r| &r.path);
+                let class = content::classify_name(path, root);
+                lines.push(info_line("Kind", class.name()));
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    "Imported tree: file contents aren't available",
+                    Style::default().fg(Color::DarkGray),
+                )));
+                frame.render_widget(Paragraph::new(lines).block(block(" Preview ")), area);
+                return None;
+            }
+
+            // File type and contents come from the background inspection
+            let inspection = match app.treemap_inspection.as_ref().filter(|i| &i.path == path) {
+                Some(inspection) => inspection,
+                None => {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(format!("{} Inspecting...", app.get_spinner())));
+                    frame.render_widget(Paragraph::new(lines).block(block(" Preview ")), area);
+                    return None;
                 }
-            } else if is_video_file(&extension) {
-                format!(
-                    "Video File\n\nName: {}\nSize: {}\nType: {}\n\nPress 'o' to play with system player",
-                    item.name,
-                    format_size(item.size, BINARY),
-                    extension.to_uppercase()
-                )
-            } else if is_audio_file(&extension) {
-                format!(
-                    "Audio File\n\nName: {}\nSize: {}\nType: {}\n\nPress 'o' to play with system player",
-                    item.name,
-                    format_size(item.size, BINARY),
-                    extension.to_uppercase()
-                )
-            } else {
-                format!(
-                    "File Information\n\nName: {}\nSize: {}\nType: {}",
-                    item.name,
-                    format_size(item.size, BINARY),
-                    extension.to_uppercase()
-                )
             };
 
-            let preview = Paragraph::new(preview_content)
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .border_type(BorderType::Rounded)
-                        .title(" Preview ")
-                        .title_style(
-                            Style::default()
-                                .fg(Color::Cyan)
-                                .add_modifier(Modifier::BOLD),
-                        ),
-                )
-                .wrap(ratatui::widgets::Wrap { trim: true });
+            if let Some(text) = &inspection.text {
+                // Text files (including .srt subtitle files)
+                let preview = Paragraph::new(text.as_str())
+                    .block(block(" Preview "))
+                    .wrap(ratatui::widgets::Wrap { trim: true });
+                frame.render_widget(preview, area);
+                return None;
+            }
+            lines.push(info_line("Kind", inspection.class.name()));
 
-            frame.render_widget(preview, area);
+            if let Some(description) = &inspection.description {
+                lines.push(info_line("Type", description));
+            }
+            for (label, value) in &inspection.metadata {
+                lines.push(info_line(label, value));
+            }
+            if let Some(error) = &inspection.error {
+                lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+            }
+
+            if let Some(listing) = &inspection.archive {
+                lines.extend(archive_lines(listing));
+            }
+
+            if !inspection.hex_dump.is_empty() {
+                lines.push(Line::from(""));
+                lines.extend(
+                    inspection
+                        .hex_dump
+                        .iter()
+                        .map(|l| Line::from(Span::styled(l.clone(), Style::default().fg(Color::Gray)))),
+                );
+            }
+
+            if let Some(thumbnail) = &inspection.thumbnail {
+                let outer = block(" Image Preview ");
+                let inner = outer.inner(area);
+                frame.render_widget(outer, area);
+
+                let info_height = (lines.len() as u16).min(inner.height / 2);
+                let parts = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(1), Constraint::Length(info_height)])
+                    .split(inner);
+                frame.render_widget(Paragraph::new(lines), parts[1]);
+
+                // Overlay protocols draw after the frame; keep the cells blank
+                if app.graphics_protocol.is_overlay() {
+                    return Some(parts[0]);
+                }
+                frame.render_widget(HalfBlockImage::new(thumbnail), parts[0]);
+                return None;
+            }
+
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "Press 'o' to open with the default application",
+                Style::default().fg(Color::DarkGray),
+            )));
+            frame.render_widget(Paragraph::new(lines).block(block(" Preview ")), area);
+            None
         } else {
             // Directory info
             let dir_info = format!(
-                "Directory\n\nName: {}\nTotal Size: {}\nItems: {}",
+                "Directory\n\nName: {}\nTotal Size: {}\nItems: {}\n\nPress 'b' for a content breakdown",
                 item.name,
                 format_size(item.size, BINARY),
                 item.children.len()
@@ -287,6 +466,7 @@ fn render_preview(frame: &mut Frame, app: &App, area: Rect) {
                 );
 
             frame.render_widget(preview, area);
+            None
         }
     } else {
         let empty = Paragraph::new("No file selected")
@@ -299,77 +479,123 @@ fn render_preview(frame: &mut Frame, app: &App, area: Rect) {
             );
 
         frame.render_widget(empty, area);
+        None
     }
 }
 
-fn is_text_file(ext: &str) -> bool {
-    matches!(
-        ext,
-        "txt" | "md" | "rs" | "toml" | "json" | "xml" | "yaml" | "yml" | "sh" | "py" | "js" | "ts"
-            | "html" | "css" | "c" | "cpp" | "h" | "hpp" | "java" | "go" | "rb" | "php" | "swift"
-            | "kt" | "log" | "csv" | "ini" | "conf" | "cfg" | "srt" | "vtt" | "ass" | "sub"
-    )
+fn info_line(label: &str, value: &str) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("{}: ", label), Style::default().fg(Color::Cyan)),
+        Span::raw(value.to_string()),
+    ])
 }
 
-fn is_image_file(ext: &str) -> bool {
-    matches!(
-        ext,
-        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "svg" | "webp" | "ico" | "tiff" | "tif"
-    )
-}
+fn archive_lines(listing: &ArchiveListing) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            format!(
+                "{} archive • {}{} entries • {} uncompressed",
+                listing.format,
+                listing.entries.len(),
+                if listing.truncated { "+" } else { "" },
+                format_size(listing.uncompressed_size, BINARY)
+            ),
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        )),
+    ];
 
-fn is_video_file(ext: &str) -> bool {
-    matches!(
-        ext,
-        "mp4" | "avi" | "mkv" | "mov" | "wmv" | "flv" | "webm" | "m4v" | "mpg" | "mpeg"
-    )
+    lines.extend(listing.entries.iter().map(|entry| {
+        let size = if entry.is_dir {
+            String::new()
+        } else {
+            format_size(entry.size, BINARY)
+        };
+        Line::from(vec![
+            Span::styled(format!("{:>10}  ", size), Style::default().fg(Color::Yellow)),
+            Span::raw(entry.name.clone()),
+        ])
+    }));
+    lines
 }
 
-fn is_audio_file(ext: &str) -> bool {
-    matches!(
-        ext,
-        "mp3" | "wav" | "flac" | "aac" | "ogg" | "m4a" | "wma" | "opus" | "alac" | "ape"
-    )
-}
+/// Bytes per content class and top extensions for a directory subtree
+fn render_breakdown(frame: &mut Frame, path: &std::path::Path, breakdown: &ContentBreakdown, area: Rect) {
+    const BAR_WIDTH: usize = 12;
+    const TOP_EXTENSIONS: usize = 8;
 
-fn read_text_preview(path: &std::path::Path, max_lines: usize) -> String {
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
-
-    match File::open(path) {
-        Ok(file) => {
-            let reader = BufReader::new(file);
-            let lines: Vec<String> = reader
-                .lines()
-                .take(max_lines)
-                .filter_map(|l| l.ok())
-                .collect();
+    let total = breakdown.total.bytes.max(1);
+    let bar = |bytes: u64| {
+        let filled = ((bytes as f64 / total as f64) * BAR_WIDTH as f64).round() as usize;
+        format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled.min(BAR_WIDTH)))
+    };
 
-            if lines.is_empty() {
-                "(Empty file)".to_string()
-            } else {
-                lines.join("\n")
-            }
-        }
-        Err(_) => "(Unable to read file)".to_string(),
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("/");
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            name.to_string(),
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!(
+            "{} in {} files",
+            format_size(breakdown.total.bytes, BINARY),
+            breakdown.total.count
+        )),
+        Line::from(""),
+    ];
+
+    for (class, totals) in &breakdown.by_class {
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:<22}", class.name()), Style::default().fg(Color::Cyan)),
+            Span::styled(bar(totals.bytes), Style::default().fg(Color::Yellow)),
+            Span::raw(format!(
+                " {:>10} {:>5.1}%",
+                format_size(totals.bytes, BINARY),
+                totals.bytes as f64 / total as f64 * 100.0
+            )),
+        ]));
     }
-}
 
-fn render_image_preview(path: &std::path::Path, _area: Rect) -> Option<String> {
-    use image::io::Reader;
-
-    // Try to load the image to get its dimensions
-    let img = Reader::open(path).ok()?.decode().ok()?;
-
-    // Return image information
-    Some(format!(
-        "{}x{} {} image\n\nPress 'o' to open in system viewer",
-        img.width(),
-        img.height(),
-        path.extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("unknown")
-            .to_uppercase()
-    ))
-}
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Top extensions",
+        Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+    )));
+    for (ext, totals) in breakdown.by_extension.iter().take(TOP_EXTENSIONS) {
+        lines.push(Line::from(format!(
+            "  .{:<12} {:>10}  {} files",
+            ext,
+            format_size(totals.bytes, BINARY),
+            totals.count
+        )));
+    }
 
+    if breakdown.unscanned_bytes > 0 {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{} below the scan depth not classified",
+                format_size(breakdown.unscanned_bytes, BINARY)
+            ),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    let widget = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(" Breakdown ")
+            .title_style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+    );
+
+    frame.render_widget(widget, area);
+}
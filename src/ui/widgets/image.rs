@@ -0,0 +1,142 @@
+use anyhow::Result;
+use image::{imageops::FilterType, DynamicImage, GenericImageView};
+use ratatui::{buffer::Buffer, layout::Rect, style::Color, widgets::Widget};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// How images are drawn in the preview panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    /// Kitty graphics protocol: pixels drawn as an overlay above the text grid
+    Kitty,
+    /// iTerm2 inline images
+    Iterm,
+    /// Sixel (only with the `sixel` cargo feature, which needs libsixel)
+    #[cfg_attr(not(feature = "sixel"), allow(dead_code))]
+    Sixel,
+    /// Unicode half blocks with truecolor; works in any modern terminal
+    HalfBlock,
+}
+
+impl GraphicsProtocol {
+    pub fn detect() -> Self {
+        if viuer::get_kitty_support() != viuer::KittySupport::None {
+            return Self::Kitty;
+        }
+        if viuer::is_iterm_supported() {
+            return Self::Iterm;
+        }
+        #[cfg(feature = "sixel")]
+        if viuer::is_sixel_supported() {
+            return Self::Sixel;
+        }
+        Self::HalfBlock
+    }
+
+    /// Protocols that draw outside of ratatui's buffer
+    pub fn is_overlay(&self) -> bool {
+        *self != Self::HalfBlock
+    }
+}
+
+/// Draws an image into the buffer using '▀' cells: the foreground colour is
+/// the upper pixel and the background colour the lower one
+pub struct HalfBlockImage<'a> {
+    image: &'a DynamicImage,
+}
+
+impl<'a> HalfBlockImage<'a> {
+    pub fn new(image: &'a DynamicImage) -> Self {
+        Self { image }
+    }
+}
+
+impl Widget for HalfBlockImage<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+
+        // Each cell is two pixels tall, so pixels come out roughly square
+        let fitted = self
+            .image
+            .resize(area.width as u32, area.height as u32 * 2, FilterType::Triangle);
+        let (width, height) = fitted.dimensions();
+        let x_offset = (area.width as u32 - width) / 2;
+
+        for y in (0..height).step_by(2) {
+            for x in 0..width {
+                let top = fitted.get_pixel(x, y);
+                let bottom = if y + 1 < height { fitted.get_pixel(x, y + 1) } else { top };
+
+                let cell = buf.get_mut(area.x + (x + x_offset) as u16, area.y + (y / 2) as u16);
+                cell.set_char('▀')
+                    .set_fg(Color::Rgb(top[0], top[1], top[2]))
+                    .set_bg(Color::Rgb(bottom[0], bottom[1], bottom[2]));
+            }
+        }
+    }
+}
+
+/// Tracks the image drawn by an overlay protocol so it's only re-sent when
+/// the file or the preview area changes, and erased when it goes away
+pub struct GraphicsOverlay {
+    protocol: GraphicsProtocol,
+    shown: Option<(PathBuf, Rect)>,
+}
+
+impl GraphicsOverlay {
+    pub fn new(protocol: GraphicsProtocol) -> Self {
+        Self {
+            protocol,
+            shown: None,
+        }
+    }
+
+    /// Whether the overlay already shows this image (or nothing, for None)
+    pub fn is_current(&self, wanted: Option<(&Path, Rect)>) -> bool {
+        if !self.protocol.is_overlay() {
+            return true;
+        }
+        self.shown.as_ref().map(|(path, area)| (path.as_path(), *area)) == wanted
+    }
+
+    /// Remove the current image. Returns true when the protocol wrote into
+    /// cells ratatui believes are unchanged, so the caller must repaint.
+    pub fn erase(&mut self) -> Result<bool> {
+        if self.shown.take().is_none() {
+            return Ok(false);
+        }
+
+        match self.protocol {
+            GraphicsProtocol::Kitty => {
+                // Delete all placements; the text underneath is untouched
+                let mut stdout = std::io::stdout();
+                write!(stdout, "\x1b_Ga=d\x1b\\")?;
+                stdout.flush()?;
+                Ok(false)
+            }
+            GraphicsProtocol::Iterm | GraphicsProtocol::Sixel => Ok(true),
+            GraphicsProtocol::HalfBlock => Ok(false),
+        }
+    }
+
+    pub fn show(&mut self, path: &Path, image: &DynamicImage, area: Rect) {
+        let config = viuer::Config {
+            x: area.x,
+            y: area.y as i16,
+            width: Some(area.width as u32),
+            height: Some(area.height as u32),
+            absolute_offset: true,
+            restore_cursor: true,
+            use_kitty: self.protocol == GraphicsProtocol::Kitty,
+            use_iterm: self.protocol == GraphicsProtocol::Iterm,
+            ..Default::default()
+        };
+
+        // A failed print just leaves the blank panel; nothing else to do
+        if viuer::print(image, &config).is_ok() {
+            self.shown = Some((path.to_path_buf(), area));
+        }
+    }
+}
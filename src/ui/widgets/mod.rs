@@ -1,2 +1,2 @@
 // Widgets module - reusable UI components
-// Will be implemented in future phases
+pub mod image;